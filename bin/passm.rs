@@ -1,58 +1,571 @@
-use core::app::App;
-use std::{fs, process};
+use passm_core::{
+    app::App,
+    audit::audit_vault,
+    backup::{restore_backup, verify_backup, write_backup},
+    breach::BreachList,
+    copy::copy_to_clipboard,
+    exporter::{export_entries, write_private, PlainExport, PlainFormat},
+    import::{
+        import_entries, keepass::read_kdbx, pass::read_password_store, read_export, ConflictPolicy,
+    },
+    index::load_index,
+    keys::{create_signed_key, read_signed_key},
+    otp::{import_otp, next_code},
+    paper::{read_paper, to_paper, to_qr_codes, PaperLine, PaperReader},
+};
+use std::{env, fs, path::PathBuf};
 
-use anyhow::Result;
-use config::config::Configuration;
-use crypto::{pgp::KeyType, signer::Signer};
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use config::{
+    config::Configuration,
+    paths::{Paths, HOME_ENV},
+};
+use crypto::{
+    exporter::{export_private_key_bytes, import_private_key_bytes},
+    gpg::{GpgKey, Recipient},
+    kdbx::Kdbx,
+    shamir::{combine, split, Share},
+    signer::Signer,
+};
+use shared::console::{PassphraseSource, Secrets};
 use simple_logger::SimpleLogger;
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Parser)]
+#[command(version, about = "Self hosted password management simplified")]
+struct Cli {
+    /// Directory holding configuration, keys and passwords.
+    /// Defaults to XDG base directories.
+    #[arg(long, env = HOME_ENV)]
+    home: Option<PathBuf>,
+
+    /// Downloaded Pwned Passwords SHA-1 or NTLM list ordered by hash.
+    /// Passwords are flagged when found in it, nothing is sent over the network.
+    #[arg(long, env = "PASSM_BREACH_LIST", value_name = "PATH")]
+    breach_list: Option<PathBuf>,
+
+    /// Ask for the passphrase with `pinentry` or `pinentry:PROGRAM`, which works without a
+    /// terminal, or read it from fd:N, file:PATH or env:NAME. The last three are meant for
+    /// automation, each of them leaves the passphrase more exposed than typing it.
+    /// Other passwords, like the one of a backup, are still typed, in the pinentry dialog
+    /// when that is the source.
+    #[arg(long, env = "PASSM_PASSPHRASE_FROM", value_name = "SOURCE")]
+    passphrase_from: Option<PassphraseSource>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check the passwords of the default namespace for weak, reused, breached and old ones
+    Audit,
+    /// Copy the current two-factor code of an entry
+    Otp {
+        /// Name of the entry
+        name: String,
+        /// Print the code instead of copying it
+        #[arg(long)]
+        print: bool,
+        /// Store an otpauth:// URI (as found in 2FA QR codes) or a base32 secret on the entry first
+        #[arg(long, value_name = "URI")]
+        import: Option<String>,
+    },
+    /// Write entries of the default namespace to a CSV or JSON file in plain text
+    Export {
+        /// File to write, created readable by its owner only
+        file: PathBuf,
+        /// csv or json, defaults to JSON for .json files and CSV otherwise
+        #[arg(long)]
+        format: Option<PlainFormat>,
+        /// Export only this entry, can be given several times
+        #[arg(long = "entry", value_name = "NAME")]
+        entries: Vec<String>,
+        /// Encrypt the export to this OpenPGP public key, as written by `gpg --export --armor`
+        #[arg(long, value_name = "PUBLIC_KEY")]
+        armor: Option<PathBuf>,
+    },
+    /// Back up a whole namespace into one password protected file, or restore it
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+    /// Print the encrypted key export of the default namespace on paper, or type it back in
+    Paper {
+        #[command(subcommand)]
+        action: PaperAction,
+    },
+    /// Split the encrypted key export of the default namespace into shares, or combine them
+    Shares {
+        #[command(subcommand)]
+        action: SharesAction,
+    },
+    /// Import entries from another password manager into the default namespace
+    Import {
+        /// Only report what would be imported
+        #[arg(long)]
+        dry_run: bool,
+        /// What to do with entries whose name is taken: skip, overwrite or rename
+        #[arg(long, value_name = "POLICY", default_value = "skip")]
+        on_conflict: ConflictPolicy,
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+}
+
+#[derive(Subcommand)]
+enum BackupAction {
+    /// Seal the key, configuration and entries of the default namespace into FILE
+    Create { file: PathBuf },
+    /// Check that FILE opens with the backup password, without restoring it
+    Verify { file: PathBuf },
+    /// Restore FILE as a new namespace, on a fresh machine it becomes the default
+    Restore {
+        file: PathBuf,
+        /// Name of the restored namespace, defaults to the backed up one
+        #[arg(long, value_name = "NAME")]
+        namespace: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PaperAction {
+    /// Render the key, encrypted with an export password, as lines to print
+    Print {
+        /// Also show the lines as a sequence of QR codes
+        #[arg(long)]
+        qr: bool,
+        /// Write the document to FILE instead of printing it
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Read the printed lines back and write the encrypted key export to FILE
+    Restore {
+        file: PathBuf,
+        /// Read the lines from a text file instead of typing them
+        #[arg(long, value_name = "PATH")]
+        from: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SharesAction {
+    /// Write COUNT shares into DIR, any THRESHOLD of them recover the key export
+    Split {
+        /// Directory the share files are written to
+        dir: PathBuf,
+        #[arg(long, value_name = "THRESHOLD")]
+        threshold: u8,
+        #[arg(long, value_name = "COUNT")]
+        count: u8,
+    },
+    /// Rebuild the encrypted key export into FILE from share files
+    Combine {
+        file: PathBuf,
+        /// Files holding one or more shares each
+        #[arg(required = true)]
+        shares: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportSource {
+    /// A pass password store, entries keep their folders as `folder/name`
+    Pass {
+        /// Secret key the store is encrypted for, from `gpg --export-secret-keys`
+        #[arg(long, value_name = "PATH")]
+        key: PathBuf,
+        /// Store to read, defaults to ~/.password-store
+        #[arg(long, env = "PASSWORD_STORE_DIR", value_name = "PATH")]
+        store: Option<PathBuf>,
+    },
+    /// A KeePass KDBX 3.1 or 4 database, groups become folders
+    Keepass {
+        /// Database file
+        file: PathBuf,
+        /// Key file the database is locked with, next to its master password
+        #[arg(long, value_name = "PATH")]
+        key_file: Option<PathBuf>,
+    },
+    /// An unencrypted Bitwarden JSON, 1Password 1PUX or CSV, LastPass, Chrome or
+    /// Firefox CSV export, the format is detected from the file
+    File {
+        /// Export file
+        file: PathBuf,
+    },
+}
+
+#[global_allocator]
+static ALLOCATOR: shared::secret::WipingAllocator = shared::secret::WipingAllocator;
+
+fn main() -> Result<()> {
+    shared::secret::disable_core_dumps();
+    let mut cli = Cli::parse();
+    // Read before the runtime starts its threads, an environment variable is removed once read
+    let source = cli
+        .passphrase_from
+        .take()
+        .unwrap_or(PassphraseSource::Prompt);
+    let secrets = Secrets::new(source)?;
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(cli, secrets))
+}
+
+async fn run(cli: Cli, secrets: Secrets) -> Result<()> {
     SimpleLogger::new()
-        .with_level(log::LevelFilter::Debug)
+        .with_level(log::LevelFilter::Warn)
         .init()
         .unwrap();
-    let namespace_configuration = Configuration::init().unwrap();
-
-    // Init signed secret key
-    let ssk = match fs::metadata(namespace_configuration.private_key_path.clone()) {
-        Ok(_) => {
-            let file =
-                fs::read_to_string(namespace_configuration.private_key_path.clone()).unwrap();
-            Signer::parse_signed_secret_from_string(file)
-                .expect("failed to parse key from the file")
+    let paths = Paths::resolve(cli.home)?;
+    let mut breach_list = cli
+        .breach_list
+        .as_deref()
+        .map(BreachList::open)
+        .transpose()?;
+    if Configuration::needs_legacy_migration(&paths) {
+        let question = format!(
+            "Found a vault at {}. Move it to {}?",
+            Paths::legacy_base_path().display(),
+            paths.data_dir.display()
+        );
+        if shared::console::confirm(&question)? {
+            Configuration::migrate_legacy(&paths)?;
+            println!("Vault moved");
         }
+    }
+    // Backups work without unlocking a key, restoring one may be the first thing run
+    if let Some(Command::Backup { action }) = cli.command {
+        return run_backup(&paths, &secrets, action).await;
+    }
+    if let Some(Command::Paper {
+        action: PaperAction::Restore { file, from },
+    }) = cli.command
+    {
+        return restore_paper(&secrets, &file, from.as_deref());
+    }
+    if let Some(Command::Shares {
+        action: SharesAction::Combine { file, shares },
+    }) = cli.command
+    {
+        return combine_shares(&secrets, &file, &shares);
+    }
+    let namespace_configuration = Configuration::init(&paths)?;
+    let private_key_path = PathBuf::from(&namespace_configuration.private_key_path);
+
+    // Init signed secret key, a new one is unlocked with the passphrase just set
+    let (ssk, passphrase) = match fs::metadata(&private_key_path) {
+        Ok(_) => (read_signed_key(&private_key_path).await?, None),
         Err(_) => {
-            let passphrase = shared::console::read_passphrase(true).unwrap();
+            let passphrase = secrets.new_passphrase()?;
             println!("Starting key pair generation");
-            let sk = Signer::generate_key(KeyType::Rsa(2048), Some(passphrase.clone()));
+            let signed =
+                create_signed_key(&private_key_path, passphrase.expose().to_string()).await?;
             println!("New key pair has been created!");
-
-            let signed = Signer::sign_key(sk, Some(passphrase));
-            fs::write(
-                namespace_configuration.private_key_path.clone(),
-                signed.to_armored_string(None).unwrap(),
-            )
-            .unwrap();
-            signed
+            (signed, Some(passphrase))
         }
     };
 
-    let passphrase = shared::console::read_passphrase(false).unwrap();
-    Signer::verify_key_passphrase(&ssk, Some(passphrase.clone())).unwrap_or_else(|_| {
-        println!("Wrong passphrase");
-        process::exit(1);
-    });
+    let passphrase = match passphrase {
+        Some(passphrase) => passphrase,
+        None => secrets.unlock_with_retries(|passphrase| {
+            Signer::verify_key_passphrase(&ssk, Some(passphrase.expose().to_string()))
+        })?,
+    };
     let signer = Signer::new(ssk, Some(passphrase));
 
-    let mut export_pgp_secret_path = namespace_configuration.private_key_path.clone();
-    export_pgp_secret_path.push_str(".aes");
-    let mut app = App::new(
-        signer,
-        namespace_configuration.passwords_dir,
-        export_pgp_secret_path.into(),
-    );
-    app.run().await;
+    match cli.command {
+        None => {
+            let mut app = App::new(signer, paths, namespace_configuration);
+            if let Some(breach_list) = breach_list {
+                app = app.with_breach_list(breach_list);
+            }
+            app.run().await;
+        }
+        Some(Command::Audit) => {
+            let (index, _) = load_index(&signer, &namespace_configuration.passwords_dir).await?;
+            let report = audit_vault(
+                &signer,
+                &namespace_configuration.passwords_dir,
+                &index,
+                breach_list.as_mut(),
+            )
+            .await?;
+            println!("{}", report);
+        }
+        Some(Command::Export {
+            file,
+            format,
+            entries,
+            armor,
+        }) => {
+            let recipient = armor
+                .map(|path| Recipient::parse(&fs::read(path)?))
+                .transpose()?;
+            if recipient.is_none() {
+                eprintln!(
+                    "WARNING: entries are written UNENCRYPTED to {}.",
+                    file.display()
+                );
+                eprintln!("WARNING: anyone who can read the file gets every password in it, delete it once handed over.");
+            }
+            let passphrase = secrets.passphrase("Enter the passphrase again to export secrets:")?;
+            let (index, _) = load_index(&signer, &namespace_configuration.passwords_dir).await?;
+            let export = PlainExport {
+                path: &file,
+                format: format.unwrap_or_else(|| PlainFormat::from_path(&file)),
+                recipient: recipient.as_ref(),
+            };
+            let names = (!entries.is_empty()).then_some(entries.as_slice());
+            let count = export_entries(
+                &signer,
+                &namespace_configuration.passwords_dir,
+                &index,
+                names,
+                passphrase.expose(),
+                &export,
+            )
+            .await?;
+            match export.recipient {
+                Some(_) => println!("Exported {} entries encrypted to {}", count, file.display()),
+                None => println!(
+                    "Exported {} entries in plain text to {}",
+                    count,
+                    file.display()
+                ),
+            }
+        }
+        Some(Command::Otp {
+            name,
+            print,
+            import,
+        }) => {
+            let passwords_dir = &namespace_configuration.passwords_dir;
+            let opaque_names = namespace_configuration.opaque_names;
+            let (mut index, _) = load_index(&signer, passwords_dir).await?;
+            if let Some(otp) = import {
+                import_otp(
+                    &signer,
+                    passwords_dir,
+                    &mut index,
+                    &name,
+                    &otp,
+                    opaque_names,
+                )
+                .await?;
+                println!("Stored one-time password of {}", name);
+            }
+            let code = next_code(&signer, passwords_dir, &mut index, &name, opaque_names).await?;
+            if print {
+                println!("{}", code.code);
+            } else {
+                copy_to_clipboard(code.code)?;
+                match code.remaining {
+                    Some(remaining) => {
+                        println!("Copied code of {}, valid for {}s", name, remaining)
+                    }
+                    None => println!("Copied code of {}", name),
+                }
+            }
+        }
+        Some(Command::Backup { .. }) => unreachable!("backups are handled before unlocking"),
+        Some(Command::Paper {
+            action: PaperAction::Print { qr, out },
+        }) => {
+            let password = secrets.read_new("Enter a password for the key export:")?;
+            let exported = export_private_key_bytes(
+                signer.export_private_key()?,
+                password.expose().to_string(),
+            )?;
+            let description = format!(
+                "Key of namespace {}, restore with `passm paper restore`",
+                namespace_configuration.name
+            );
+            let mut paper = to_paper(&exported, &description);
+            if qr {
+                let codes = to_qr_codes(&paper)?;
+                for code in codes {
+                    paper.push('\n');
+                    paper.push_str(&code);
+                    paper.push('\n');
+                }
+            }
+            match out {
+                Some(out) => {
+                    write_private(&out, paper.as_bytes()).await?;
+                    println!("Written to {}", out.display());
+                }
+                None => print!("{}", paper),
+            }
+        }
+        Some(Command::Paper { .. }) => unreachable!("paper restores are handled before unlocking"),
+        Some(Command::Shares {
+            action:
+                SharesAction::Split {
+                    dir,
+                    threshold,
+                    count,
+                },
+        }) => {
+            let password = secrets.read_new("Enter a password for the key export:")?;
+            let exported = export_private_key_bytes(
+                signer.export_private_key()?,
+                password.expose().to_string(),
+            )?;
+            let shares = split(&exported, threshold, count)?;
+            fs::create_dir_all(&dir)?;
+            for share in &shares {
+                let path = dir.join(format!(
+                    "{}-share-{}-of-{}.txt",
+                    namespace_configuration.name, share.index, share.count
+                ));
+                write_private(&path, share.to_string().as_bytes()).await?;
+                println!("{}", path.display());
+            }
+            println!(
+                "Any {} of these shares and the export password recover the key, hand each to a different person",
+                threshold
+            );
+        }
+        Some(Command::Shares { .. }) => unreachable!("shares are combined before unlocking"),
+        Some(Command::Import {
+            dry_run,
+            on_conflict,
+            source,
+        }) => {
+            let imported = match source {
+                ImportSource::Pass { key, store } => {
+                    let store = store.map(Ok).unwrap_or_else(default_password_store)?;
+                    let passphrase = secrets.read("Enter the passphrase of the GPG key:")?;
+                    let gpg_key =
+                        GpgKey::parse(&fs::read(key)?, Some(passphrase.expose().to_string()))?;
+                    read_password_store(&store, &gpg_key).await?
+                }
+                ImportSource::Keepass { file, key_file } => {
+                    let key_file = key_file.map(fs::read).transpose()?;
+                    let password =
+                        secrets.read("Enter the master password of the KeePass database:")?;
+                    // Databases locked with a key file only have no password at all
+                    let password =
+                        Some(password.expose()).filter(|p| !p.is_empty() || key_file.is_none());
+                    let kdbx = Kdbx::open(&fs::read(file)?, password, key_file.as_deref())?;
+                    read_kdbx(kdbx)?
+                }
+                ImportSource::File { file } => {
+                    let (format, imported) = read_export(&fs::read(file)?)?;
+                    println!("Reading a {} export", format);
+                    for (from, to) in &imported.mapping {
+                        println!("  {} -> {}", from, to);
+                    }
+                    println!();
+                    imported
+                }
+            };
+            let passwords_dir = &namespace_configuration.passwords_dir;
+            let (mut index, _) = load_index(&signer, passwords_dir).await?;
+            let report = import_entries(
+                &signer,
+                passwords_dir,
+                &mut index,
+                imported,
+                on_conflict,
+                namespace_configuration.opaque_names,
+                dry_run,
+            )
+            .await?;
+            println!("{}", report);
+        }
+    }
+
+    Ok(())
+}
 
+async fn run_backup(paths: &Paths, secrets: &Secrets, action: BackupAction) -> Result<()> {
+    match action {
+        BackupAction::Create { file } => {
+            let namespace_configuration = Configuration::init_from_path(paths)?;
+            let password = secrets.read_new("Enter a password for the backup:")?;
+            let summary = write_backup(&namespace_configuration, password.expose(), &file).await?;
+            println!("{}", summary);
+            println!("Written to {} and verified", file.display());
+        }
+        BackupAction::Verify { file } => {
+            let password = secrets.read("Enter the password of the backup:")?;
+            println!("{}", verify_backup(&file, password.expose()).await?);
+        }
+        BackupAction::Restore { file, namespace } => {
+            let password = secrets.read("Enter the password of the backup:")?;
+            let (namespace_configuration, summary) =
+                restore_backup(paths, &file, password.expose(), namespace.as_deref()).await?;
+            println!("{}", summary);
+            println!(
+                "Restored as namespace {}, unlock it with the passphrase of its key",
+                namespace_configuration.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Rebuilds the encrypted key export from paper lines, checking each typed
+/// line as soon as it is entered.
+fn restore_paper(
+    secrets: &Secrets,
+    file: &std::path::Path,
+    from: Option<&std::path::Path>,
+) -> Result<()> {
+    let exported = match from {
+        Some(from) => read_paper(&fs::read_to_string(from)?)?,
+        None => {
+            println!("Type the `Lines:` header and then every numbered line, in any order");
+            let mut reader = PaperReader::new();
+            while !reader.is_complete() {
+                let Some(line) = shared::console::prompt(">")? else {
+                    // The input was closed before every line was typed
+                    reader
+                        .finish()
+                        .map_err(|err| anyhow!("Incomplete backup, {}", err))?;
+                    break;
+                };
+                match reader.push(&line) {
+                    Ok(PaperLine::Header) | Ok(PaperLine::Data(_)) if !reader.is_complete() => {
+                        println!("{} lines left", reader.missing().len())
+                    }
+                    Ok(_) => {}
+                    Err(err) => println!("{}, type it again", err),
+                }
+            }
+            reader.finish()?
+        }
+    };
+    save_key_export(secrets, file, exported)
+}
+
+/// Rebuilds the encrypted key export from any threshold of shares.
+fn combine_shares(secrets: &Secrets, file: &std::path::Path, files: &[PathBuf]) -> Result<()> {
+    let mut shares = Vec::new();
+    for path in files {
+        shares.extend(Share::parse_all(&fs::read_to_string(path)?)?);
+    }
+    save_key_export(secrets, file, combine(&shares)?)
+}
+
+/// Writes a recovered key export once its password opens it.
+fn save_key_export(secrets: &Secrets, file: &std::path::Path, exported: Vec<u8>) -> Result<()> {
+    let password = secrets.read("Enter the password of the key export:")?;
+    let armored = import_private_key_bytes(exported.clone(), password.expose().to_string())
+        .ok()
+        .and_then(|armored| String::from_utf8(armored).ok())
+        .ok_or_else(|| anyhow!("Wrong password for the key export"))?;
+    Signer::parse_signed_secret_from_string(armored)?;
+    fs::write(file, exported)?;
+    println!("Key export written to {}", file.display());
     Ok(())
 }
+
+fn default_password_store() -> Result<PathBuf> {
+    env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".password-store"))
+        .ok_or_else(|| anyhow!("HOME is not set, pass the store with --store"))
+}
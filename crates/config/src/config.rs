@@ -1,125 +1,552 @@
-use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use shared::error::{Error, Result};
 use std::{
     fs,
     path::{Path, PathBuf},
-    process,
 };
 
-const DEFAULT_BASE_PATH: &str = "./.passm";
+use crate::{
+    migrations::{
+        read_config_file, CONFIG_VERSION, MAIN_CONFIG_MIGRATIONS, NAMESPACE_CONFIG_MIGRATIONS,
+    },
+    paths::{move_path, Paths},
+};
+
 const PASSWORDS_DIR_NAME: &str = "passwds";
+const NAMESPACES_DIR_NAME: &str = "namespaces";
 const DEFAULT_NAMESPACE_NAME: &str = "default";
 const DEFAULT_PRIVATE_KEY_NAME: &str = ".private_1";
 const MAIN_CONFIG_NAME: &str = ".config.toml";
+const NAMESPACE_CONFIG_SUFFIX: &str = ".config.toml";
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NamespaceConfig {
+    pub version: u32,
     pub private_key_path: String,
     pub passwords_dir: PathBuf,
     pub name: String,
+    /// Store entries under random file names, keeping names inside the encrypted payloads
+    pub opaque_names: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Configuration {
+    version: u32,
     configurations: Vec<String>,
     default: String,
 }
 
 impl Configuration {
-    fn get_namespace_config_path(base_path: PathBuf, name: &str) -> PathBuf {
-        base_path.join(format!(".{}.config.toml", name))
+    fn get_namespace_config_path(config_dir: &Path, name: &str) -> PathBuf {
+        config_dir.join(format!(".{}{}", name, NAMESPACE_CONFIG_SUFFIX))
+    }
+    fn get_main_config_path(config_dir: &Path) -> PathBuf {
+        config_dir.join(MAIN_CONFIG_NAME)
+    }
+    fn get_namespace_dir(data_dir: &Path, name: &str) -> PathBuf {
+        data_dir.join(NAMESPACES_DIR_NAME).join(name)
+    }
+    fn validate_namespace_name(name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(Error::Config("Namespace name can not be empty".to_string()));
+        }
+        if !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::Config(
+                "Namespace name may only contain letters, digits, '-' and '_'".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn namespaces(&self) -> &Vec<String> {
+        &self.configurations
+    }
+    pub fn default_namespace(&self) -> &str {
+        &self.default
+    }
+
+    pub fn read(paths: &Paths) -> Result<Configuration> {
+        let config_path = Configuration::get_main_config_path(&paths.config_dir);
+        if !config_path.exists() {
+            return Err(Error::Config(
+                "No main configuration file found".to_string(),
+            ));
+        }
+        read_config_file(&config_path, &MAIN_CONFIG_MIGRATIONS)
     }
-    fn get_main_config_path(base_path: PathBuf) -> PathBuf {
-        base_path.join(MAIN_CONFIG_NAME)
+    fn write(&self, paths: &Paths) -> Result<()> {
+        let config_path = Configuration::get_main_config_path(&paths.config_dir);
+        fs::write(
+            config_path,
+            toml::to_string(self).map_err(|e| Error::Config(e.to_string()))?,
+        )?;
+        Ok(())
     }
+
     pub fn init_new(
-        path: String,
+        paths: &Paths,
         default_namespace_name: Option<String>,
     ) -> Result<NamespaceConfig> {
         /*
          *  If dir exists, check that MAIN_CONFIGURATION_FILE_NAME does not exists.
          *  If dir does not exists, create it, create MAIN_CONFIGURATION_FILE_NAME file.
          * */
-        let base_path = Path::new(&path);
-        let default_namespace = default_namespace_name.unwrap_or_else(|| String::from("default"));
-        let main_config_path = Configuration::get_main_config_path(base_path.to_path_buf());
+        let default_namespace =
+            default_namespace_name.unwrap_or_else(|| DEFAULT_NAMESPACE_NAME.to_string());
 
-        fs::create_dir(base_path).unwrap();
+        fs::create_dir_all(&paths.config_dir)?;
+        fs::create_dir_all(&paths.data_dir)?;
 
-        let namespace_config = Configuration::init_new_or_read_existing_namespace_config(
-            base_path.to_path_buf(),
-            &default_namespace,
-        )?;
+        let namespace_config =
+            Configuration::init_new_or_read_existing_namespace_config(paths, &default_namespace)?;
 
         let main_config = Configuration {
+            version: CONFIG_VERSION,
             configurations: vec![default_namespace],
             default: namespace_config.name.clone(),
         };
-        fs::write(main_config_path, toml::to_string(&main_config).unwrap()).unwrap();
+        main_config.write(paths)?;
         Ok(namespace_config)
     }
     pub fn init_new_or_read_existing_namespace_config(
-        base_path: PathBuf,
+        paths: &Paths,
         name: &str,
     ) -> Result<NamespaceConfig> {
         let namespace_config_path =
-            Configuration::get_namespace_config_path(base_path.clone(), name);
+            Configuration::get_namespace_config_path(&paths.config_dir, name);
         match fs::metadata(&namespace_config_path) {
-            Ok(_) => {
-                let existing_config: NamespaceConfig =
-                    toml::from_str(&fs::read_to_string(namespace_config_path).unwrap()).unwrap();
-                Ok(existing_config)
-            }
+            Ok(_) => Configuration::read_namespace_config(paths, name),
             _ => {
+                let namespace_dir = Configuration::get_namespace_dir(&paths.data_dir, name);
                 let namespace_config = NamespaceConfig {
-                    passwords_dir: base_path.join(PASSWORDS_DIR_NAME),
-                    private_key_path: base_path
+                    version: CONFIG_VERSION,
+                    passwords_dir: namespace_dir.join(PASSWORDS_DIR_NAME),
+                    private_key_path: namespace_dir
                         .join(DEFAULT_PRIVATE_KEY_NAME)
                         .to_str()
                         .unwrap()
                         .to_string(),
-                    name: DEFAULT_NAMESPACE_NAME.to_string(),
+                    name: name.to_string(),
+                    opaque_names: false,
                 };
-                fs::create_dir_all(&namespace_config.passwords_dir).unwrap();
-                fs::write(
-                    namespace_config_path,
-                    toml::to_string(&namespace_config).unwrap(),
-                )
-                .unwrap();
+                fs::create_dir_all(&namespace_config.passwords_dir)?;
+                Configuration::write_namespace_config(paths, &namespace_config)?;
                 Ok(namespace_config)
             }
         }
     }
-    pub fn init_from_path(path: String) -> Result<NamespaceConfig> {
-        let base_path = Path::new(&path);
-        let config_path = Configuration::get_main_config_path(base_path.to_path_buf());
-        match fs::metadata(&config_path) {
-            Ok(_) => {
-                println!("Config path exists");
+    pub fn read_namespace_config(paths: &Paths, name: &str) -> Result<NamespaceConfig> {
+        let namespace_config_path =
+            Configuration::get_namespace_config_path(&paths.config_dir, name);
+        if !namespace_config_path.exists() {
+            return Err(Error::Config(format!(
+                "No configuration found for namespace {}",
+                name
+            )));
+        }
+        read_config_file(&namespace_config_path, &NAMESPACE_CONFIG_MIGRATIONS)
+    }
+    fn write_namespace_config(paths: &Paths, namespace_config: &NamespaceConfig) -> Result<()> {
+        fs::write(
+            Configuration::get_namespace_config_path(&paths.config_dir, &namespace_config.name),
+            toml::to_string(namespace_config).map_err(|e| Error::Config(e.to_string()))?,
+        )?;
+        Ok(())
+    }
+
+    /// Creates a new namespace with its own passwords directory and key location.
+    /// The key itself is generated by the caller.
+    pub fn create_namespace(paths: &Paths, name: &str) -> Result<NamespaceConfig> {
+        Configuration::validate_namespace_name(name)?;
+        let mut config = Configuration::read(paths)?;
+        if config.configurations.iter().any(|n| n == name) {
+            return Err(Error::Config(format!("Namespace {} already exists", name)));
+        }
+        if Configuration::get_namespace_dir(&paths.data_dir, name).exists() {
+            return Err(Error::Config(format!(
+                "Directory for namespace {} already exists",
+                name
+            )));
+        }
+        let namespace_config =
+            Configuration::init_new_or_read_existing_namespace_config(paths, name)?;
+        config.configurations.push(name.to_string());
+        config.write(paths)?;
+        Ok(namespace_config)
+    }
+
+    /// Renames a namespace. Its directory is moved along, key and passwords
+    /// kept elsewhere stay where they are. Nothing changes if a step fails.
+    pub fn rename_namespace(paths: &Paths, name: &str, new_name: &str) -> Result<NamespaceConfig> {
+        Configuration::validate_namespace_name(new_name)?;
+        let mut config = Configuration::read(paths)?;
+        if !config.configurations.iter().any(|n| n == name) {
+            return Err(Error::Config(format!("Namespace {} does not exist", name)));
+        }
+        if config.configurations.iter().any(|n| n == new_name) {
+            return Err(Error::Config(format!(
+                "Namespace {} already exists",
+                new_name
+            )));
+        }
+        let old_dir = Configuration::get_namespace_dir(&paths.data_dir, name);
+        let new_dir = Configuration::get_namespace_dir(&paths.data_dir, new_name);
+        if new_dir.exists() {
+            return Err(Error::Config(format!(
+                "Directory for namespace {} already exists",
+                new_name
+            )));
+        }
+        let old_config = Configuration::read_namespace_config(paths, name)?;
+        let mut namespace_config = old_config.clone();
+        namespace_config.name = new_name.to_string();
+        let moves_dir = old_dir.exists();
+        if moves_dir {
+            let rebase = |path: &Path| match path.strip_prefix(&old_dir) {
+                Ok(relative) => new_dir.join(relative),
+                Err(_) => path.to_path_buf(),
+            };
+            namespace_config.passwords_dir = rebase(&old_config.passwords_dir);
+            namespace_config.private_key_path = rebase(Path::new(&old_config.private_key_path))
+                .to_str()
+                .ok_or_else(|| Error::Config("Key path is not valid unicode".to_string()))?
+                .to_string();
+            move_path(&old_dir, &new_dir)?;
+        }
+
+        for n in config.configurations.iter_mut().filter(|n| *n == name) {
+            *n = new_name.to_string();
+        }
+        if config.default == name {
+            config.default = new_name.to_string();
+        }
+        let renamed = Configuration::write_namespace_config(paths, &namespace_config)
+            .and_then(|_| config.write(paths));
+        if let Err(err) = renamed {
+            let _ = fs::remove_file(Configuration::get_namespace_config_path(
+                &paths.config_dir,
+                new_name,
+            ));
+            if moves_dir {
+                let _ = move_path(&new_dir, &old_dir);
             }
-            _ => return Err(anyhow!("No main configuration file found")),
+            return Err(err);
         }
-        let existing_config: Configuration =
-            toml::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        fs::remove_file(Configuration::get_namespace_config_path(
+            &paths.config_dir,
+            name,
+        ))?;
+        Ok(namespace_config)
+    }
 
-        let namespace_config_path = Configuration::get_namespace_config_path(
-            base_path.to_path_buf(),
-            &existing_config.default,
-        );
-        let existing_namespace_config: NamespaceConfig =
-            toml::from_str(&fs::read_to_string(namespace_config_path).unwrap()).unwrap();
-        Ok(existing_namespace_config)
-    }
-    pub fn init() -> Result<NamespaceConfig> {
-        let base_path = Path::new(DEFAULT_BASE_PATH);
-        let config = match fs::metadata(base_path) {
-            Ok(_) => Configuration::init_from_path(DEFAULT_BASE_PATH.to_string()),
-            Err(_) => Configuration::init_new(DEFAULT_BASE_PATH.to_string(), None),
+    /// Deletes a namespace together with its key and passwords.
+    /// The default namespace can not be deleted.
+    pub fn delete_namespace(paths: &Paths, name: &str) -> Result<()> {
+        let mut config = Configuration::read(paths)?;
+        if !config.configurations.iter().any(|n| n == name) {
+            return Err(Error::Config(format!("Namespace {} does not exist", name)));
+        }
+        if config.default == name {
+            return Err(Error::Config(
+                "Default namespace can not be deleted".to_string(),
+            ));
+        }
+        Configuration::remove_namespace_files(paths, name)?;
+        config.configurations.retain(|n| n != name);
+        config.write(paths)?;
+        Ok(())
+    }
+
+    /// Removes a namespace that was just created, when filling it failed.
+    /// If it was the only one the main configuration goes too, so no empty
+    /// vault is left behind.
+    pub fn discard_namespace(paths: &Paths, name: &str) -> Result<()> {
+        let mut config = Configuration::read(paths)?;
+        Configuration::remove_namespace_files(paths, name)?;
+        config.configurations.retain(|n| n != name);
+        if config.configurations.is_empty() {
+            fs::remove_file(Configuration::get_main_config_path(&paths.config_dir))?;
+            return Ok(());
+        }
+        if config.default == name {
+            config.default = config.configurations[0].clone();
+        }
+        config.write(paths)
+    }
+
+    fn remove_namespace_files(paths: &Paths, name: &str) -> Result<()> {
+        let namespace_config = Configuration::read_namespace_config(paths, name)?;
+        if namespace_config.passwords_dir.exists() {
+            fs::remove_dir_all(&namespace_config.passwords_dir)?;
+        }
+        let private_key_path = Path::new(&namespace_config.private_key_path);
+        if private_key_path.exists() {
+            fs::remove_file(private_key_path)?;
+        }
+        let namespace_dir = Configuration::get_namespace_dir(&paths.data_dir, name);
+        if namespace_dir.exists() && fs::read_dir(&namespace_dir)?.next().is_none() {
+            fs::remove_dir(namespace_dir)?;
+        }
+        fs::remove_file(Configuration::get_namespace_config_path(
+            &paths.config_dir,
+            name,
+        ))?;
+        Ok(())
+    }
+
+    pub fn set_default_namespace(paths: &Paths, name: &str) -> Result<()> {
+        let mut config = Configuration::read(paths)?;
+        if !config.configurations.iter().any(|n| n == name) {
+            return Err(Error::Config(format!("Namespace {} does not exist", name)));
+        }
+        config.default = name.to_string();
+        config.write(paths)
+    }
+
+    /// Changes how entries of a namespace are named on disk. Existing entries
+    /// are moved the next time the namespace is unlocked.
+    pub fn set_opaque_names(
+        paths: &Paths,
+        name: &str,
+        opaque_names: bool,
+    ) -> Result<NamespaceConfig> {
+        let mut namespace_config = Configuration::read_namespace_config(paths, name)?;
+        namespace_config.opaque_names = opaque_names;
+        Configuration::write_namespace_config(paths, &namespace_config)?;
+        Ok(namespace_config)
+    }
+
+    pub fn init_from_path(paths: &Paths) -> Result<NamespaceConfig> {
+        let existing_config = Configuration::read(paths)?;
+        Configuration::read_namespace_config(paths, &existing_config.default)
+    }
+    pub fn init(paths: &Paths) -> Result<NamespaceConfig> {
+        let main_config_path = Configuration::get_main_config_path(&paths.config_dir);
+        match fs::metadata(main_config_path) {
+            Ok(_) => Configuration::init_from_path(paths),
+            Err(_) => Configuration::init_new(paths, None),
+        }
+    }
+
+    /// Whether a vault created relative to the working directory exists
+    /// and nothing has been set up at `paths` yet.
+    pub fn needs_legacy_migration(paths: &Paths) -> bool {
+        let legacy_base_path = Paths::legacy_base_path();
+        Configuration::get_main_config_path(&legacy_base_path).exists()
+            && !Configuration::get_main_config_path(&paths.config_dir).exists()
+            && legacy_base_path.canonicalize().ok() != paths.config_dir.canonicalize().ok()
+    }
+
+    /// Moves a vault from the legacy location to `paths`. Configuration files
+    /// go to the config directory, everything else to the data directory.
+    pub fn migrate_legacy(paths: &Paths) -> Result<()> {
+        Configuration::migrate_from(&Paths::legacy_base_path(), paths)
+    }
+
+    fn migrate_from(legacy_base_path: &Path, paths: &Paths) -> Result<()> {
+        let legacy_paths = Paths::from_home(legacy_base_path.to_path_buf());
+        let legacy_config = Configuration::read(&legacy_paths)?;
+        let namespace_configs = legacy_config
+            .namespaces()
+            .iter()
+            .map(|name| Configuration::read_namespace_config(&legacy_paths, name))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Files are moved before any configuration is rewritten, so a failed
+        // move leaves configurations pointing where the files still are
+        fs::create_dir_all(&paths.config_dir)?;
+        fs::create_dir_all(&paths.data_dir)?;
+        for entry in fs::read_dir(legacy_base_path)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let is_config = file_name
+                .to_str()
+                .map(|n| n.contains(NAMESPACE_CONFIG_SUFFIX))
+                .unwrap_or(false);
+            let target_dir = if is_config {
+                &paths.config_dir
+            } else {
+                &paths.data_dir
+            };
+            move_path(&entry.path(), &target_dir.join(file_name))?;
+        }
+
+        // Stored paths are relative to the legacy directory, point them to the new one
+        let rebase = |path: &Path| -> PathBuf {
+            match path.strip_prefix(legacy_base_path) {
+                Ok(relative) => paths.data_dir.join(relative),
+                Err(_) => path.to_path_buf(),
+            }
         };
-        if let Err(err) = config {
-            println!("Error: {}", err);
-            process::exit(1)
+        for mut namespace_config in namespace_configs {
+            namespace_config.passwords_dir = rebase(&namespace_config.passwords_dir);
+            namespace_config.private_key_path =
+                rebase(Path::new(&namespace_config.private_key_path))
+                    .to_str()
+                    .ok_or_else(|| Error::Config("Key path is not valid unicode".to_string()))?
+                    .to_string();
+            Configuration::write_namespace_config(paths, &namespace_config)?;
         }
-        Ok(config.unwrap())
+        fs::remove_dir(legacy_base_path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use super::Configuration;
+    use crate::paths::Paths;
+
+    fn temp_base_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("passm-config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        path
+    }
+
+    #[test]
+    fn manage_namespaces() {
+        let base_path = temp_base_path("namespaces");
+        let paths = &Paths::from_home(base_path.clone());
+        let default = Configuration::init_new(paths, None).unwrap();
+        assert_eq!(default.name, "default");
+
+        let work = Configuration::create_namespace(paths, "work").unwrap();
+        assert_eq!(work.name, "work");
+        assert!(work.passwords_dir.exists());
+        assert_ne!(work.passwords_dir, default.passwords_dir);
+        assert!(Configuration::create_namespace(paths, "work").is_err());
+        assert!(Configuration::create_namespace(paths, "../x").is_err());
+        assert!(!work.opaque_names);
+        Configuration::set_opaque_names(paths, "work", true).unwrap();
+        assert!(
+            Configuration::read_namespace_config(paths, "work")
+                .unwrap()
+                .opaque_names
+        );
+
+        Configuration::set_default_namespace(paths, "work").unwrap();
+        fs::write(work.passwords_dir.join("mail"), "secret").unwrap();
+        let renamed = Configuration::rename_namespace(paths, "work", "office").unwrap();
+        assert!(!work.passwords_dir.exists());
+        assert_eq!(
+            fs::read_to_string(renamed.passwords_dir.join("mail")).unwrap(),
+            "secret"
+        );
+        assert!(renamed.private_key_path.contains("office"));
+        assert!(renamed.opaque_names);
+        // The old name is free again
+        Configuration::create_namespace(paths, "work").unwrap();
+        assert!(Configuration::rename_namespace(paths, "work", "office").is_err());
+        Configuration::delete_namespace(paths, "work").unwrap();
+        let config = Configuration::read(paths).unwrap();
+        assert_eq!(config.default_namespace(), "office");
+        assert_eq!(config.namespaces(), &vec!["default", "office"]);
+
+        assert!(Configuration::delete_namespace(paths, "office").is_err());
+        Configuration::set_default_namespace(paths, "default").unwrap();
+        Configuration::delete_namespace(paths, "office").unwrap();
+        assert!(!renamed.passwords_dir.exists());
+        let config = Configuration::read(paths).unwrap();
+        assert_eq!(config.namespaces(), &vec!["default"]);
+
+        let restored = Configuration::create_namespace(paths, "restored").unwrap();
+        Configuration::discard_namespace(paths, "restored").unwrap();
+        assert!(!restored.passwords_dir.exists());
+        let config = Configuration::read(paths).unwrap();
+        assert_eq!(config.namespaces(), &vec!["default"]);
+        // Discarding the only namespace leaves no vault behind
+        Configuration::discard_namespace(paths, "default").unwrap();
+        assert!(Configuration::read(paths).is_err());
+        assert!(!default.passwords_dir.exists());
+
+        fs::remove_dir_all(base_path).unwrap();
+    }
+
+    #[test]
+    fn upgrade_unversioned_config() {
+        let base_path = temp_base_path("unversioned");
+        let paths = &Paths::from_home(base_path.clone());
+        fs::create_dir_all(&base_path).unwrap();
+        fs::write(
+            base_path.join(".config.toml"),
+            "configurations = [\"default\"]\ndefault = \"default\"\n",
+        )
+        .unwrap();
+
+        let config = Configuration::read(paths).unwrap();
+        assert_eq!(config.default_namespace(), "default");
+        assert!(fs::read_to_string(base_path.join(".config.toml"))
+            .unwrap()
+            .contains("version = 2"));
+        assert!(base_path.join(".config.toml.v0.bak").exists());
+
+        fs::write(base_path.join(".config.toml"), "version = 42\n").unwrap();
+        assert!(Configuration::read(paths).is_err());
+
+        fs::remove_dir_all(base_path).unwrap();
+    }
+
+    #[test]
+    fn migrate_legacy_vault() {
+        let legacy_base_path = temp_base_path("legacy");
+        let target = temp_base_path("migrated");
+        let legacy_paths = Paths::from_home(legacy_base_path.clone());
+        let legacy = Configuration::init_new(&legacy_paths, None).unwrap();
+        fs::write(legacy.passwords_dir.join("mail"), "secret").unwrap();
+        fs::write(&legacy.private_key_path, "key").unwrap();
+
+        let paths = Paths {
+            config_dir: target.join("config"),
+            data_dir: target.join("data"),
+        };
+        Configuration::migrate_from(&legacy_base_path, &paths).unwrap();
+
+        assert!(!legacy_base_path.exists());
+        let migrated = Configuration::init_from_path(&paths).unwrap();
+        assert!(migrated.passwords_dir.starts_with(&paths.data_dir));
+        assert_eq!(
+            fs::read_to_string(migrated.passwords_dir.join("mail")).unwrap(),
+            "secret"
+        );
+        assert_eq!(
+            fs::read_to_string(migrated.private_key_path).unwrap(),
+            "key"
+        );
+
+        fs::remove_dir_all(target).unwrap();
+    }
+
+    #[test]
+    fn failed_legacy_migration_keeps_paths() {
+        let legacy_base_path = temp_base_path("legacy-blocked");
+        let target = temp_base_path("blocked");
+        let legacy =
+            Configuration::init_new(&Paths::from_home(legacy_base_path.clone()), None).unwrap();
+        let paths = Paths {
+            config_dir: target.join("config"),
+            data_dir: target.join("data"),
+        };
+        // A file where the namespaces directory has to go
+        fs::create_dir_all(&paths.data_dir).unwrap();
+        fs::write(paths.data_dir.join("namespaces"), "").unwrap();
+        assert!(Configuration::migrate_from(&legacy_base_path, &paths).is_err());
+
+        // Wherever its configuration ended up, it still points to the files
+        let config = [&legacy_base_path, &paths.config_dir]
+            .into_iter()
+            .find_map(|dir| {
+                Configuration::read_namespace_config(&Paths::from_home(dir.clone()), "default").ok()
+            })
+            .unwrap();
+        assert_eq!(config.passwords_dir, legacy.passwords_dir);
+        assert!(config.passwords_dir.exists());
+
+        fs::remove_dir_all(legacy_base_path).unwrap();
+        fs::remove_dir_all(target).unwrap();
     }
 }
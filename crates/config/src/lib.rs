@@ -1,4 +1,6 @@
 pub mod config;
+pub mod migrations;
+pub mod paths;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,154 @@
+use serde::de::DeserializeOwned;
+use shared::error::{Error, Result};
+use std::{fs, path::Path};
+use toml::{value::Table, Value};
+
+/// Version written to newly created configuration files.
+pub const CONFIG_VERSION: u32 = 2;
+
+const VERSION_KEY: &str = "version";
+
+/// Upgrades a configuration table from version `n` to `n + 1`, where `n` is
+/// the index of the migration in its chain. Settings added in a new version
+/// get their default values here, so structs never need serde defaults.
+type Migration = fn(&mut Table) -> Result<()>;
+
+pub(crate) const MAIN_CONFIG_MIGRATIONS: [Migration; CONFIG_VERSION as usize] =
+    [add_version, unchanged];
+pub(crate) const NAMESPACE_CONFIG_MIGRATIONS: [Migration; CONFIG_VERSION as usize] =
+    [add_version, add_opaque_names];
+
+/// Files written before versioning was introduced are version 0.
+fn add_version(_table: &mut Table) -> Result<()> {
+    Ok(())
+}
+
+/// Version bumps that did not change the file.
+fn unchanged(_table: &mut Table) -> Result<()> {
+    Ok(())
+}
+
+/// Existing namespaces keep entries in files named after them.
+fn add_opaque_names(table: &mut Table) -> Result<()> {
+    table.insert("opaque_names".to_string(), Value::Boolean(false));
+    Ok(())
+}
+
+fn newer_version(version: impl std::fmt::Display) -> Error {
+    Error::Config(format!(
+        "Config version {} is newer than the supported version {}, please upgrade passm",
+        version, CONFIG_VERSION
+    ))
+}
+
+fn get_version(table: &Table) -> Result<u32> {
+    match table.get(VERSION_KEY) {
+        None => Ok(0),
+        Some(Value::Integer(version)) if *version >= 0 => {
+            u32::try_from(*version).map_err(|_| newer_version(version))
+        }
+        Some(other) => Err(Error::Config(format!("Invalid config version: {}", other))),
+    }
+}
+
+/// Applies `migrations` to `table` until it reaches `CONFIG_VERSION`.
+/// Returns the version the table had before the migration.
+pub(crate) fn migrate(table: &mut Table, migrations: &[Migration]) -> Result<u32> {
+    let version = get_version(table)?;
+    if version > CONFIG_VERSION {
+        return Err(newer_version(version));
+    }
+    for (target, migration) in migrations
+        .iter()
+        .enumerate()
+        .skip(version as usize)
+        .map(|(from, migration)| (from + 1, migration))
+    {
+        migration(table)?;
+        table.insert(VERSION_KEY.to_string(), Value::Integer(target as i64));
+    }
+    Ok(version)
+}
+
+/// Reads a configuration file, upgrading it in place when it was written by
+/// an older version. The original file is kept next to it as a backup.
+pub(crate) fn read_config_file<T: DeserializeOwned>(
+    path: &Path,
+    migrations: &[Migration],
+) -> Result<T> {
+    let content = fs::read_to_string(path)?;
+    let mut table: Table = toml::from_str(&content)
+        .map_err(|e| Error::Config(format!("Failed to parse {}: {}", path.display(), e)))?;
+    let version = migrate(&mut table, migrations).map_err(|e| match e {
+        Error::Config(message) => Error::Config(format!("{}: {}", path.display(), message)),
+        e => e,
+    })?;
+    if version < CONFIG_VERSION {
+        let mut backup_path = path.as_os_str().to_owned();
+        backup_path.push(format!(".v{}.bak", version));
+        fs::copy(path, &backup_path)?;
+        let migrated = toml::to_string(&table).map_err(|e| Error::Config(e.to_string()))?;
+        fs::write(path, migrated)?;
+    }
+    Value::Table(table)
+        .try_into()
+        .map_err(|e| Error::Config(format!("Invalid {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use toml::{value::Table, Value};
+
+    use super::{
+        migrate, Migration, CONFIG_VERSION, MAIN_CONFIG_MIGRATIONS, NAMESPACE_CONFIG_MIGRATIONS,
+    };
+
+    #[test]
+    fn migrates_unversioned_config() {
+        let mut table: Table = toml::from_str("default = \"default\"").unwrap();
+        let version = migrate(&mut table, &MAIN_CONFIG_MIGRATIONS).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(
+            table.get("version"),
+            Some(&Value::Integer(CONFIG_VERSION as i64))
+        );
+    }
+
+    #[test]
+    fn adds_opaque_names_setting() {
+        let mut table: Table = toml::from_str("version = 1\nname = \"work\"").unwrap();
+        assert_eq!(
+            migrate(&mut table, &NAMESPACE_CONFIG_MIGRATIONS).unwrap(),
+            1
+        );
+        assert_eq!(table.get("opaque_names"), Some(&Value::Boolean(false)));
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut table: Table = toml::from_str("version = 9999").unwrap();
+        assert!(migrate(&mut table, &MAIN_CONFIG_MIGRATIONS).is_err());
+        // Would be version 2 if cut down to 32 bits
+        let mut table: Table = toml::from_str("version = 4294967298").unwrap();
+        let err = migrate(&mut table, &MAIN_CONFIG_MIGRATIONS).unwrap_err();
+        assert!(err.to_string().contains("newer than the supported version"));
+        let mut table: Table = toml::from_str("version = \"one\"").unwrap();
+        assert!(migrate(&mut table, &MAIN_CONFIG_MIGRATIONS).is_err());
+    }
+
+    #[test]
+    fn runs_only_pending_migrations() {
+        fn add_setting(table: &mut Table) -> shared::error::Result<()> {
+            table.insert("setting".to_string(), Value::Boolean(true));
+            Ok(())
+        }
+        let migrations: [Migration; 1] = [add_setting];
+        let mut table: Table = toml::from_str("version = 1").unwrap();
+        migrate(&mut table, &migrations).unwrap();
+        assert!(table.get("setting").is_none());
+
+        let mut table = Table::new();
+        migrate(&mut table, &migrations).unwrap();
+        assert_eq!(table.get("setting"), Some(&Value::Boolean(true)));
+    }
+}
@@ -0,0 +1,149 @@
+use shared::error::{Error, Result};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const HOME_ENV: &str = "PASSM_HOME";
+const APP_DIR_NAME: &str = "passm";
+const LEGACY_BASE_PATH: &str = "./.passm";
+
+/// Locations of passm files. Configuration files live in `config_dir`,
+/// keys and passwords in `data_dir`. Both point to the same directory
+/// when a home directory is given explicitly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+}
+
+impl Paths {
+    pub fn from_home(home: PathBuf) -> Self {
+        Self {
+            config_dir: home.clone(),
+            data_dir: home,
+        }
+    }
+
+    /// Resolves paths in order of precedence: `home` (the `--home` flag),
+    /// the `PASSM_HOME` environment variable, then XDG base directories.
+    pub fn resolve(home: Option<PathBuf>) -> Result<Self> {
+        Self::resolve_with(home, |name| env::var_os(name))
+    }
+
+    /// Like [`Paths::resolve`], looking environment variables up with `var`.
+    fn resolve_with(home: Option<PathBuf>, var: impl Fn(&str) -> Option<OsString>) -> Result<Self> {
+        if let Some(home) = home.or_else(|| var(HOME_ENV).map(PathBuf::from)) {
+            return Ok(Paths::from_home(home));
+        }
+        Ok(Self {
+            config_dir: xdg_dir(&var, "XDG_CONFIG_HOME", ".config")?.join(APP_DIR_NAME),
+            data_dir: xdg_dir(&var, "XDG_DATA_HOME", ".local/share")?.join(APP_DIR_NAME),
+        })
+    }
+
+    /// Directory used by versions of passm that stored everything relative
+    /// to the current working directory.
+    pub fn legacy_base_path() -> PathBuf {
+        PathBuf::from(LEGACY_BASE_PATH)
+    }
+}
+
+fn xdg_dir(
+    var: impl Fn(&str) -> Option<OsString>,
+    variable: &str,
+    fallback: &str,
+) -> Result<PathBuf> {
+    // The XDG spec requires relative paths to be ignored
+    if let Some(dir) = var(variable).map(PathBuf::from) {
+        if dir.is_absolute() {
+            return Ok(dir);
+        }
+    }
+    let home = var("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::Config(format!("Neither {} nor HOME is set", variable)))?;
+    Ok(home.join(fallback))
+}
+
+/// Moves `from` to `to`, falling back to copy and delete when a plain
+/// rename is not possible (e.g. across file systems).
+pub(crate) fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursive(from, to)?;
+    if from.is_dir() {
+        fs::remove_dir_all(from)?;
+    } else {
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, path::PathBuf};
+
+    use super::{Paths, HOME_ENV};
+
+    /// Environment holding only `vars`, the process one is shared by all tests.
+    fn environment(vars: &'static [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| OsString::from(value))
+        }
+    }
+
+    #[test]
+    fn explicit_home_wins() {
+        let paths = Paths::resolve(Some(PathBuf::from("/tmp/passm-home"))).unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("/tmp/passm-home"));
+        assert_eq!(paths.data_dir, PathBuf::from("/tmp/passm-home"));
+    }
+
+    #[test]
+    fn home_variable_comes_next() {
+        let var = environment(&[(HOME_ENV, "/srv/passm"), ("XDG_DATA_HOME", "/data")]);
+        let paths = Paths::resolve_with(None, &var).unwrap();
+        assert_eq!(paths, Paths::from_home(PathBuf::from("/srv/passm")));
+        let paths = Paths::resolve_with(Some(PathBuf::from("/tmp/passm-home")), &var).unwrap();
+        assert_eq!(paths.data_dir, PathBuf::from("/tmp/passm-home"));
+    }
+
+    #[test]
+    fn falls_back_to_xdg_directories() {
+        let var = environment(&[
+            ("HOME", "/home/me"),
+            ("XDG_CONFIG_HOME", "/etc/me"),
+            ("XDG_DATA_HOME", "relative"),
+        ]);
+        let paths = Paths::resolve_with(None, var).unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("/etc/me/passm"));
+        // Relative XDG paths are ignored
+        assert_eq!(paths.data_dir, PathBuf::from("/home/me/.local/share/passm"));
+
+        let paths = Paths::resolve_with(None, environment(&[("HOME", "/home/me")])).unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("/home/me/.config/passm"));
+        assert!(Paths::resolve_with(None, environment(&[])).is_err());
+    }
+}
@@ -1,30 +1,50 @@
-use std::{
-    path::{Path, PathBuf},
-    process,
-    str::FromStr,
-    time::Duration,
-};
+use std::{collections::HashMap, path::PathBuf, process, time::Duration};
 
-use anyhow::{Ok, Result};
-use clipboard::{ClipboardContext, ClipboardProvider};
-use crypto::signer::Signer;
-use log::debug;
+use chrono::Local;
+use config::{
+    config::{Configuration, NamespaceConfig},
+    paths::Paths,
+};
+use crypto::{
+    otp::{unix_time, Otp},
+    signer::Signer,
+};
+use log::{debug, error};
 use shared::{
+    error::{Error, Result},
     events::{Event, KeyCode},
-    state::{ActivePage, State},
+    input::TextInput,
+    notification::NotificationLevel,
+    password::{Entry, EntryKind, OtpCode},
+    search::search,
+    secret::{normalize_passphrase, SecretString},
+    state::{find_by_prefix, navigate, ActivePage, DetailsField, State, PAGE_SIZE},
+    strength::estimate,
 };
 use tokio::{
-    fs, join,
+    join,
     sync::{broadcast::Sender, mpsc::UnboundedReceiver},
 };
 use ui::{ui::UI, EventLoop};
 
 use crate::{
-    exporter::export_private_key,
-    files::{delete_password, read_password_bytes, read_passwords_from_path, save_to_file},
+    audit::{audit_vault, breached_count},
+    backup::write_backup,
+    breach::BreachList,
+    copy::copy_to_clipboard,
+    exporter::{export_entries, export_private_key, PlainExport, PlainFormat},
+    files::{delete_entry, list_entries, migrate_file_names, read_entry, save_entry},
+    import::{import_entries, preview_import, read_export, ConflictPolicy, ImportFormat, Imported},
+    index::{load_index, Index},
+    keys::{create_signed_key, unlock_signer},
+    otp::{next_code, CachedCode},
 };
 
 const TERMINATE_PAGES: [shared::state::ActivePage; 1] = [ActivePage::PasswordsList];
+const TICK_RATE: Duration = Duration::from_millis(8);
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(4);
+const NOTIFICATION_TICKS: u32 = (NOTIFICATION_DURATION.as_millis() / TICK_RATE.as_millis()) as u32;
+const WEAK_SECRET_WARNING: &str = "easy to guess, press Enter again to use it anyway";
 
 pub struct App {
     ui: Option<UI>,
@@ -33,53 +53,108 @@ pub struct App {
     tr_terminate_event_loop: Sender<()>,
     event_loop: Option<EventLoop>,
     signer: Signer,
+    paths: Paths,
     passwords_dir: PathBuf,
+    /// Metadata index of the active namespace, loaded on the first refresh
+    index: Option<Index>,
+    /// Whether entries of the active namespace are stored under random file names
+    opaque_names: bool,
+    otp_code: CachedCode,
+    /// Downloaded Pwned Passwords list to check passwords against
+    breach_list: Option<BreachList>,
+    /// Breach counts of the active namespace, checked once on the first refresh
+    breached: Option<HashMap<String, u64>>,
+    /// Export read for the import preview, saved once it is confirmed
+    pending_import: Option<(ImportFormat, Imported)>,
+    import_policy: ConflictPolicy,
+    /// Entries picked for a plaintext export, None for the whole namespace
+    plain_export_names: Option<Vec<String>>,
     export_pgp_secret_file_path: PathBuf,
     should_refresh_passwords: bool,
 }
 
 impl App {
-    pub fn new(
-        signer: Signer,
-        passwords_dir: PathBuf,
-        export_pgp_secret_file_path: PathBuf,
-    ) -> Self {
+    pub fn new(signer: Signer, paths: Paths, namespace_config: NamespaceConfig) -> Self {
         // Send tr_state to integrations loop later
-        let mut event_loop = EventLoop::new(Duration::from_millis(8));
+        let mut event_loop = EventLoop::new(TICK_RATE);
+        let state = State {
+            active_namespace: namespace_config.name.clone(),
+            ..State::default()
+        };
         Self {
             should_refresh_passwords: true,
             ui: Some(UI::new()),
-            state: State::default(),
+            state,
             rec_event: event_loop.rec_event.take().unwrap(),
             tr_terminate_event_loop: event_loop.tr_terminate.clone(),
             event_loop: Some(event_loop),
             signer,
-            passwords_dir,
-            export_pgp_secret_file_path,
+            paths,
+            export_pgp_secret_file_path: App::get_export_pgp_secret_file_path(&namespace_config),
+            passwords_dir: namespace_config.passwords_dir,
+            index: None,
+            opaque_names: namespace_config.opaque_names,
+            otp_code: CachedCode::default(),
+            breach_list: None,
+            breached: None,
+            pending_import: None,
+            import_policy: ConflictPolicy::default(),
+            plain_export_names: None,
         }
     }
 
+    /// Flags passwords found in a local Pwned Passwords list.
+    pub fn with_breach_list(mut self, breach_list: BreachList) -> Self {
+        self.breach_list = Some(breach_list);
+        self
+    }
+
+    fn get_export_pgp_secret_file_path(namespace_config: &NamespaceConfig) -> PathBuf {
+        let mut export_pgp_secret_path = namespace_config.private_key_path.clone();
+        export_pgp_secret_path.push_str(".aes");
+        export_pgp_secret_path.into()
+    }
+
     async fn run_ui(&mut self, ui: &mut UI) -> Result<()> {
-        ui.setup_terminal().unwrap();
+        ui.setup_terminal()?;
         loop {
             if let Some(event) = self.rec_event.recv().await {
                 match event {
                     Event::Tick => {
                         if self.should_refresh_passwords {
-                            let passwords = read_passwords_from_path(&self.passwords_dir).await?;
-                            self.state.passwords_list = passwords;
                             self.should_refresh_passwords = false;
+                            if let Err(err) = self.refresh_passwords().await {
+                                self.show_error(err);
+                            }
                         }
-                        ui.draw(self.state.clone()).await?;
+                        self.state.otp_code = self.revealed_otp_code();
+                        self.state.tick_notifications();
+                        ui.draw(&self.state).await?;
                     }
                     Event::KeyEvent(key_code) => {
+                        // Any key dismisses the error popup
+                        if self.state.error.take().is_some() {
+                            continue;
+                        }
                         if key_code.is_terminate()
                             && TERMINATE_PAGES.contains(&self.state.active_page)
+                            && self.state.passwords_list_jump.is_none()
                         {
-                            self.tr_terminate_event_loop.send(())?;
+                            self.tr_terminate_event_loop
+                                .send(())
+                                .map_err(|e| Error::Terminal(e.to_string()))?;
                             break;
                         }
-                        self.handle_input(key_code).await?;
+                        if let Err(err) = self.handle_input(key_code).await {
+                            self.show_error(err);
+                        }
+                    }
+                    Event::Paste(text) => {
+                        if self.state.error.is_none() {
+                            if let Err(err) = self.handle_paste(text) {
+                                self.show_error(err);
+                            }
+                        }
                     }
                     Event::Terminate => {
                         break;
@@ -92,22 +167,34 @@ impl App {
     }
 
     pub async fn handle_input(&mut self, input: KeyCode) -> Result<()> {
+        if input == KeyCode::Ctrl('r') && self.state.secret_input_focused() {
+            self.state.secret_revealed = !self.state.secret_revealed;
+            return Ok(());
+        }
+        let page = self.state.active_page;
+        let selected = self.state.active_password_record;
+        let result = self.handle_page_input(input).await;
+        // Revealed secrets are hidden again as soon as the focus moves
+        if self.state.active_page != page {
+            self.state.secret_revealed = false;
+        }
+        if self.state.active_page != page || self.state.active_password_record != selected {
+            self.state.revealed_entry = None;
+        }
+        result
+    }
+
+    async fn handle_page_input(&mut self, input: KeyCode) -> Result<()> {
+        if self.handle_list_navigation(input) {
+            return Ok(());
+        }
         match self.state.active_page {
             ActivePage::PasswordsList => match input {
-                KeyCode::Down => {
-                    if self.state.active_password_record >= self.state.passwords_list.len() - 1 {
-                        return Ok(());
-                    }
-                    self.state.active_password_record += 1;
-                }
-                KeyCode::Up => {
-                    if self.state.active_password_record == 0 {
-                        return Ok(());
-                    }
-                    self.state.active_password_record -= 1;
+                KeyCode::Char('f') => {
+                    self.state.passwords_list_jump = Some(String::new());
                 }
                 KeyCode::Char('/') => {
-                    self.state.passwords_list_search = self.state.passwords_list.clone();
+                    self.filter_passwords_list()?;
                     self.state.active_page = ActivePage::SearchPasswordsListName;
                 }
                 KeyCode::Char('p') => {
@@ -116,15 +203,20 @@ impl App {
                         .parent()
                         .map(|r| r.canonicalize().unwrap_or(r.to_path_buf()))
                         .unwrap_or(self.export_pgp_secret_file_path.clone());
-                    dir_root.push(self.export_pgp_secret_file_path.file_name().unwrap());
+                    if let Some(file_name) = self.export_pgp_secret_file_path.file_name() {
+                        dir_root.push(file_name);
+                    }
                     self.state.export_pgp_secret_location =
-                        Some(dir_root.to_str().unwrap().to_string());
+                        TextInput::new(dir_root.to_string_lossy().to_string());
                     self.state.export_pgp_secret_location_error = false;
-                    self.state.export_pgp_secret_master_password = Some("".to_string());
+                    self.state.export_pgp_secret_master_password.clear();
                     self.state.active_page = ActivePage::ExportPgpLocation;
                 }
                 KeyCode::Char('a') => {
-                    self.state.active_page = ActivePage::CreateNewPasswordName;
+                    self.start_new_entry(EntryKind::Password);
+                }
+                KeyCode::Char('N') => {
+                    self.start_new_entry(EntryKind::Note);
                 }
                 KeyCode::Char('e') => {
                     self.fill_selected_password_for_editing().await?;
@@ -136,9 +228,67 @@ impl App {
                 KeyCode::Char('\n') => {
                     self.copy_selected_password_to_clipboard().await?;
                 }
+                KeyCode::Char('v') => {
+                    self.toggle_selected_entry_reveal().await?;
+                }
+                KeyCode::Char('t') => {
+                    if let Some(pass) = self
+                        .state
+                        .passwords_list
+                        .get(self.state.active_password_record)
+                    {
+                        let name = pass.name.clone();
+                        self.copy_otp_code(&name).await?;
+                    }
+                }
                 KeyCode::Char('x') => {
                     self.export_pgp_private_key().await?;
                 }
+                KeyCode::Char('l') => {
+                    self.state.message_log_scroll = 0;
+                    self.state.active_page = ActivePage::MessageLog;
+                }
+                KeyCode::Char('A') => {
+                    self.run_audit().await?;
+                    self.state.active_page = ActivePage::Audit;
+                }
+                KeyCode::Char('I') => {
+                    self.state.import_location.clear();
+                    self.state.active_page = ActivePage::ImportLocation;
+                }
+                KeyCode::Char('E') => {
+                    let scope = format!(
+                        "All {} entries of namespace {}",
+                        self.state.passwords_list.len(),
+                        self.state.active_namespace
+                    );
+                    self.start_plain_export(None, scope);
+                }
+                KeyCode::Char('B') => {
+                    let file_name = format!(
+                        "{}-{}.passm-backup",
+                        self.state.active_namespace,
+                        Local::now().format("%Y%m%d")
+                    );
+                    let location = self.paths.data_dir.join("backups").join(file_name);
+                    self.state.backup_location =
+                        TextInput::new(location.to_string_lossy().to_string());
+                    self.state.backup_password.clear();
+                    self.state.backup_password_confirm.clear();
+                    self.state.backup_error = None;
+                    self.state.active_page = ActivePage::BackupLocation;
+                }
+                KeyCode::Char('n') => {
+                    self.refresh_namespaces()?;
+                    self.state.active_namespace_record = self
+                        .state
+                        .namespaces
+                        .iter()
+                        .position(|n| *n == self.state.active_namespace)
+                        .unwrap_or(0);
+                    self.state.namespace_error = None;
+                    self.state.active_page = ActivePage::NamespacesList;
+                }
                 _ => {}
             },
             ActivePage::CreateNewPasswordName => match input {
@@ -149,73 +299,49 @@ impl App {
                     self.state.active_page = ActivePage::CreateNewPasswordBody;
                 }
                 KeyCode::Ctrl('c') => {
-                    self.state.password_name_input = None;
-                    self.state.password_input = None;
-                    self.state.active_page = ActivePage::PasswordsList;
-                }
-                KeyCode::Backspace => {
-                    let mut curr = self
-                        .state
-                        .password_name_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.pop();
-                    self.state.password_name_input = Some(curr);
-                }
-                KeyCode::Char(char) => {
-                    let mut curr = self
-                        .state
-                        .password_name_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.push(char);
-                    self.state.password_name_input = Some(curr);
+                    self.cancel_entry_form();
                 }
-                _ => {}
+                _ => self.handle_text_input(input)?,
             },
             ActivePage::CreateNewPasswordBody => match input {
                 KeyCode::BackTab => {
                     self.state.active_page = ActivePage::CreateNewPasswordName;
                 }
+                KeyCode::Tab => {
+                    self.state.entry_details_field = DetailsField::Username;
+                    self.state.active_page = ActivePage::CreateNewPasswordDetails;
+                }
                 KeyCode::Ctrl('c') => {
-                    self.state.password_name_input = None;
-                    self.state.password_input = None;
-                    self.state.active_page = ActivePage::PasswordsList;
+                    self.cancel_entry_form();
                 }
                 KeyCode::Ctrl('d') => {
-                    let pass_name = self
-                        .state
-                        .password_name_input
-                        .take()
-                        .unwrap_or_else(|| "".to_string());
-                    let pass = self
-                        .state
-                        .password_input
-                        .take()
-                        .unwrap_or_else(|| "".to_string());
-                    self.save_password(pass_name, pass).await?;
-                    self.should_refresh_passwords = true;
-                    self.state.active_page = ActivePage::PasswordsList;
+                    self.save_password_inputs().await?;
                 }
-                KeyCode::Char(char) => {
-                    let mut curr = self
-                        .state
-                        .password_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.push(char);
-                    self.state.password_input = Some(curr);
-                }
-                KeyCode::Backspace => {
-                    let mut curr = self
-                        .state
-                        .password_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.pop();
-                    self.state.password_input = Some(curr);
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::CreateNewPasswordDetails => match input {
+                // Notes span several lines, Enter and arrows edit them
+                KeyCode::Char('\n') | KeyCode::Up | KeyCode::Down
+                    if self.state.entry_details_field == DetailsField::Notes =>
+                {
+                    self.handle_text_input(input)?
                 }
-                _ => {}
+                KeyCode::Tab | KeyCode::Down | KeyCode::Char('\n') => {
+                    if let Some(next) = self.state.entry_details_field.next() {
+                        self.state.entry_details_field = next;
+                    }
+                }
+                KeyCode::BackTab | KeyCode::Up => match self.state.entry_details_field.previous() {
+                    Some(previous) => self.state.entry_details_field = previous,
+                    None => self.state.active_page = ActivePage::CreateNewPasswordBody,
+                },
+                KeyCode::Ctrl('c') => {
+                    self.cancel_entry_form();
+                }
+                KeyCode::Ctrl('d') => {
+                    self.save_password_inputs().await?;
+                }
+                _ => self.handle_text_input(input)?,
             },
             ActivePage::EditPasswordName => match input {
                 KeyCode::Char('\n') => {
@@ -225,99 +351,65 @@ impl App {
                     self.state.active_page = ActivePage::EditPasswordBody;
                 }
                 KeyCode::Ctrl('c') => {
-                    self.state.password_name_input = None;
-                    self.state.password_input = None;
-                    self.state.active_page = ActivePage::PasswordsList;
+                    self.cancel_entry_form();
                 }
-                KeyCode::Backspace => {
-                    let mut curr = self
-                        .state
-                        .password_name_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.pop();
-                    self.state.password_name_input = Some(curr);
-                }
-                KeyCode::Char(char) => {
-                    let mut curr = self
-                        .state
-                        .password_name_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.push(char);
-                    self.state.password_name_input = Some(curr);
-                }
-                _ => {}
+                _ => self.handle_text_input(input)?,
             },
             ActivePage::EditPasswordBody => match input {
                 KeyCode::BackTab => {
                     self.state.active_page = ActivePage::EditPasswordName;
                 }
+                KeyCode::Tab => {
+                    self.state.entry_details_field = DetailsField::Username;
+                    self.state.active_page = ActivePage::EditPasswordDetails;
+                }
                 KeyCode::Ctrl('c') => {
-                    self.state.password_name_input = None;
-                    self.state.password_input = None;
-                    self.state.active_page = ActivePage::PasswordsList;
+                    self.cancel_entry_form();
                 }
                 KeyCode::Ctrl('d') => {
-                    let pass_name = self
-                        .state
-                        .password_name_input
-                        .take()
-                        .unwrap_or_else(|| "".to_string());
-                    let pass = self
-                        .state
-                        .password_input
-                        .take()
-                        .unwrap_or_else(|| "".to_string());
-                    self.save_password(pass_name, pass).await?;
-                    self.should_refresh_passwords = true;
-                    self.state.active_page = ActivePage::PasswordsList;
-                }
-                KeyCode::Char(char) => {
-                    let mut curr = self
-                        .state
-                        .password_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.push(char);
-                    self.state.password_input = Some(curr);
-                }
-                KeyCode::Backspace => {
-                    let mut curr = self
-                        .state
-                        .password_input
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.pop();
-                    self.state.password_input = Some(curr);
+                    self.save_password_inputs().await?;
                 }
-                _ => {}
+                _ => self.handle_text_input(input)?,
             },
-            ActivePage::SearchPasswordsList => match input {
-                KeyCode::Down => {
-                    if self.state.active_password_record_search
-                        >= self.state.passwords_list_search.len() - 1
-                    {
-                        return Ok(());
-                    }
-                    self.state.active_password_record_search += 1;
+            ActivePage::EditPasswordDetails => match input {
+                // Notes span several lines, Enter and arrows edit them
+                KeyCode::Char('\n') | KeyCode::Up | KeyCode::Down
+                    if self.state.entry_details_field == DetailsField::Notes =>
+                {
+                    self.handle_text_input(input)?
                 }
-                KeyCode::Up => {
-                    if self.state.active_password_record_search == 0 {
-                        return Ok(());
+                KeyCode::Tab | KeyCode::Down | KeyCode::Char('\n') => {
+                    if let Some(next) = self.state.entry_details_field.next() {
+                        self.state.entry_details_field = next;
                     }
-                    self.state.active_password_record_search -= 1;
                 }
+                KeyCode::BackTab | KeyCode::Up => match self.state.entry_details_field.previous() {
+                    Some(previous) => self.state.entry_details_field = previous,
+                    None => self.state.active_page = ActivePage::EditPasswordBody,
+                },
+                KeyCode::Ctrl('c') => {
+                    self.cancel_entry_form();
+                }
+                KeyCode::Ctrl('d') => {
+                    self.save_password_inputs().await?;
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::SearchPasswordsList => match input {
                 KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => {
-                    self.state.passwords_list_search_term = None;
+                    self.state.passwords_list_search_term.clear();
                     self.state.passwords_list_search = vec![];
+                    self.state.passwords_list_search_highlights = vec![];
                     self.state.active_page = ActivePage::PasswordsList;
                 }
                 KeyCode::Tab | KeyCode::BackTab => {
                     self.state.active_page = ActivePage::SearchPasswordsListName;
                 }
                 KeyCode::Char('a') => {
-                    self.state.active_page = ActivePage::CreateNewPasswordName;
+                    self.start_new_entry(EntryKind::Password);
+                }
+                KeyCode::Char('N') => {
+                    self.start_new_entry(EntryKind::Note);
                 }
                 KeyCode::Char('e') => {
                     self.fill_selected_password_for_editing().await?;
@@ -329,6 +421,26 @@ impl App {
                 KeyCode::Char('\n') => {
                     self.copy_selected_password_to_clipboard_search().await?;
                 }
+                KeyCode::Char('t') => {
+                    if let Some(pass) = self
+                        .state
+                        .passwords_list_search
+                        .get(self.state.active_password_record_search)
+                    {
+                        let name = pass.name.clone();
+                        self.copy_otp_code(&name).await?;
+                    }
+                }
+                KeyCode::Char('E') if !self.state.passwords_list_search.is_empty() => {
+                    let names: Vec<String> = self
+                        .state
+                        .passwords_list_search
+                        .iter()
+                        .map(|password| password.name.clone())
+                        .collect();
+                    let scope = format!("{} searched entries", names.len());
+                    self.start_plain_export(Some(names), scope);
+                }
                 _ => {}
             },
             ActivePage::SearchPasswordsListName => match input {
@@ -336,65 +448,32 @@ impl App {
                     self.state.active_page = ActivePage::SearchPasswordsList;
                 }
                 KeyCode::Ctrl('c') => {
-                    self.state.passwords_list_search_term = None;
+                    self.state.passwords_list_search_term.clear();
                     self.state.passwords_list_search = vec![];
+                    self.state.passwords_list_search_highlights = vec![];
                     self.state.active_page = ActivePage::PasswordsList;
                 }
-                KeyCode::Backspace => {
-                    let mut curr = self
-                        .state
-                        .passwords_list_search_term
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.pop();
-                    self.state.passwords_list_search_term = Some(curr);
-                    self.filter_passwords_list()?;
-                }
-                KeyCode::Char(char) => {
-                    let mut curr = self
-                        .state
-                        .passwords_list_search_term
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.push(char);
-                    self.state.passwords_list_search_term = Some(curr);
-                    self.filter_passwords_list()?;
-                }
-                _ => {}
+                _ => self.handle_text_input(input)?,
             },
             ActivePage::ExportPgpLocation => match input {
                 KeyCode::Char('\n') => {
                     // TODO add check for location validity
                     if !self.check_if_pgp_export_location_valid().await? {
                         self.state.export_pgp_secret_location_error = true;
+                        self.notify(
+                            NotificationLevel::Warning,
+                            "Export location can not be empty".to_string(),
+                        );
                     } else {
                         self.state.active_page = ActivePage::ExportPgpMasterPassword;
                     }
                 }
                 KeyCode::Ctrl('c') => {
-                    self.state.export_pgp_secret_master_password = None;
-                    self.state.export_pgp_secret_location = None;
+                    self.state.export_pgp_secret_master_password.clear();
+                    self.state.export_pgp_secret_location.clear();
                     self.state.active_page = ActivePage::PasswordsList;
                 }
-                KeyCode::Backspace => {
-                    let mut curr = self
-                        .state
-                        .export_pgp_secret_location
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.pop();
-                    self.state.export_pgp_secret_location = Some(curr);
-                }
-                KeyCode::Char(char) => {
-                    let mut curr = self
-                        .state
-                        .export_pgp_secret_location
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.push(char);
-                    self.state.export_pgp_secret_location = Some(curr);
-                }
-                _ => {}
+                _ => self.handle_text_input(input)?,
             },
             ActivePage::ExportPgpMasterPassword => match input {
                 KeyCode::Char('\n') => {
@@ -402,30 +481,374 @@ impl App {
                     self.state.active_page = ActivePage::PasswordsList;
                 }
                 KeyCode::Ctrl('c') => {
-                    self.state.export_pgp_secret_master_password = None;
-                    self.state.export_pgp_secret_location = None;
+                    self.state.export_pgp_secret_master_password.clear();
+                    self.state.export_pgp_secret_location.clear();
                     self.state.active_page = ActivePage::PasswordsList;
                 }
-                KeyCode::Backspace => {
-                    let mut curr = self
-                        .state
-                        .export_pgp_secret_master_password
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.pop();
-                    self.state.export_pgp_secret_master_password = Some(curr);
-                }
-                KeyCode::Char(char) => {
-                    let mut curr = self
-                        .state
-                        .export_pgp_secret_master_password
-                        .take()
-                        .unwrap_or_else(|| "".to_owned());
-                    curr.push(char);
-                    self.state.export_pgp_secret_master_password = Some(curr);
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::PlainExportLocation => match input {
+                KeyCode::Char('\n') if !self.state.plain_export_location.is_empty() => {
+                    self.state.active_page = ActivePage::PlainExportPassphrase;
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_plain_export();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::PlainExportPassphrase => match input {
+                KeyCode::Char('\n') => {
+                    let exported = self.export_plain().await;
+                    self.state.plain_export_passphrase.clear();
+                    exported?;
+                    self.cancel_plain_export();
+                }
+                KeyCode::BackTab => {
+                    self.state.active_page = ActivePage::PlainExportLocation;
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_plain_export();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::BackupLocation => match input {
+                KeyCode::Char('\n') if !self.state.backup_location.is_empty() => {
+                    self.state.active_page = ActivePage::BackupPassword;
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_backup();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::BackupPassword => match input {
+                KeyCode::Char('\n') => {
+                    let password = self.state.backup_password.text();
+                    if App::accept_new_secret(password, &mut self.state.backup_error) {
+                        self.state.backup_password_confirm.clear();
+                        self.state.active_page = ActivePage::BackupPasswordConfirm;
+                    }
+                }
+                KeyCode::BackTab => {
+                    self.state.backup_error = None;
+                    self.state.active_page = ActivePage::BackupLocation;
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_backup();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::BackupPasswordConfirm => match input {
+                KeyCode::Char('\n') => {
+                    if self.state.backup_password_confirm.text()
+                        != self.state.backup_password.text()
+                    {
+                        self.state.backup_error = Some("passwords do not match".to_string());
+                        return Ok(());
+                    }
+                    let written = self.write_backup().await;
+                    self.cancel_backup();
+                    written?;
+                }
+                KeyCode::BackTab => {
+                    self.state.backup_error = None;
+                    self.state.active_page = ActivePage::BackupPassword;
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_backup();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::NamespacesList => match input {
+                KeyCode::Down
+                    if self.state.active_namespace_record + 1 < self.state.namespaces.len() =>
+                {
+                    self.state.active_namespace_record += 1;
+                }
+                KeyCode::Up if self.state.active_namespace_record > 0 => {
+                    self.state.active_namespace_record -= 1;
+                }
+                KeyCode::Char('\n') => {
+                    let Some(selected) = self.selected_namespace() else {
+                        return Ok(());
+                    };
+                    if selected == self.state.active_namespace {
+                        self.state.active_page = ActivePage::PasswordsList;
+                    } else {
+                        self.state.namespace_passphrase_input.clear();
+                        self.state.namespace_error = None;
+                        self.state.active_page = ActivePage::UnlockNamespace;
+                    }
+                }
+                KeyCode::Char('a') => {
+                    self.state.namespace_name_input.clear();
+                    self.state.namespace_passphrase_input.clear();
+                    self.state.namespace_error = None;
+                    self.state.active_page = ActivePage::CreateNamespaceName;
+                }
+                KeyCode::Char('r') => {
+                    self.state.namespace_name_input =
+                        TextInput::new(self.selected_namespace().unwrap_or_default());
+                    self.state.namespace_error = None;
+                    self.state.active_page = ActivePage::RenameNamespace;
+                }
+                KeyCode::Char('d') => {
+                    if self.selected_namespace().as_ref() == Some(&self.state.active_namespace) {
+                        self.state.namespace_error =
+                            Some("Active namespace can not be deleted".to_string());
+                        return Ok(());
+                    }
+                    self.state.namespace_name_input.clear();
+                    self.state.namespace_error = None;
+                    self.state.active_page = ActivePage::DeleteNamespace;
+                }
+                KeyCode::Char('o') => {
+                    // Entries of other namespaces can not be read without unlocking them
+                    if self.selected_namespace().as_ref() != Some(&self.state.active_namespace) {
+                        self.state.namespace_error =
+                            Some("Only the active namespace can hide its file names".to_string());
+                        return Ok(());
+                    }
+                    self.toggle_opaque_names().await?;
+                }
+                KeyCode::Char('s') => {
+                    if let Some(selected) = self.selected_namespace() {
+                        Configuration::set_default_namespace(&self.paths, &selected)?;
+                        self.refresh_namespaces()?;
+                        self.notify(
+                            NotificationLevel::Success,
+                            format!("{} is now the default namespace", selected),
+                        );
+                    }
+                }
+                KeyCode::Esc | KeyCode::Ctrl('c') | KeyCode::Char('q') => {
+                    self.state.namespace_error = None;
+                    self.state.active_page = ActivePage::PasswordsList;
+                }
+                _ => {}
+            },
+            ActivePage::MessageLog => match input {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.scroll_message_log(1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.scroll_message_log(-1);
+                }
+                KeyCode::PageDown => {
+                    self.scroll_message_log(PAGE_SIZE as isize);
+                }
+                KeyCode::PageUp => {
+                    self.scroll_message_log(-(PAGE_SIZE as isize));
+                }
+                KeyCode::Esc | KeyCode::Ctrl('c') | KeyCode::Char('q') => {
+                    self.state.active_page = ActivePage::PasswordsList;
+                }
+                _ => {}
+            },
+            ActivePage::ImportLocation => match input {
+                KeyCode::Char('\n') => {
+                    self.read_import_file().await?;
+                    self.state.active_page = ActivePage::ImportPreview;
+                }
+                KeyCode::Ctrl('c') => {
+                    self.state.import_location.clear();
+                    self.state.active_page = ActivePage::PasswordsList;
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::ImportPreview => match input {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.scroll_import_preview(1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.scroll_import_preview(-1);
+                }
+                KeyCode::PageDown => {
+                    self.scroll_import_preview(PAGE_SIZE as isize);
+                }
+                KeyCode::PageUp => {
+                    self.scroll_import_preview(-(PAGE_SIZE as isize));
+                }
+                KeyCode::Char('c') => {
+                    self.import_policy = self.import_policy.next();
+                    self.refresh_import_preview().await?;
+                }
+                KeyCode::Char('\n') => {
+                    self.import_pending().await?;
+                    self.state.active_page = ActivePage::PasswordsList;
+                }
+                KeyCode::Esc | KeyCode::Ctrl('c') | KeyCode::Char('q') => {
+                    self.pending_import = None;
+                    self.state.import_preview = None;
+                    self.state.active_page = ActivePage::PasswordsList;
                 }
                 _ => {}
             },
+            ActivePage::Audit => match input {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.scroll_audit(1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.scroll_audit(-1);
+                }
+                KeyCode::PageDown => {
+                    self.scroll_audit(PAGE_SIZE as isize);
+                }
+                KeyCode::PageUp => {
+                    self.scroll_audit(-(PAGE_SIZE as isize));
+                }
+                KeyCode::Char('r') => {
+                    self.run_audit().await?;
+                }
+                KeyCode::Esc | KeyCode::Ctrl('c') | KeyCode::Char('q') => {
+                    self.state.audit_report = None;
+                    self.state.active_page = ActivePage::PasswordsList;
+                }
+                _ => {}
+            },
+            ActivePage::CreateNamespaceName => match input {
+                KeyCode::Char('\n') | KeyCode::Tab => {
+                    let name = self.state.namespace_name_input.text().to_string();
+                    if name.is_empty() || self.state.namespaces.contains(&name) {
+                        self.state.namespace_error =
+                            Some("name is empty or already taken".to_string());
+                    } else {
+                        self.state.namespace_error = None;
+                        self.state.active_page = ActivePage::CreateNamespacePassphrase;
+                    }
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_namespace_input();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::CreateNamespacePassphrase => match input {
+                KeyCode::BackTab => {
+                    self.state.namespace_error = None;
+                    self.state.active_page = ActivePage::CreateNamespaceName;
+                }
+                KeyCode::Char('\n') => {
+                    let passphrase = self.state.namespace_passphrase_input.text().trim_end();
+                    if App::accept_new_secret(passphrase, &mut self.state.namespace_error) {
+                        self.state.namespace_passphrase_confirm.clear();
+                        self.state.active_page = ActivePage::CreateNamespacePassphraseConfirm;
+                    }
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_namespace_input();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::CreateNamespacePassphraseConfirm => match input {
+                KeyCode::BackTab => {
+                    self.state.namespace_error = None;
+                    self.state.active_page = ActivePage::CreateNamespacePassphrase;
+                }
+                KeyCode::Char('\n') => {
+                    if let Err(err) = self.create_namespace().await {
+                        self.state.namespace_error = Some(err.to_string());
+                    }
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_namespace_input();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::RenameNamespace => match input {
+                KeyCode::Char('\n') => {
+                    if let Err(err) = self.rename_selected_namespace() {
+                        self.state.namespace_error = Some(err.to_string());
+                    }
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_namespace_input();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::DeleteNamespace => match input {
+                KeyCode::Char('\n') => {
+                    if let Err(err) = self.delete_selected_namespace() {
+                        self.state.namespace_error = Some(err.to_string());
+                    }
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_namespace_input();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+            ActivePage::UnlockNamespace => match input {
+                KeyCode::Char('\n') => {
+                    if let Err(err) = self.unlock_selected_namespace().await {
+                        self.state.namespace_error = Some(err.to_string());
+                    }
+                }
+                KeyCode::Ctrl('c') => {
+                    self.cancel_namespace_input();
+                }
+                _ => self.handle_text_input(input)?,
+            },
+        }
+        Ok(())
+    }
+
+    /// Input field edited on the active page, if the page has one.
+    fn active_input(&mut self) -> Option<&mut TextInput> {
+        match self.state.active_page {
+            ActivePage::CreateNewPasswordName | ActivePage::EditPasswordName => {
+                Some(&mut self.state.password_name_input)
+            }
+            ActivePage::CreateNewPasswordBody | ActivePage::EditPasswordBody => {
+                Some(&mut self.state.password_input)
+            }
+            ActivePage::CreateNewPasswordDetails | ActivePage::EditPasswordDetails => {
+                Some(match self.state.entry_details_field {
+                    DetailsField::Username => &mut self.state.entry_username_input,
+                    DetailsField::Url => &mut self.state.entry_url_input,
+                    DetailsField::Tags => &mut self.state.entry_tags_input,
+                    DetailsField::Otp => &mut self.state.entry_otp_input,
+                    DetailsField::Notes => &mut self.state.entry_notes_input,
+                })
+            }
+            ActivePage::SearchPasswordsListName => Some(&mut self.state.passwords_list_search_term),
+            ActivePage::ExportPgpLocation => Some(&mut self.state.export_pgp_secret_location),
+            ActivePage::ImportLocation => Some(&mut self.state.import_location),
+            ActivePage::PlainExportLocation => Some(&mut self.state.plain_export_location),
+            ActivePage::PlainExportPassphrase => Some(&mut self.state.plain_export_passphrase),
+            ActivePage::BackupLocation => Some(&mut self.state.backup_location),
+            ActivePage::BackupPassword => Some(&mut self.state.backup_password),
+            ActivePage::BackupPasswordConfirm => Some(&mut self.state.backup_password_confirm),
+            ActivePage::ExportPgpMasterPassword => {
+                Some(&mut self.state.export_pgp_secret_master_password)
+            }
+            ActivePage::CreateNamespaceName
+            | ActivePage::RenameNamespace
+            | ActivePage::DeleteNamespace => Some(&mut self.state.namespace_name_input),
+            ActivePage::CreateNamespacePassphrase | ActivePage::UnlockNamespace => {
+                Some(&mut self.state.namespace_passphrase_input)
+            }
+            ActivePage::CreateNamespacePassphraseConfirm => {
+                Some(&mut self.state.namespace_passphrase_confirm)
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_text_input(&mut self, input: KeyCode) -> Result<()> {
+        let changed = self
+            .active_input()
+            .map(|field| field.handle_key(input))
+            .unwrap_or(false);
+        if changed && self.state.active_page == ActivePage::SearchPasswordsListName {
+            self.filter_passwords_list()?;
+        }
+        Ok(())
+    }
+
+    fn handle_paste(&mut self, text: String) -> Result<()> {
+        if let Some(field) = self.active_input() {
+            field.paste(&text);
+            if self.state.active_page == ActivePage::SearchPasswordsListName {
+                self.filter_passwords_list()?;
+            }
         }
         Ok(())
     }
@@ -434,56 +857,313 @@ impl App {
         let el = self.event_loop.take().unwrap();
         // Handle state update
         let mut ui = self.ui.take().unwrap();
-        let _ = join!(el.run(), self.run_ui(&mut ui));
+        let tr_terminate_event_loop = self.tr_terminate_event_loop.clone();
+        let (_, result) = join!(el.run(), async {
+            let result = self.run_ui(&mut ui).await;
+            // Stop the event loop if the UI loop failed
+            let _ = tr_terminate_event_loop.send(());
+            result
+        });
         ui.shutdown_terminal();
+        if let Err(err) = result {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
         process::exit(0);
     }
 
     fn filter_passwords_list(&mut self) -> Result<()> {
-        let term = &self
+        let hits = search(
+            &self.state.passwords_list,
+            self.state.passwords_list_search_term.text(),
+            &self.state.recently_used,
+        );
+        self.state.passwords_list_search = hits
+            .iter()
+            .map(|hit| self.state.passwords_list[hit.index].clone())
+            .collect();
+        self.state.passwords_list_search_highlights =
+            hits.into_iter().map(|hit| hit.positions).collect();
+        self.state.active_password_record_search = self
             .state
-            .passwords_list_search_term
-            .clone()
-            .unwrap_or_else(|| "".to_owned())
-            .to_lowercase();
-        self.state.passwords_list_search = self
+            .active_password_record_search
+            .min(self.state.passwords_list_search.len().saturating_sub(1));
+        Ok(())
+    }
+
+    async fn save_password_inputs(&mut self) -> Result<()> {
+        let pass_name = self.state.password_name_input.text().to_string();
+        if pass_name.is_empty() {
+            self.notify(
+                NotificationLevel::Warning,
+                "Password name can not be empty".to_string(),
+            );
+            return Ok(());
+        }
+        let otp = self.state.entry_otp();
+        if let Some(Err(err)) = otp.as_deref().map(Otp::parse) {
+            self.notify(NotificationLevel::Warning, err.to_string());
+            return Ok(());
+        }
+        let mut entry = Entry::new(
+            self.state.entry_kind,
+            self.state.password_input.text().to_string(),
+        );
+        entry.otp = otp;
+        entry.notes = self.state.entry_notes();
+        entry.metadata = self.state.entry_metadata_inputs();
+        self.save_password(&pass_name, &mut entry).await?;
+        self.should_refresh_passwords = true;
+        self.cancel_entry_form();
+        self.notify(NotificationLevel::Success, format!("Saved {}", pass_name));
+        Ok(())
+    }
+
+    /// Moves the selection of the focused passwords list. Returns true if the key was handled.
+    fn handle_list_navigation(&mut self, input: KeyCode) -> bool {
+        let (selected, len) = match self.state.active_page {
+            ActivePage::PasswordsList => {
+                if self.handle_jump_input(input) {
+                    return true;
+                }
+                (
+                    &mut self.state.active_password_record,
+                    self.state.passwords_list.len(),
+                )
+            }
+            ActivePage::SearchPasswordsList => (
+                &mut self.state.active_password_record_search,
+                self.state.passwords_list_search.len(),
+            ),
+            _ => return false,
+        };
+        match navigate(*selected, len, input) {
+            Some(next) => {
+                *selected = next;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Type-ahead: typed characters jump to the first name starting with them.
+    /// Any other key leaves type-ahead and is handled by the list as usual.
+    fn handle_jump_input(&mut self, input: KeyCode) -> bool {
+        let Some(jump) = self.state.passwords_list_jump.as_mut() else {
+            return false;
+        };
+        match input {
+            KeyCode::Char('\n') | KeyCode::Esc => {
+                self.state.passwords_list_jump = None;
+                return true;
+            }
+            KeyCode::Backspace => {
+                jump.pop();
+            }
+            KeyCode::Char(c) => jump.push(c),
+            _ => {
+                self.state.passwords_list_jump = None;
+                return false;
+            }
+        }
+        if let Some(index) = find_by_prefix(&self.state.passwords_list, jump) {
+            self.state.active_password_record = index;
+        }
+        true
+    }
+
+    fn start_new_entry(&mut self, kind: EntryKind) {
+        self.state
+            .fill_entry_form("", &Entry::new(kind, String::new()));
+        self.state.active_page = ActivePage::CreateNewPasswordName;
+    }
+
+    /// Leaves the entry form, dropping whatever was typed.
+    fn cancel_entry_form(&mut self) {
+        self.state
+            .fill_entry_form("", &Entry::new(EntryKind::Password, String::new()));
+        self.state.active_page = ActivePage::PasswordsList;
+    }
+
+    async fn toggle_selected_entry_reveal(&mut self) -> Result<()> {
+        if self.state.revealed_entry.take().is_some() {
+            return Ok(());
+        }
+        let name = self
             .state
             .passwords_list
-            .clone()
-            .iter()
-            .filter_map(|p| {
-                if !term.is_empty() && !p.name.to_lowercase().contains(term) {
-                    return None;
-                };
-                Some(p.clone())
-            })
-            .collect();
-        let (len, _) = self.state.passwords_list_search.len().overflowing_sub(1);
-        if self.state.active_password_record_search > len {
-            self.state.active_password_record_search = len;
+            .get(self.state.active_password_record)
+            .ok_or(Error::NothingSelected)?
+            .name
+            .clone();
+        let entry = self.read_entry(&name).await?;
+        self.state.mark_used(&name);
+        self.state.revealed_entry = Some((name, entry));
+        Ok(())
+    }
+
+    async fn read_entry(&mut self, name: &str) -> Result<Entry> {
+        let index = self.take_index().await?;
+        let entry = read_entry(&self.signer, &self.passwords_dir, &index, name).await;
+        self.index = Some(index);
+        entry
+    }
+
+    /// Takes the metadata index of the active namespace, loading it on first use.
+    /// Callers put it back into `self.index` when they are done.
+    async fn take_index(&mut self) -> Result<Index> {
+        if let Some(index) = self.index.take() {
+            return Ok(index);
+        }
+        let (mut index, updated) = load_index(&self.signer, &self.passwords_dir).await?;
+        if updated {
+            self.state.log(
+                NotificationLevel::Warning,
+                "Metadata index was updated from the entries on disk".to_string(),
+            );
+        }
+        if index.needs_file_name_migration(self.opaque_names) {
+            self.migrate_file_names(&mut index).await?;
+        }
+        Ok(index)
+    }
+
+    /// Moves entries to the file names the namespace is configured for.
+    async fn migrate_file_names(&mut self, index: &mut Index) -> Result<()> {
+        let moved =
+            migrate_file_names(&self.signer, &self.passwords_dir, index, self.opaque_names).await?;
+        let message = match self.opaque_names {
+            true => format!("Moved {} entries to random file names", moved),
+            false => format!("Moved {} entries to files named after them", moved),
+        };
+        self.notify(NotificationLevel::Success, message);
+        Ok(())
+    }
+
+    async fn refresh_passwords(&mut self) -> Result<()> {
+        let index = self.take_index().await?;
+        let passwords = list_entries(&self.passwords_dir, &index).await;
+        let checked = match self.breached.is_none() && self.breach_list.is_some() {
+            true => self.check_breaches(&index).await,
+            false => Ok(()),
+        };
+        self.index = Some(index);
+        let mut passwords = passwords?;
+        if let Some(breached) = &self.breached {
+            for password in passwords.iter_mut() {
+                password.breached = breached.get(&password.name).copied();
+            }
+        }
+        self.state.passwords_list = passwords;
+        checked
+    }
+
+    /// Looks every password of the namespace up in the breach list.
+    async fn check_breaches(&mut self, index: &Index) -> Result<()> {
+        let breach_list = match self.breach_list.as_mut() {
+            Some(breach_list) => breach_list,
+            None => return Ok(()),
+        };
+        let mut breached = HashMap::new();
+        let mut checked = 0;
+        for password in list_entries(&self.passwords_dir, index).await? {
+            // Unreadable entries are reported by the audit
+            if let Ok(entry) =
+                read_entry(&self.signer, &self.passwords_dir, index, &password.name).await
+            {
+                checked += 1;
+                if let Some(count) = breached_count(breach_list, &entry)? {
+                    breached.insert(password.name, count);
+                }
+            }
         }
+        let level = match breached.is_empty() {
+            true => NotificationLevel::Success,
+            false => NotificationLevel::Warning,
+        };
+        let message = format!(
+            "Checked {} entries against the breach list, {} breached",
+            checked,
+            breached.len()
+        );
+        self.breached = Some(breached);
+        self.notify(level, message);
         Ok(())
     }
 
-    async fn save_password(&self, name: String, text: String) -> Result<()> {
-        let encryped = self.signer.encrypt(text.as_bytes())?;
-        save_to_file(&encryped, &self.passwords_dir.join(name))
-            .await
-            .expect("Expect to save");
+    /// Keeps the breach flag of a saved entry current.
+    fn recheck_breach(&mut self, name: &str, entry: &Entry) -> Result<()> {
+        if let (Some(breach_list), Some(breached)) =
+            (self.breach_list.as_mut(), self.breached.as_mut())
+        {
+            match breached_count(breach_list, entry)? {
+                Some(count) => breached.insert(name.to_string(), count),
+                None => breached.remove(name),
+            };
+        }
         Ok(())
     }
 
+    async fn save_password(&mut self, name: &str, entry: &mut Entry) -> Result<()> {
+        let mut index = self.take_index().await?;
+        let result = save_entry(
+            &self.signer,
+            &self.passwords_dir,
+            &mut index,
+            name,
+            entry,
+            self.opaque_names,
+        )
+        .await;
+        self.index = Some(index);
+        result?;
+        self.recheck_breach(name, entry)
+    }
+
+    /// Switches the active namespace between random and readable file names
+    /// and moves its entries right away. The setting is restored when they
+    /// can not be moved.
+    async fn toggle_opaque_names(&mut self) -> Result<()> {
+        let previous = self.opaque_names;
+        let mut index = self.take_index().await?;
+        Configuration::set_opaque_names(&self.paths, &self.state.active_namespace, !previous)?;
+        self.opaque_names = !previous;
+        let migrated = self.migrate_file_names(&mut index).await;
+        self.index = Some(index);
+        self.should_refresh_passwords = true;
+        if let Err(err) = migrated {
+            self.opaque_names = previous;
+            Configuration::set_opaque_names(&self.paths, &self.state.active_namespace, previous)?;
+            return Err(err);
+        }
+        let message = match self.opaque_names {
+            true => "Entry names are now hidden on disk",
+            false => "Entry files are now named after the entries",
+        };
+        self.notify(NotificationLevel::Success, message.to_string());
+        Ok(())
+    }
+
+    async fn delete_password(&mut self, name: &str) -> Result<()> {
+        let mut index = self.take_index().await?;
+        let result = delete_entry(&self.signer, &self.passwords_dir, &mut index, name).await;
+        self.index = Some(index);
+        if let Some(breached) = self.breached.as_mut() {
+            breached.remove(name);
+        }
+        result
+    }
+
     async fn fill_selected_password_for_editing(&mut self) -> Result<()> {
         let pass = self
             .state
             .passwords_list
             .get(self.state.active_password_record)
-            .unwrap();
-        let pass_bytes = read_password_bytes(&self.passwords_dir.join(&pass.name)).await?;
-        let decrypted = self.signer.decrypt(&pass_bytes)?;
-        let plain = String::from_utf8(decrypted).unwrap();
-        self.state.password_name_input = Some(pass.name.clone());
-        self.state.password_input = Some(plain);
+            .ok_or(Error::NothingSelected)?;
+        let name = pass.name.clone();
+        let entry = self.read_entry(&name).await?;
+        self.state.mark_used(&name);
+        self.state.fill_entry_form(&name, &entry);
         Ok(())
     }
 
@@ -492,11 +1172,21 @@ impl App {
             .state
             .passwords_list_search
             .get(self.state.active_password_record_search)
-            .unwrap();
-        delete_password(&self.passwords_dir.join(&pass.name)).await?;
-        self.state
+            .ok_or(Error::NothingSelected)?;
+        let name = pass.name.clone();
+        self.delete_password(&name).await?;
+        let removed = self
+            .state
             .passwords_list_search
             .remove(self.state.active_password_record_search);
+        self.state
+            .passwords_list_search_highlights
+            .remove(self.state.active_password_record_search);
+        self.should_refresh_passwords = true;
+        self.notify(
+            NotificationLevel::Success,
+            format!("Deleted {}", removed.name),
+        );
         Ok(())
     }
 
@@ -505,70 +1195,441 @@ impl App {
             .state
             .passwords_list
             .get(self.state.active_password_record)
-            .unwrap();
-        delete_password(&self.passwords_dir.join(&pass.name)).await?;
-        self.state
+            .ok_or(Error::NothingSelected)?;
+        let name = pass.name.clone();
+        self.delete_password(&name).await?;
+        let removed = self
+            .state
             .passwords_list
             .remove(self.state.active_password_record);
+        self.notify(
+            NotificationLevel::Success,
+            format!("Deleted {}", removed.name),
+        );
         Ok(())
     }
 
-    async fn copy_selected_password_to_clipboard_search(&self) -> Result<()> {
+    async fn copy_selected_password_to_clipboard_search(&mut self) -> Result<()> {
         let pass = self
             .state
             .passwords_list_search
             .get(self.state.active_password_record_search)
-            .unwrap();
-        let pass_bytes = read_password_bytes(&self.passwords_dir.join(&pass.name)).await?;
-        let decrypted = self.signer.decrypt(&pass_bytes)?;
-        let mut ctx = ClipboardContext::new().unwrap();
-        let plain = String::from_utf8(decrypted).unwrap();
-        ctx.set_contents(plain).unwrap();
+            .ok_or(Error::NothingSelected)?;
+        let name = pass.name.clone();
+        let entry = self.read_entry(&name).await?;
+        copy_to_clipboard(entry.body.expose().to_string())?;
+        self.state.mark_used(&name);
+        let message = format!("Copied {} to clipboard", name);
+        self.notify(NotificationLevel::Success, message);
         Ok(())
     }
 
-    async fn copy_selected_password_to_clipboard(&self) -> Result<()> {
+    async fn copy_selected_password_to_clipboard(&mut self) -> Result<()> {
         let pass = self
             .state
             .passwords_list
             .get(self.state.active_password_record)
-            .unwrap();
-        let pass_bytes = read_password_bytes(&self.passwords_dir.join(&pass.name)).await?;
-        let decrypted = self.signer.decrypt(&pass_bytes)?;
-        let mut ctx = ClipboardContext::new().unwrap();
-        let plain = String::from_utf8(decrypted).unwrap();
-        ctx.set_contents(plain).unwrap();
+            .ok_or(Error::NothingSelected)?;
+        let name = pass.name.clone();
+        let entry = self.read_entry(&name).await?;
+        copy_to_clipboard(entry.body.expose().to_string())?;
+        self.state.mark_used(&name);
+        let message = format!("Copied {} to clipboard", name);
+        self.notify(NotificationLevel::Success, message);
         Ok(())
     }
 
-    async fn check_if_pgp_export_location_valid(&self) -> Result<bool> {
-        if let Some(loc) = &self.state.export_pgp_secret_location {
-            if loc.is_empty() {
-                return Ok(false);
-            }
+    /// Code of the revealed entry's one-time password, if it has one.
+    fn revealed_otp_code(&mut self) -> Option<OtpCode> {
+        let otp = self
+            .state
+            .selected_revealed_entry()
+            .and_then(|entry| entry.otp.as_deref());
+        self.otp_code.get(otp, unix_time())
+    }
+
+    async fn copy_otp_code(&mut self, name: &str) -> Result<()> {
+        let mut index = self.take_index().await?;
+        let code = next_code(
+            &self.signer,
+            &self.passwords_dir,
+            &mut index,
+            name,
+            self.opaque_names,
+        )
+        .await;
+        self.index = Some(index);
+        let code = code?;
+        let revealed = self.state.revealed_entry.as_ref();
+        if code.remaining.is_none() && revealed.is_some_and(|(revealed, _)| revealed == name) {
+            // The counter moved on, show the next code
+            let entry = self.read_entry(name).await?;
+            self.state.revealed_entry = Some((name.to_string(), entry));
         }
-        Ok(true)
+        copy_to_clipboard(code.code)?;
+        self.state.mark_used(name);
+        let message = match code.remaining {
+            Some(remaining) => format!("Copied code of {}, valid for {}s", name, remaining),
+            None => format!("Copied code of {}", name),
+        };
+        self.notify(NotificationLevel::Success, message);
+        Ok(())
     }
 
-    async fn export_pgp_private_key(&self) -> Result<()> {
+    async fn check_if_pgp_export_location_valid(&self) -> Result<bool> {
+        Ok(!self.state.export_pgp_secret_location.is_empty())
+    }
+
+    async fn export_pgp_private_key(&mut self) -> Result<()> {
         debug!("Exporting pgp key");
+        let location = PathBuf::from(self.state.export_pgp_secret_location.text());
         export_private_key(
             &self.signer,
             self.state
                 .export_pgp_secret_master_password
-                .as_ref()
-                .unwrap_or(&String::default())
+                .text()
                 .to_string(),
-            PathBuf::from_str(
-                self.state
-                    .export_pgp_secret_location
-                    .as_ref()
-                    .unwrap_or(&String::default()),
-            )?,
+            location.clone(),
         )
-        .await
-        .unwrap();
+        .await?;
         debug!("Exported pgp key");
+        self.notify(
+            NotificationLevel::Success,
+            format!("Exported secret key to {}", location.display()),
+        );
+        Ok(())
+    }
+
+    fn start_plain_export(&mut self, names: Option<Vec<String>>, scope: String) {
+        self.plain_export_names = names;
+        self.state.plain_export_scope = scope;
+        self.state.plain_export_location.clear();
+        self.state.plain_export_passphrase.clear();
+        self.state.active_page = ActivePage::PlainExportLocation;
+    }
+
+    fn cancel_plain_export(&mut self) {
+        self.plain_export_names = None;
+        self.state.plain_export_location.clear();
+        self.state.plain_export_passphrase.clear();
+        self.state.active_page = ActivePage::PasswordsList;
+    }
+
+    /// Writes the picked entries in plain text once the passphrase is typed again.
+    async fn export_plain(&mut self) -> Result<()> {
+        let path = PathBuf::from(self.state.plain_export_location.text().trim());
+        let export = PlainExport {
+            path: &path,
+            format: PlainFormat::from_path(&path),
+            recipient: None,
+        };
+        let index = self.take_index().await?;
+        let exported = export_entries(
+            &self.signer,
+            &self.passwords_dir,
+            &index,
+            self.plain_export_names.as_deref(),
+            self.state.plain_export_passphrase.text(),
+            &export,
+        )
+        .await;
+        self.index = Some(index);
+        let message = format!(
+            "Exported {} entries in plain text to {}, delete it once handed over",
+            exported?,
+            path.display()
+        );
+        self.notify(NotificationLevel::Warning, message);
+        Ok(())
+    }
+
+    /// Backs the active namespace up to the typed location and checks the
+    /// written file.
+    async fn write_backup(&mut self) -> Result<()> {
+        let namespace_config =
+            Configuration::read_namespace_config(&self.paths, &self.state.active_namespace)?;
+        let location = PathBuf::from(self.state.backup_location.text().trim());
+        let summary = write_backup(
+            &namespace_config,
+            self.state.backup_password.text(),
+            &location,
+        )
+        .await?;
+        self.state
+            .log(NotificationLevel::Success, summary.to_string());
+        self.notify(
+            NotificationLevel::Success,
+            format!("Backup written to {} and verified", location.display()),
+        );
+        Ok(())
+    }
+
+    fn cancel_backup(&mut self) {
+        self.state.backup_location.clear();
+        self.state.backup_password.clear();
+        self.state.backup_password_confirm.clear();
+        self.state.backup_error = None;
+        self.state.active_page = ActivePage::PasswordsList;
+    }
+
+    fn notify(&mut self, level: NotificationLevel, message: String) {
+        self.state.notify(level, message, NOTIFICATION_TICKS);
+    }
+
+    fn show_error(&mut self, err: Error) {
+        error!("{}", err);
+        self.state.log(NotificationLevel::Error, err.to_string());
+        self.state.error = Some(err.to_string());
+    }
+
+    fn scroll_message_log(&mut self, delta: isize) {
+        let max = self.state.message_log.len().saturating_sub(1);
+        self.state.message_log_scroll = self
+            .state
+            .message_log_scroll
+            .saturating_add_signed(delta)
+            .min(max);
+    }
+
+    fn scroll_audit(&mut self, delta: isize) {
+        let max = self
+            .state
+            .audit_report
+            .as_ref()
+            .map(|report| report.to_string().lines().count())
+            .unwrap_or_default();
+        self.state.audit_scroll = self
+            .state
+            .audit_scroll
+            .saturating_add_signed(delta)
+            .min(max);
+    }
+
+    async fn run_audit(&mut self) -> Result<()> {
+        let index = self.take_index().await?;
+        let report = audit_vault(
+            &self.signer,
+            &self.passwords_dir,
+            &index,
+            self.breach_list.as_mut(),
+        )
+        .await;
+        self.index = Some(index);
+        let report = report?;
+        self.notify(NotificationLevel::Success, report.summary());
+        self.state.audit_report = Some(report);
+        self.state.audit_scroll = 0;
+        Ok(())
+    }
+
+    fn scroll_import_preview(&mut self, delta: isize) {
+        let max = self
+            .state
+            .import_preview
+            .as_ref()
+            .map(|preview| preview.lines())
+            .unwrap_or_default();
+        self.state.import_preview_scroll = self
+            .state
+            .import_preview_scroll
+            .saturating_add_signed(delta)
+            .min(max);
+    }
+
+    /// Reads the export file typed in, keeping it in memory for the preview.
+    async fn read_import_file(&mut self) -> Result<()> {
+        let path = self.state.import_location.text().trim().to_string();
+        if path.is_empty() {
+            return Err(Error::InvalidInput(
+                "Export file can not be empty".to_string(),
+            ));
+        }
+        let data = tokio::fs::read(&path).await?;
+        self.pending_import = Some(read_export(&data)?);
+        self.import_policy = ConflictPolicy::default();
+        self.state.import_preview_scroll = 0;
+        self.refresh_import_preview().await
+    }
+
+    /// Plans the pending import against the names in the namespace.
+    async fn refresh_import_preview(&mut self) -> Result<()> {
+        let index = self.take_index().await?;
+        self.state.import_preview = self.pending_import.as_ref().map(|(format, imported)| {
+            preview_import(*format, imported, &index, self.import_policy)
+        });
+        self.index = Some(index);
+        Ok(())
+    }
+
+    async fn import_pending(&mut self) -> Result<()> {
+        self.state.import_preview = None;
+        let Some((_, imported)) = self.pending_import.take() else {
+            return Ok(());
+        };
+        let mut index = self.take_index().await?;
+        let report = import_entries(
+            &self.signer,
+            &self.passwords_dir,
+            &mut index,
+            imported,
+            self.import_policy,
+            self.opaque_names,
+            false,
+        )
+        .await;
+        self.index = Some(index);
+        let report = report?;
+        for (name, reason) in &report.skipped {
+            self.state.log(
+                NotificationLevel::Warning,
+                format!("Skipped {}: {}", name, reason),
+            );
+        }
+        self.notify(NotificationLevel::Success, report.summary());
+        // Imported passwords are checked against the breach list too
+        self.breached = None;
+        self.should_refresh_passwords = true;
+        Ok(())
+    }
+
+    fn refresh_namespaces(&mut self) -> Result<()> {
+        let config = Configuration::read(&self.paths)?;
+        self.state.namespaces = config.namespaces().clone();
+        self.state.default_namespace = config.default_namespace().to_string();
+        if self.state.active_namespace_record >= self.state.namespaces.len() {
+            self.state.active_namespace_record = self.state.namespaces.len().saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    fn selected_namespace(&self) -> Option<String> {
+        self.state
+            .namespaces
+            .get(self.state.active_namespace_record)
+            .cloned()
+    }
+
+    fn cancel_namespace_input(&mut self) {
+        self.state.namespace_name_input.clear();
+        self.state.namespace_passphrase_input.clear();
+        self.state.namespace_passphrase_confirm.clear();
+        self.state.namespace_error = None;
+        self.state.active_page = ActivePage::NamespacesList;
+    }
+
+    fn switch_namespace(&mut self, namespace_config: NamespaceConfig, signer: Signer) {
+        self.signer = signer;
+        self.export_pgp_secret_file_path = App::get_export_pgp_secret_file_path(&namespace_config);
+        self.passwords_dir = namespace_config.passwords_dir;
+        self.opaque_names = namespace_config.opaque_names;
+        self.index = None;
+        self.breached = None;
+        self.pending_import = None;
+        self.state.active_namespace = namespace_config.name;
+        self.state.passwords_list = vec![];
+        self.state.active_password_record = 0;
+        self.state.namespace_name_input.clear();
+        self.state.namespace_passphrase_input.clear();
+        self.state.namespace_error = None;
+        self.should_refresh_passwords = true;
+        self.state.active_page = ActivePage::PasswordsList;
+        let message = format!("Switched to namespace {}", self.state.active_namespace);
+        self.notify(NotificationLevel::Success, message);
+    }
+
+    async fn create_namespace(&mut self) -> Result<()> {
+        let name = self.state.namespace_name_input.text().to_string();
+        if self.state.namespace_passphrase_confirm.text()
+            != self.state.namespace_passphrase_input.text()
+        {
+            return Err(Error::InvalidInput("Passphrases do not match".to_string()));
+        }
+        let passphrase = normalize_passphrase(SecretString::new(
+            self.state.namespace_passphrase_input.text().to_string(),
+        ));
+        if passphrase.is_empty() {
+            return Err(Error::InvalidInput(
+                "Passphrase can not be empty".to_string(),
+            ));
+        }
+        let namespace_config = Configuration::create_namespace(&self.paths, &name)?;
+        let ssk = create_signed_key(
+            namespace_config.private_key_path.as_ref(),
+            passphrase.expose().to_string(),
+        )
+        .await?;
+        self.refresh_namespaces()?;
+        self.notify(
+            NotificationLevel::Success,
+            format!("Created namespace {}", name),
+        );
+        self.state.namespace_passphrase_confirm.clear();
+        self.switch_namespace(namespace_config, Signer::new(ssk, Some(passphrase)));
+        Ok(())
+    }
+
+    /// Checks a new passphrase or password before it is typed again. One
+    /// that is easy to guess is only taken when Enter is pressed again on
+    /// the warning.
+    fn accept_new_secret(secret: &str, error: &mut Option<String>) -> bool {
+        if secret.is_empty() {
+            *error = Some("can not be empty".to_string());
+            return false;
+        }
+        let strength = estimate(secret);
+        if strength.is_weak() && error.as_deref() != Some(WEAK_SECRET_WARNING) {
+            *error = Some(WEAK_SECRET_WARNING.to_string());
+            return false;
+        }
+        *error = None;
+        true
+    }
+
+    fn rename_selected_namespace(&mut self) -> Result<()> {
+        let selected = self.selected_namespace().ok_or(Error::NothingSelected)?;
+        let new_name = self.state.namespace_name_input.text().to_string();
+        if new_name != selected {
+            let namespace_config =
+                Configuration::rename_namespace(&self.paths, &selected, &new_name)?;
+            if self.state.active_namespace == selected {
+                self.export_pgp_secret_file_path =
+                    App::get_export_pgp_secret_file_path(&namespace_config);
+                self.passwords_dir = namespace_config.passwords_dir;
+                self.state.active_namespace = new_name.clone();
+            }
+            self.notify(
+                NotificationLevel::Success,
+                format!("Renamed namespace {} to {}", selected, new_name),
+            );
+        }
+        self.refresh_namespaces()?;
+        self.cancel_namespace_input();
+        Ok(())
+    }
+
+    fn delete_selected_namespace(&mut self) -> Result<()> {
+        let selected = self.selected_namespace().ok_or(Error::NothingSelected)?;
+        if self.state.namespace_name_input.text() != selected {
+            return Err(Error::InvalidInput(format!("type {} to confirm", selected)));
+        }
+        Configuration::delete_namespace(&self.paths, &selected)?;
+        self.refresh_namespaces()?;
+        self.notify(
+            NotificationLevel::Success,
+            format!("Deleted namespace {}", selected),
+        );
+        self.cancel_namespace_input();
+        Ok(())
+    }
+
+    async fn unlock_selected_namespace(&mut self) -> Result<()> {
+        let selected = self.selected_namespace().ok_or(Error::NothingSelected)?;
+        let namespace_config = Configuration::read_namespace_config(&self.paths, &selected)?;
+        let passphrase = normalize_passphrase(SecretString::new(
+            self.state.namespace_passphrase_input.text().to_string(),
+        ));
+        let signer = unlock_signer(namespace_config.private_key_path.as_ref(), passphrase).await?;
+        self.switch_namespace(namespace_config, signer);
         Ok(())
     }
 }
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, path::Path};
+
+use chrono::{DateTime, Local};
+use crypto::{hash::sha256, signer::Signer};
+use shared::{
+    audit::AuditReport,
+    error::Result,
+    password::{Entry, EntryKind, Password},
+    strength::estimate,
+};
+
+use crate::{
+    breach::BreachList,
+    files::{list_entries, read_entry},
+    index::Index,
+};
+
+/// Passwords unchanged for longer are reported as old.
+const MAX_AGE_DAYS: i64 = 365;
+
+/// Collects findings entry by entry. Passwords are only kept as hashes, to
+/// find reused ones, and are dropped as soon as an entry is reviewed.
+pub struct Auditor {
+    now: DateTime<Local>,
+    report: AuditReport,
+    fingerprints: HashMap<[u8; 32], Vec<String>>,
+}
+
+impl Auditor {
+    pub fn new(now: DateTime<Local>) -> Self {
+        Self {
+            now,
+            report: AuditReport::default(),
+            fingerprints: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, password: &Password, entry: &Entry) {
+        self.report.entries += 1;
+        // Notes and entries that only hold a one-time password have nothing to rate
+        if entry.kind != EntryKind::Password || entry.body.is_empty() {
+            return;
+        }
+        self.report.passwords += 1;
+        let name = &password.name;
+
+        let strength = estimate(entry.body.expose());
+        if strength.is_weak() {
+            self.report.weak.push((name.clone(), strength));
+        }
+        self.fingerprints
+            .entry(sha256(entry.body.expose().as_bytes()))
+            .or_default()
+            .push(name.clone());
+        if let Some(modified) = password.modified {
+            let age = (self.now - modified).num_days();
+            if age > MAX_AGE_DAYS {
+                self.report.old.push((name.clone(), age));
+            }
+        }
+
+        let mut missing = vec![];
+        if entry.metadata.username.is_none() {
+            missing.push("username");
+        }
+        if entry.metadata.url.is_none() {
+            missing.push("url");
+        }
+        if !missing.is_empty() {
+            self.report.missing_metadata.push((name.clone(), missing));
+        }
+    }
+
+    /// Flags a password found in a breach list.
+    pub fn add_breached(&mut self, name: &str, count: u64) {
+        self.report.breached.push((name.to_string(), count));
+    }
+
+    pub fn add_unreadable(&mut self, name: &str) {
+        self.report.entries += 1;
+        self.report.unreadable.push(name.to_string());
+    }
+
+    pub fn finish(mut self) -> AuditReport {
+        let mut reused: Vec<Vec<String>> = self
+            .fingerprints
+            .into_values()
+            .filter(|names| names.len() > 1)
+            .collect();
+        reused.sort();
+        self.report.reused = reused;
+        self.report
+    }
+}
+
+/// Decrypts every entry of a namespace and reviews it, looking passwords up
+/// in the breach list when one is given.
+pub async fn audit_vault(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &Index,
+    mut breach_list: Option<&mut BreachList>,
+) -> Result<AuditReport> {
+    let mut auditor = Auditor::new(Local::now());
+    for password in list_entries(passwords_dir, index).await? {
+        let entry = match read_entry(signer, passwords_dir, index, &password.name).await {
+            Ok(entry) => entry,
+            Err(_) => {
+                auditor.add_unreadable(&password.name);
+                continue;
+            }
+        };
+        auditor.add(&password, &entry);
+        if let Some(breach_list) = breach_list.as_deref_mut() {
+            if let Some(count) = breached_count(breach_list, &entry)? {
+                auditor.add_breached(&password.name, count);
+            }
+        }
+    }
+    Ok(auditor.finish())
+}
+
+/// How often the password of an entry was seen in breaches. Notes are not looked up.
+pub fn breached_count(breach_list: &mut BreachList, entry: &Entry) -> Result<Option<u64>> {
+    if entry.kind != EntryKind::Password || entry.body.is_empty() {
+        return Ok(None);
+    }
+    breach_list.count(entry.body.expose())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local};
+    use shared::password::{Entry, EntryKind, Password};
+
+    use super::Auditor;
+
+    #[test]
+    fn reviews_entries() {
+        let now = Local::now();
+        let mut auditor = Auditor::new(now);
+        let mut add = |name: &str, body: &str, days: i64| {
+            let password = Password {
+                name: name.to_string(),
+                modified: Some(now - Duration::days(days)),
+                ..Password::default()
+            };
+            let mut entry = Entry::new(EntryKind::Password, body.to_string());
+            entry.metadata.username = Some("me".to_string());
+            entry.metadata.url = (name != "bank").then(|| "https://example.com".to_string());
+            auditor.add(&password, &entry);
+        };
+        add("mail", "letmein", 3);
+        add("bank", "k7#Qm!2vXz9$Lp4w", 400);
+        add("shop", "k7#Qm!2vXz9$Lp4w", 10);
+        auditor.add(
+            &Password::default(),
+            &Entry::new(EntryKind::Note, "1234".to_string()),
+        );
+
+        let report = auditor.finish();
+        assert_eq!((report.entries, report.passwords), (4, 3));
+        assert_eq!(report.weak.len(), 1);
+        assert_eq!(report.weak[0].0, "mail");
+        assert_eq!(report.reused, vec![vec!["bank", "shop"]]);
+        assert_eq!(report.old, vec![("bank".to_string(), 400)]);
+        assert_eq!(
+            report.missing_metadata,
+            vec![("bank".to_string(), vec!["url"])]
+        );
+        assert!(report.score() < 100);
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, TimeZone};
+use config::{
+    config::{Configuration, NamespaceConfig},
+    paths::Paths,
+};
+use crypto::sealed;
+use serde::{Deserialize, Serialize};
+use shared::error::{Error, Result};
+use tokio::fs;
+
+use crate::{
+    files::{list_entry_files, save_to_file, write_atomically},
+    index::INDEX_FILE_NAME,
+};
+
+const BACKUP_VERSION: u32 = 1;
+
+/// Everything needed to bring a namespace back, sealed into one file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Archive {
+    version: u32,
+    /// Unix time the backup was made
+    created: i64,
+    config: NamespaceConfig,
+    /// Armored secret key, still locked with its own passphrase
+    key: String,
+    /// Base64 content of the passwords directory files by relative path,
+    /// the encrypted entries and the metadata index
+    files: BTreeMap<String, String>,
+}
+
+/// What a backup holds, shown after writing, verifying or restoring it.
+#[derive(Clone, Debug)]
+pub struct BackupSummary {
+    pub namespace: String,
+    pub created: DateTime<Local>,
+    pub entries: usize,
+    /// Size of the backup file in bytes
+    pub size: usize,
+}
+
+impl fmt::Display for BackupSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Backup of namespace {} from {}: key, configuration and {} entries, {} bytes",
+            self.namespace,
+            self.created.format("%Y-%m-%d %H:%M"),
+            self.entries,
+            self.size
+        )
+    }
+}
+
+impl Archive {
+    fn summary(&self, size: usize) -> BackupSummary {
+        BackupSummary {
+            namespace: self.config.name.clone(),
+            created: Local
+                .timestamp_opt(self.created, 0)
+                .single()
+                .unwrap_or_default(),
+            entries: self
+                .files
+                .keys()
+                .filter(|path| *path != INDEX_FILE_NAME)
+                .count(),
+            size,
+        }
+    }
+}
+
+async fn collect(config: &NamespaceConfig) -> Result<Archive> {
+    let key = fs::read_to_string(&config.private_key_path)
+        .await
+        .map_err(|_| Error::Key(format!("no key found at {}", config.private_key_path)))?;
+    let mut names = list_entry_files(&config.passwords_dir).await?;
+    if config.passwords_dir.join(INDEX_FILE_NAME).exists() {
+        names.insert(INDEX_FILE_NAME.to_string());
+    }
+    let mut files = BTreeMap::new();
+    for name in names {
+        let content = fs::read(config.passwords_dir.join(&name)).await?;
+        files.insert(name, base64::encode(content));
+    }
+    Ok(Archive {
+        version: BACKUP_VERSION,
+        created: Local::now().timestamp(),
+        config: config.clone(),
+        key,
+        files,
+    })
+}
+
+/// Path of an archived file inside `dir`, refusing anything that would
+/// end up outside of it.
+fn file_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let parts: Vec<&str> = name.split('/').collect();
+    if parts.iter().any(|part| matches!(*part, "" | "." | "..")) || name.contains('\\') {
+        return Err(Error::InvalidInput(format!(
+            "backup holds an invalid file name {}",
+            name
+        )));
+    }
+    Ok(parts
+        .iter()
+        .fold(dir.to_path_buf(), |path, part| path.join(part)))
+}
+
+/// Decodes the files of an archive, checking their names, before anything
+/// is written.
+fn decode_files(archive: &Archive) -> Result<Vec<(&str, Vec<u8>)>> {
+    archive
+        .files
+        .iter()
+        .map(|(name, content)| {
+            file_path(Path::new(""), name)?;
+            let content = base64::decode(content)
+                .map_err(|e| Error::InvalidInput(format!("bad content of {}: {}", name, e)))?;
+            Ok((name.as_str(), content))
+        })
+        .collect()
+}
+
+/// Writes the key and files of an archive into a freshly created namespace.
+async fn unpack(key: &str, files: &[(&str, Vec<u8>)], config: &NamespaceConfig) -> Result<()> {
+    let key_path = PathBuf::from(&config.private_key_path);
+    if key_path.exists() {
+        return Err(Error::Key(format!(
+            "a key already exists at {}",
+            key_path.display()
+        )));
+    }
+    for (name, content) in files {
+        save_to_file(content, &file_path(&config.passwords_dir, name)?).await?;
+    }
+    save_to_file(key.as_bytes(), &key_path).await
+}
+
+// Key derivation takes a while on purpose, it runs off the async workers
+async fn seal(data: Vec<u8>, password: &str) -> Result<Vec<u8>> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || sealed::seal(&data, &password))
+        .await
+        .map_err(|e| Error::Encryption(e.to_string()))?
+}
+
+async fn open_sealed(data: Vec<u8>, password: &str) -> Result<Vec<u8>> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || sealed::open(&data, &password))
+        .await
+        .map_err(|e| Error::Decryption(e.to_string()))?
+}
+
+async fn open(data: Vec<u8>, password: &str) -> Result<Archive> {
+    let data = open_sealed(data, password).await?;
+    let archive: Archive = serde_json::from_slice(&data)
+        .map_err(|e| Error::Decryption(format!("bad backup content: {}", e)))?;
+    if archive.version > BACKUP_VERSION {
+        return Err(Error::Decryption(format!(
+            "backup version {} is newer than this passm supports",
+            archive.version
+        )));
+    }
+    Ok(archive)
+}
+
+/// Seals the key, configuration and entries of a namespace into `path`
+/// with `password`. The written file is read back and opened to make sure
+/// it restores to exactly what was backed up.
+pub async fn write_backup(
+    config: &NamespaceConfig,
+    password: &str,
+    path: &Path,
+) -> Result<BackupSummary> {
+    if password.is_empty() {
+        return Err(Error::InvalidInput(
+            "Backup password can not be empty".to_string(),
+        ));
+    }
+    let archive = collect(config).await?;
+    let data = serde_json::to_vec(&archive).map_err(|e| Error::Encryption(e.to_string()))?;
+    write_atomically(path, &seal(data.clone(), password).await?).await?;
+
+    let written = fs::read(path).await?;
+    let size = written.len();
+    if open_sealed(written, password).await? != data {
+        return Err(Error::Encryption(format!(
+            "backup at {} does not match the vault, it was not written correctly",
+            path.display()
+        )));
+    }
+    Ok(archive.summary(size))
+}
+
+/// Opens a backup without restoring it.
+pub async fn verify_backup(path: &Path, password: &str) -> Result<BackupSummary> {
+    let data = fs::read(path).await?;
+    let size = data.len();
+    Ok(open(data, password).await?.summary(size))
+}
+
+/// Restores a backup as a new namespace, named like the backed up one
+/// unless `name` is given. On a machine without a vault the configuration
+/// is created with the restored namespace as the default. If the backup
+/// can not be written out, the namespace is removed again.
+pub async fn restore_backup(
+    paths: &Paths,
+    path: &Path,
+    password: &str,
+    name: Option<&str>,
+) -> Result<(NamespaceConfig, BackupSummary)> {
+    let data = fs::read(path).await?;
+    let size = data.len();
+    let archive = open(data, password).await?;
+    let files = decode_files(&archive)?;
+    let name = name.unwrap_or(&archive.config.name);
+    let config = match Configuration::read(paths) {
+        Ok(_) => Configuration::create_namespace(paths, name)?,
+        Err(_) => Configuration::init_new(paths, Some(name.to_string()))?,
+    };
+    let restored = match unpack(&archive.key, &files, &config).await {
+        Ok(()) => Configuration::set_opaque_names(paths, name, archive.config.opaque_names),
+        Err(err) => Err(err),
+    };
+    match restored {
+        Ok(config) => Ok((config, archive.summary(size))),
+        Err(err) => {
+            let _ = Configuration::discard_namespace(paths, name);
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use config::{config::Configuration, paths::Paths};
+
+    use super::{
+        collect, decode_files, file_path, restore_backup, unpack, verify_backup, write_backup,
+        INDEX_FILE_NAME,
+    };
+
+    #[tokio::test]
+    async fn unpacks_collected_namespace() {
+        let base_path = env::temp_dir().join(format!("passm-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base_path);
+        let paths = Paths::from_home(base_path.join("source"));
+        let source = Configuration::init_new(&paths, None).unwrap();
+        fs::write(&source.private_key_path, "key").unwrap();
+        fs::create_dir_all(source.passwords_dir.join("work")).unwrap();
+        fs::write(source.passwords_dir.join("work/mail"), [0xff, 0]).unwrap();
+        fs::write(source.passwords_dir.join(INDEX_FILE_NAME), "index").unwrap();
+
+        let archive = collect(&source).await.unwrap();
+        assert_eq!(archive.summary(0).entries, 1);
+
+        let paths = Paths::from_home(base_path.join("target"));
+        let target = Configuration::init_new(&paths, None).unwrap();
+        let files = decode_files(&archive).unwrap();
+        unpack(&archive.key, &files, &target).await.unwrap();
+        assert_eq!(
+            fs::read(target.passwords_dir.join("work/mail")).unwrap(),
+            [0xff, 0]
+        );
+        assert_eq!(fs::read_to_string(&target.private_key_path).unwrap(), "key");
+        assert!(unpack(&archive.key, &files, &target).await.is_err());
+
+        assert!(file_path(&target.passwords_dir, "../key").is_err());
+        assert!(file_path(&target.passwords_dir, "/etc/passwd").is_err());
+        fs::remove_dir_all(base_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn restores_written_backups() {
+        let base_path = env::temp_dir().join(format!("passm-backup-file-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base_path);
+        let paths = Paths::from_home(base_path.join("source"));
+        let source = Configuration::init_new(&paths, Some("work".to_string())).unwrap();
+        fs::write(&source.private_key_path, "key").unwrap();
+        fs::create_dir_all(&source.passwords_dir).unwrap();
+        fs::write(source.passwords_dir.join("mail"), "sealed entry").unwrap();
+        let file = base_path.join("work.backup");
+
+        let written = write_backup(&source, "backup password", &file)
+            .await
+            .unwrap();
+        assert_eq!(written.entries, 1);
+        let verified = verify_backup(&file, "backup password").await.unwrap();
+        assert_eq!(verified.namespace, "work");
+
+        let paths = Paths::from_home(base_path.join("target"));
+        let (restored, summary) = restore_backup(&paths, &file, "backup password", None)
+            .await
+            .unwrap();
+        assert_eq!(restored.name, "work");
+        assert_eq!(summary.entries, 1);
+        assert_eq!(
+            fs::read_to_string(restored.passwords_dir.join("mail")).unwrap(),
+            "sealed entry"
+        );
+        assert_eq!(
+            fs::read_to_string(&restored.private_key_path).unwrap(),
+            "key"
+        );
+        fs::remove_dir_all(base_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_wrong_backup_passwords() {
+        let base_path = env::temp_dir().join(format!("passm-backup-wrong-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base_path);
+        let paths = Paths::from_home(base_path.join("source"));
+        let source = Configuration::init_new(&paths, None).unwrap();
+        fs::write(&source.private_key_path, "key").unwrap();
+        fs::create_dir_all(&source.passwords_dir).unwrap();
+        let file = base_path.join("default.backup");
+        write_backup(&source, "backup password", &file)
+            .await
+            .unwrap();
+
+        assert!(verify_backup(&file, "wrong").await.is_err());
+        let paths = Paths::from_home(base_path.join("target"));
+        assert!(restore_backup(&paths, &file, "wrong", Some("other"))
+            .await
+            .is_err());
+        // Nothing is created for a backup that could not be opened
+        assert!(Configuration::read(&paths).is_err());
+        fs::remove_dir_all(base_path).unwrap();
+    }
+}
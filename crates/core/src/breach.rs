@@ -0,0 +1,155 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::Path,
+};
+
+use crypto::hash::{ntlm_hex, sha1_hex};
+use shared::error::{Error, Result};
+
+/// Hash used by a Pwned Passwords list, told apart by the hash length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashKind {
+    Sha1,
+    Ntlm,
+}
+
+impl HashKind {
+    fn from_length(length: usize) -> Option<Self> {
+        match length {
+            40 => Some(HashKind::Sha1),
+            32 => Some(HashKind::Ntlm),
+            _ => None,
+        }
+    }
+
+    fn hash(self, password: &str) -> String {
+        match self {
+            HashKind::Sha1 => sha1_hex(password.as_bytes()),
+            HashKind::Ntlm => ntlm_hex(password),
+        }
+    }
+}
+
+/// Downloaded Pwned Passwords list ordered by hash, with `HASH:COUNT` lines.
+/// Lookups binary search the file on disk, nothing is loaded into memory and
+/// nothing leaves the machine.
+pub struct BreachList {
+    reader: BufReader<File>,
+    len: u64,
+    kind: HashKind,
+}
+
+/// Splits a `HASH:COUNT` line.
+fn parse_line(line: &str) -> Option<(&str, u64)> {
+    let (hash, count) = line.trim_end().split_once(':')?;
+    Some((hash, count.trim().parse().ok()?))
+}
+
+impl BreachList {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut first_line = String::new();
+        reader.read_line(&mut first_line)?;
+        let kind = parse_line(&first_line)
+            .and_then(|(hash, _)| HashKind::from_length(hash.len()))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "{} is not a Pwned Passwords SHA-1 or NTLM list ordered by hash",
+                    path.display()
+                ))
+            })?;
+        Ok(Self { reader, len, kind })
+    }
+
+    pub fn kind(&self) -> HashKind {
+        self.kind
+    }
+
+    /// Start and content of the first line starting at or after `offset`.
+    fn line_at(&mut self, offset: u64) -> Result<Option<(u64, String)>> {
+        let mut start = offset;
+        if offset > 0 {
+            // Skip the rest of the line `offset` falls into
+            self.reader.seek(SeekFrom::Start(offset - 1))?;
+            let mut skipped = vec![];
+            start = offset - 1 + self.reader.read_until(b'\n', &mut skipped)? as u64;
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some((start, line)))
+    }
+
+    /// How often the hash was seen in breaches, None if it is not in the list.
+    fn lookup(&mut self, hash: &str) -> Result<Option<u64>> {
+        // Smallest offset whose next line holds a hash >= the one looked up
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.line_at(middle)? {
+                Some((start, line)) if parse_line(&line).is_some_and(|(h, _)| h < hash) => {
+                    low = start + 1;
+                }
+                _ => high = middle,
+            }
+        }
+        Ok(self
+            .line_at(low)?
+            .and_then(|(_, line)| parse_line(&line).map(|(h, count)| (h == hash, count)))
+            .and_then(|(found, count)| found.then_some(count)))
+    }
+
+    /// How often a password was seen in breaches. The password is hashed in memory only.
+    pub fn count(&mut self, password: &str) -> Result<Option<u64>> {
+        let hash = self.kind.hash(password);
+        self.lookup(&hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crypto::hash::sha1_hex;
+
+    use super::{BreachList, HashKind};
+
+    #[test]
+    fn finds_hashes_in_sorted_file() {
+        let mut hashes: Vec<String> = (0..500)
+            .map(|i| sha1_hex(format!("pw{}", i).as_bytes()))
+            .collect();
+        hashes.push(sha1_hex(b"password"));
+        hashes.sort();
+        let content: String = hashes
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| format!("{}:{}\r\n", hash, i + 1))
+            .collect();
+        let path = env::temp_dir().join(format!("passm-breach-{}.txt", std::process::id()));
+        fs::write(&path, content).unwrap();
+
+        let mut list = BreachList::open(&path).unwrap();
+        assert_eq!(list.kind(), HashKind::Sha1);
+        let position = hashes
+            .iter()
+            .position(|h| *h == sha1_hex(b"password"))
+            .unwrap();
+        assert_eq!(list.count("password").unwrap(), Some(position as u64 + 1));
+        assert_eq!(list.count(&hashes[0]).unwrap(), None);
+        for i in [0, 1, 250, 499] {
+            assert!(list.count(&format!("pw{}", i)).unwrap().is_some());
+        }
+        assert_eq!(list.count("not breached at all").unwrap(), None);
+
+        fs::write(&path, "not a list\n").unwrap();
+        assert!(BreachList::open(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
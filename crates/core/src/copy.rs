@@ -0,0 +1,9 @@
+use clipboard::{ClipboardContext, ClipboardProvider};
+use shared::error::{Error, Result};
+
+pub fn copy_to_clipboard(text: String) -> Result<()> {
+    let mut ctx: ClipboardContext =
+        ClipboardProvider::new().map_err(|e| Error::Clipboard(e.to_string()))?;
+    ctx.set_contents(text)
+        .map_err(|e| Error::Clipboard(e.to_string()))
+}
@@ -1,9 +1,20 @@
-use anyhow::{Ok, Result};
-use crypto::{exporter::export_private_key_bytes, signer::Signer};
-use log::{debug, info};
-use std::path::PathBuf;
+use crypto::{exporter::export_private_key_bytes, gpg::Recipient, signer::Signer};
+use log::debug;
+use shared::{
+    error::{Error, Result},
+    password::{Entry, EntryKind},
+    secret::SecretString,
+};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tokio::{fs, io::AsyncWriteExt};
 
-use crate::files::save_to_file;
+use crate::{
+    files::{list_entries, read_entry, save_to_file},
+    index::Index,
+};
 
 pub async fn export_private_key(
     signer: &Signer,
@@ -12,13 +23,196 @@ pub async fn export_private_key(
 ) -> Result<()> {
     let armored = signer.export_private_key()?;
     let exported = export_private_key_bytes(armored, master_password.to_string())?;
-    debug!(
-        "saving to: {}",
-        export_file_name
-            .clone()
-            .to_str()
-            .expect("expect to get path")
-    );
+    debug!("saving to: {}", export_file_name.display());
     save_to_file(&exported, &export_file_name).await?;
     Ok(())
 }
+
+/// Format of a plaintext export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlainFormat {
+    Csv,
+    Json,
+}
+
+impl PlainFormat {
+    /// JSON for `.json` files, CSV for anything else.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => PlainFormat::Json,
+            _ => PlainFormat::Csv,
+        }
+    }
+}
+
+impl FromStr for PlainFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "csv" => Ok(PlainFormat::Csv),
+            "json" => Ok(PlainFormat::Json),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown export format {}, expected csv or json",
+                format
+            ))),
+        }
+    }
+}
+
+/// Columns of CSV exports. The first five are named like in browser exports,
+/// so other password managers, and passm itself, can import the file.
+const CSV_HEADER: [&str; 8] = [
+    "name", "url", "username", "password", "note", "otp", "tags", "kind",
+];
+
+fn to_csv(entries: Vec<Entry>) -> Result<Vec<u8>> {
+    let failed = |e: csv::Error| Error::Encryption(format!("failed to write CSV: {}", e));
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(CSV_HEADER).map_err(failed)?;
+    for entry in &entries {
+        let (password, note, kind) = match entry.kind {
+            EntryKind::Password => (
+                entry.body.expose(),
+                entry
+                    .notes
+                    .as_ref()
+                    .map(SecretString::expose)
+                    .unwrap_or_default(),
+                "password",
+            ),
+            EntryKind::Note => ("", entry.body.expose(), "note"),
+        };
+        writer
+            .write_record([
+                entry.name.as_deref().unwrap_or_default(),
+                entry.metadata.url.as_deref().unwrap_or_default(),
+                entry.metadata.username.as_deref().unwrap_or_default(),
+                password,
+                note,
+                entry.otp.as_deref().unwrap_or_default(),
+                &entry.metadata.tags.join(","),
+                kind,
+            ])
+            .map_err(failed)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| Error::Encryption(format!("failed to write CSV: {}", e)))
+}
+
+/// Writes a file only its owner can read, also when it already existed.
+pub async fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await?;
+    }
+    file.write_all(content).await?;
+    file.sync_all().await?;
+    Ok(())
+}
+
+/// Where and how entries are exported.
+pub struct PlainExport<'a> {
+    pub path: &'a Path,
+    pub format: PlainFormat,
+    /// Public key to encrypt the export to, as an armored OpenPGP message
+    pub recipient: Option<&'a Recipient>,
+}
+
+/// Decrypts entries and writes them out in plain text, unless a recipient
+/// is given. `names` picks the entries, None exports the whole namespace.
+/// The passphrase of the key has to be typed again; the file is only
+/// readable by its owner.
+pub async fn export_entries(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &Index,
+    names: Option<&[String]>,
+    passphrase: &str,
+    export: &PlainExport<'_>,
+) -> Result<usize> {
+    signer.check_passphrase(passphrase)?;
+    let names: Vec<String> = match names {
+        Some(names) => names.to_vec(),
+        None => list_entries(passwords_dir, index)
+            .await?
+            .into_iter()
+            .map(|password| password.name)
+            .collect(),
+    };
+    let mut entries = Vec::with_capacity(names.len());
+    for name in names {
+        let mut entry = read_entry(signer, passwords_dir, index, &name).await?;
+        entry.name = Some(name);
+        entries.push(entry);
+    }
+    let count = entries.len();
+    let (content, file_name) = match export.format {
+        PlainFormat::Csv => (to_csv(entries)?, "passm-export.csv"),
+        PlainFormat::Json => (
+            serde_json::to_vec_pretty(&entries).map_err(|e| Error::Encryption(e.to_string()))?,
+            "passm-export.json",
+        ),
+    };
+    match export.recipient {
+        Some(recipient) => {
+            let armored = recipient.encrypt(file_name, &content)?;
+            write_private(export.path, armored.as_bytes()).await?;
+        }
+        None => write_private(export.path, &content).await?,
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use shared::{
+        password::{Entry, EntryKind},
+        secret::SecretString,
+    };
+
+    use super::{to_csv, PlainFormat};
+    use crate::import::{read_export, ImportFormat};
+
+    #[test]
+    fn writes_importable_csv() {
+        let mut mail = Entry::new(EntryKind::Password, "hunter2".to_string());
+        mail.name = Some("work/mail".to_string());
+        mail.notes = Some(SecretString::new("first, \"quoted\"\nsecond".to_string()));
+        mail.metadata.tags = vec!["mail".to_string(), "work".to_string()];
+        let mut wifi = Entry::new(EntryKind::Note, "guest network".to_string());
+        wifi.name = Some("wifi".to_string());
+
+        let csv = to_csv(vec![mail, wifi]).unwrap();
+        let (format, imported) = read_export(&csv).unwrap();
+        assert_eq!(format, ImportFormat::Chrome);
+        // Chrome exports have no folders, the slash is kept in the name
+        assert_eq!(imported.entries[0].name, "work-mail");
+        assert_eq!(imported.entries[0].entry.body.expose(), "hunter2");
+        assert_eq!(
+            imported.entries[0]
+                .entry
+                .notes
+                .as_ref()
+                .map(|notes| notes.expose()),
+            Some("first, \"quoted\"\nsecond")
+        );
+        assert_eq!(imported.entries[1].entry.kind, EntryKind::Note);
+
+        assert_eq!(
+            PlainFormat::from_path(Path::new("out.JSON")),
+            PlainFormat::Json
+        );
+        assert_eq!(PlainFormat::from_path(Path::new("out")), PlainFormat::Csv);
+    }
+}
@@ -1,19 +1,193 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{anyhow, Result};
-
-use shared::password::Password;
+use chrono::DateTime;
+use crypto::signer::Signer;
+use rand::{thread_rng, RngCore};
+use shared::{
+    error::{Error, Result},
+    password::{Entry, Password},
+};
 use tokio::fs;
 
+use crate::index::{write_index, Index, IndexRecord, INDEX_FILE_NAME};
+
+/// Suffix of files being written, they are renamed into place once complete.
+const TMP_SUFFIX: &str = ".passm-tmp";
+
 pub async fn save_to_file(content: &[u8], path: &PathBuf) -> Result<()> {
-    let parent_dir = path.parent().expect("expect pathname with a parent");
-    fs::create_dir_all(parent_dir)
-        .await
-        .expect("expect to create dirs");
-    fs::write(path, content).await.expect("expect to write");
+    let parent_dir = path
+        .parent()
+        .ok_or_else(|| Error::InvalidInput(format!("{} has no parent", path.display())))?;
+    fs::create_dir_all(parent_dir).await?;
+    fs::write(path, content).await?;
+    Ok(())
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(TMP_SUFFIX);
+    tmp.into()
+}
+
+/// Writes next to `path` first and renames over it, so readers never see a partial file.
+pub async fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let tmp = tmp_path(path);
+    save_to_file(content, &tmp).await?;
+    fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Random name for a file whose name must not reveal the entry.
+fn random_file_name() -> String {
+    let mut id = [0u8; 16];
+    thread_rng().fill_bytes(&mut id);
+    id.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Encrypts and saves an entry and records its metadata in the index.
+/// New entries get a random file name when `opaque_names` is set, existing
+/// ones keep their file. The entry is written to a temporary file, the index
+/// is replaced and only then the entry is renamed into place; the in-memory
+/// index changes once both are on disk. If the process dies in between, the
+/// index is reconciled with the entries on the next load.
+/// The name is set on `entry` when the file name does not reveal it.
+pub async fn save_entry(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &mut Index,
+    name: &str,
+    entry: &mut Entry,
+    opaque_names: bool,
+) -> Result<()> {
+    if is_reserved_name(name) {
+        return Err(Error::InvalidInput(format!("{} is a reserved name", name)));
+    }
+    if !is_valid_path(name) {
+        return Err(Error::InvalidInput(format!(
+            "{} is not a valid entry name, folders are separated by single slashes",
+            name
+        )));
+    }
+    let file = match index.get(name) {
+        Some(record) => record.file.clone(),
+        None => opaque_names.then(random_file_name),
+    };
+    entry.name = file.as_ref().map(|_| name.to_string());
+    let path = passwords_dir.join(file.as_deref().unwrap_or(name));
+    let encrypted = signer.encrypt(&entry.to_bytes()?)?;
+    save_to_file(&encrypted, &tmp_path(&path)).await?;
+
+    let mut updated = index.clone();
+    updated.insert(name.to_string(), IndexRecord::new(entry, file));
+    if let Err(err) = write_index(signer, passwords_dir, &updated).await {
+        let _ = fs::remove_file(tmp_path(&path)).await;
+        return Err(err);
+    }
+    fs::rename(tmp_path(&path), &path).await?;
+    *index = updated;
+    Ok(())
+}
+
+/// Decrypts an entry by name.
+pub async fn read_entry(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &Index,
+    name: &str,
+) -> Result<Entry> {
+    let bytes = read_password_bytes(&passwords_dir.join(index.file_name(name))).await?;
+    Entry::from_bytes(&signer.decrypt(&bytes)?)
+}
+
+/// Deletes an entry and its index record.
+pub async fn delete_entry(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &mut Index,
+    name: &str,
+) -> Result<()> {
+    let path = passwords_dir.join(index.file_name(name));
+    delete_password(&path).await?;
+    remove_empty_folders(passwords_dir, &path).await;
+    let mut updated = index.clone();
+    updated.remove(name);
+    write_index(signer, passwords_dir, &updated).await?;
+    *index = updated;
     Ok(())
 }
 
+/// Moves entries to random file names with the name stored inside the payload
+/// when `opaque_names` is set, or back to files named after the entries.
+/// Every moved entry is written to a temporary file before the index is
+/// replaced; old files are removed only once the new ones are in place, so an
+/// interrupted migration leaves every entry in one of the two files and the
+/// next load picks it up. Returns the number of moved entries.
+pub async fn migrate_file_names(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &mut Index,
+    opaque_names: bool,
+) -> Result<usize> {
+    let mut updated = index.clone();
+    let mut moves: Vec<(PathBuf, PathBuf)> = vec![];
+    let mut result = Ok(());
+    for name in index.names() {
+        let moved = async {
+            let Some(record) = index.get(name) else {
+                return Ok(None);
+            };
+            if record.file.is_some() == opaque_names {
+                return Ok(None);
+            }
+            if !opaque_names && is_reserved_name(name) {
+                return Err(Error::InvalidInput(format!("{} is a reserved name", name)));
+            }
+            let mut entry = read_entry(signer, passwords_dir, index, name).await?;
+            let file = opaque_names.then(random_file_name);
+            entry.name = file.as_ref().map(|_| name.to_string());
+            let new_path = passwords_dir.join(file.as_deref().unwrap_or(name));
+            if fs::metadata(&new_path).await.is_ok() {
+                return Err(Error::InvalidInput(format!(
+                    "{} already exists",
+                    new_path.display()
+                )));
+            }
+            save_to_file(&signer.encrypt(&entry.to_bytes()?)?, &tmp_path(&new_path)).await?;
+            updated.insert(name.clone(), IndexRecord::new(&entry, file));
+            Ok(Some((passwords_dir.join(index.file_name(name)), new_path)))
+        }
+        .await;
+        match moved {
+            Ok(Some(paths)) => moves.push(paths),
+            Ok(None) => {}
+            Err(err) => {
+                result = Err(err);
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        result = write_index(signer, passwords_dir, &updated).await;
+    }
+    if let Err(err) = result {
+        for (_, new_path) in &moves {
+            let _ = fs::remove_file(tmp_path(new_path)).await;
+        }
+        return Err(err);
+    }
+
+    for (old_path, new_path) in &moves {
+        fs::rename(tmp_path(new_path), new_path).await?;
+        fs::remove_file(old_path).await?;
+        remove_empty_folders(passwords_dir, old_path).await;
+    }
+    *index = updated;
+    Ok(moves.len())
+}
+
 pub async fn delete_password(path: &PathBuf) -> Result<()> {
     fs::remove_file(path).await?;
     Ok(())
@@ -24,19 +198,72 @@ pub async fn read_password_bytes(path: &PathBuf) -> Result<Vec<u8>> {
     Ok(content)
 }
 
-pub async fn read_passwords_from_path(path: &PathBuf) -> Result<Vec<Password>> {
-    match fs::read_dir(path).await {
-        Ok(mut dir) => {
-            let mut entries: Vec<Password> = vec![];
-            while let Some(entry) = dir.next_entry().await.unwrap() {
-                if entry.file_type().await.unwrap().is_file() {
-                    entries.push(Password {
-                        name: entry.file_name().to_str().unwrap().to_string(),
-                    })
-                }
+/// Whether a name stays inside the passwords directory when used as a path.
+fn is_valid_path(name: &str) -> bool {
+    name.split('/')
+        .all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+/// Removes the folders of a deleted entry file that are left empty.
+async fn remove_empty_folders(passwords_dir: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) =
+        dir.filter(|dir| dir.starts_with(passwords_dir) && *dir != passwords_dir)
+    {
+        // Fails on folders that still hold entries
+        if fs::remove_dir(current).await.is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// Files in a passwords directory that are not entries.
+pub fn is_reserved_name(name: &str) -> bool {
+    name == INDEX_FILE_NAME || name.ends_with(TMP_SUFFIX)
+}
+
+/// Names of all entry files in a passwords directory.
+pub async fn list_entry_files(path: &Path) -> Result<BTreeSet<String>> {
+    Ok(read_passwords_from_path(path)
+        .await?
+        .into_iter()
+        .map(|password| password.name)
+        .collect())
+}
+
+/// Entries of a passwords directory with their names and metadata from the index.
+pub async fn list_entries(path: &Path, index: &Index) -> Result<Vec<Password>> {
+    Ok(index.resolve(read_passwords_from_path(path).await?))
+}
+
+/// Entry files of a passwords directory. Files in sub-directories are named by
+/// their path, so an entry named `work/mail` lives in `work/mail`.
+pub async fn read_passwords_from_path(path: &Path) -> Result<Vec<Password>> {
+    let mut entries: Vec<Password> = vec![];
+    let mut dirs = vec![(path.to_path_buf(), String::new())];
+    while let Some((dir_path, prefix)) = dirs.pop() {
+        let mut dir = fs::read_dir(&dir_path).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            let Some(name) = entry
+                .file_name()
+                .to_str()
+                .map(|name| format!("{}{}", prefix, name))
+            else {
+                continue;
+            };
+            if metadata.is_dir() {
+                dirs.push((entry.path(), format!("{}/", name)));
+            } else if metadata.is_file() && !is_reserved_name(&name) {
+                entries.push(Password {
+                    name,
+                    modified: metadata.modified().ok().map(DateTime::from),
+                    size: metadata.len(),
+                    ..Password::default()
+                })
             }
-            Ok(entries)
         }
-        Err(_err) => return Err(anyhow!("Error reading passwords directory")),
     }
+    Ok(entries)
 }
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+use shared::error::{Error, Result};
+
+use super::{Imported, RawEntry};
+
+const LOGIN: u8 = 1;
+const SECURE_NOTE: u8 = 2;
+const CARD: u8 = 3;
+const IDENTITY: u8 = 4;
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "type")]
+    kind: u8,
+    #[serde(default)]
+    name: String,
+    folder_id: Option<String>,
+    notes: Option<String>,
+    #[serde(default)]
+    fields: Vec<Field>,
+    login: Option<Login>,
+    card: Option<Value>,
+    identity: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: Option<String>,
+    value: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Vec<Uri>,
+}
+
+#[derive(Deserialize)]
+struct Uri {
+    uri: Option<String>,
+}
+
+/// `key: value` lines of the text fields of a card or identity.
+fn detail_lines(details: &Value) -> Vec<String> {
+    let Some(details) = details.as_object() else {
+        return vec![];
+    };
+    details
+        .iter()
+        .filter_map(|(key, value)| Some(format!("{}: {}", key, value.as_str()?)))
+        .collect()
+}
+
+/// Reads an unencrypted Bitwarden JSON export. Logins keep their first URI,
+/// the other URIs and custom fields are added to the notes. Secure notes,
+/// cards and identities become notes.
+pub fn read_json(text: &str) -> Result<Imported> {
+    let export: Export = serde_json::from_str(text)
+        .map_err(|err| Error::InvalidInput(format!("bad Bitwarden export: {}", err)))?;
+    if export.encrypted {
+        return Err(Error::InvalidInput(
+            "encrypted Bitwarden exports are not supported, export as unencrypted JSON".to_string(),
+        ));
+    }
+    let folders: HashMap<String, String> = export
+        .folders
+        .into_iter()
+        .map(|folder| (folder.id, folder.name))
+        .collect();
+
+    let mut imported = Imported {
+        mapping: [
+            ("folderId", "folder"),
+            ("name", "name"),
+            ("login.username", "username"),
+            ("login.password", "password"),
+            ("login.totp", "otp"),
+            ("login.uris", "url, others to notes"),
+            ("notes", "notes"),
+            ("fields", "notes"),
+            ("secureNote, card, identity", "note"),
+        ]
+        .map(|(from, to)| (from.to_string(), to.to_string()))
+        .into(),
+        ..Imported::default()
+    };
+    for item in export.items {
+        let mut raw = RawEntry {
+            folders: item
+                .folder_id
+                .and_then(|id| folders.get(&id).cloned())
+                .into_iter()
+                .collect(),
+            title: item.name,
+            ..RawEntry::default()
+        };
+        match (item.kind, item.login) {
+            (LOGIN, Some(login)) => {
+                let mut uris = login.uris.into_iter().filter_map(|uri| uri.uri);
+                raw.url = uris.next();
+                raw.username = login.username;
+                raw.password = login.password.unwrap_or_default();
+                raw.otp = login.totp;
+                raw.notes.extend(item.notes);
+                raw.notes.extend(uris.map(|uri| format!("url: {}", uri)));
+            }
+            (LOGIN | SECURE_NOTE, _) => raw.notes.extend(item.notes),
+            (CARD | IDENTITY, _) => {
+                let details = item.card.or(item.identity).unwrap_or_default();
+                raw.notes.extend(detail_lines(&details));
+                raw.notes.extend(item.notes);
+            }
+            (kind, _) => {
+                imported
+                    .skipped
+                    .push((raw.title, format!("item type {} is not supported", kind)));
+                continue;
+            }
+        }
+        raw.notes.extend(item.fields.into_iter().map(|field| {
+            format!(
+                "{}: {}",
+                field.name.unwrap_or_default(),
+                field.value.unwrap_or_default()
+            )
+        }));
+        imported.entries.push(raw.into_imported());
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::password::EntryKind;
+
+    use super::read_json;
+
+    #[test]
+    fn maps_logins_notes_and_cards() {
+        let export = r#"{
+            "encrypted": false,
+            "folders": [{"id": "f1", "name": "Work"}],
+            "items": [
+                {"type": 1, "name": "VPN", "folderId": "f1", "notes": null,
+                 "fields": [{"name": "PIN", "value": "1234", "type": 1}],
+                 "login": {"username": "me", "password": "s3cret", "totp": "otpauth://totp/VPN?secret=JBSWY3DPEHPK3PXP",
+                           "uris": [{"match": null, "uri": "https://vpn.example.com"}, {"uri": "https://alt.example.com"}]}},
+                {"type": 2, "name": "Wifi", "folderId": null, "notes": "guest network", "secureNote": {"type": 0}},
+                {"type": 3, "name": "Visa", "notes": null,
+                 "card": {"cardholderName": "Me", "number": "4111", "code": null}},
+                {"type": 5, "name": "Server key"}
+            ]
+        }"#;
+        let imported = read_json(export).unwrap();
+        let vpn = &imported.entries[0];
+        assert_eq!(vpn.name, "Work/VPN");
+        assert_eq!(vpn.entry.body.expose(), "s3cret");
+        assert_eq!(
+            vpn.entry.metadata.url.as_deref(),
+            Some("https://vpn.example.com")
+        );
+        assert_eq!(
+            vpn.entry.notes.as_ref().map(|notes| notes.expose()),
+            Some("url: https://alt.example.com\nPIN: 1234")
+        );
+
+        let wifi = &imported.entries[1].entry;
+        assert_eq!(
+            (wifi.kind, wifi.body.expose()),
+            (EntryKind::Note, "guest network")
+        );
+        let visa = &imported.entries[2].entry;
+        assert_eq!(visa.kind, EntryKind::Note);
+        assert_eq!(visa.body.expose(), "cardholderName: Me\nnumber: 4111");
+        assert_eq!(imported.skipped.len(), 1);
+
+        assert!(read_json(r#"{"encrypted": true, "items": []}"#).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use ::csv::{ReaderBuilder, StringRecord};
+use shared::{
+    error::{Error, Result},
+    password::EntryMetadata,
+};
+
+use super::{ImportFormat, Imported, RawEntry};
+
+/// Entry field a CSV column is read into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Column {
+    Title,
+    Folder,
+    Url,
+    Username,
+    Password,
+    Otp,
+    Notes,
+    Tags,
+    /// Rows marked as archived are skipped
+    Archived,
+}
+
+impl Column {
+    fn label(self) -> &'static str {
+        match self {
+            Column::Title => "name",
+            Column::Folder => "folder",
+            Column::Url => "url",
+            Column::Username => "username",
+            Column::Password => "password",
+            Column::Otp => "otp",
+            Column::Notes => "notes",
+            Column::Tags => "tags",
+            Column::Archived => "skip archived",
+        }
+    }
+}
+
+/// Columns of every export by lowercase header. When several headers map to
+/// the same field, the first one found in the file is used.
+const ONEPASSWORD: &[(&str, Column)] = &[
+    ("title", Column::Title),
+    ("vault", Column::Folder),
+    ("url", Column::Url),
+    ("website", Column::Url),
+    ("username", Column::Username),
+    ("password", Column::Password),
+    ("otpauth", Column::Otp),
+    ("one-time password", Column::Otp),
+    ("notes", Column::Notes),
+    ("notesplain", Column::Notes),
+    ("tags", Column::Tags),
+    ("archived", Column::Archived),
+];
+const LASTPASS: &[(&str, Column)] = &[
+    ("name", Column::Title),
+    ("grouping", Column::Folder),
+    ("url", Column::Url),
+    ("username", Column::Username),
+    ("password", Column::Password),
+    ("totp", Column::Otp),
+    ("extra", Column::Notes),
+];
+const CHROME: &[(&str, Column)] = &[
+    ("name", Column::Title),
+    ("url", Column::Url),
+    ("username", Column::Username),
+    ("password", Column::Password),
+    ("note", Column::Notes),
+];
+const FIREFOX: &[(&str, Column)] = &[
+    ("url", Column::Url),
+    ("username", Column::Username),
+    ("password", Column::Password),
+];
+
+/// URL LastPass gives to secure notes.
+const LASTPASS_NOTE_URL: &str = "http://sn";
+
+fn detect(headers: &[String]) -> Option<ImportFormat> {
+    let has = |header: &str| headers.iter().any(|h| h == header);
+    if has("httprealm") && has("formactionorigin") {
+        Some(ImportFormat::Firefox)
+    } else if has("grouping") && has("extra") {
+        Some(ImportFormat::LastPass)
+    } else if has("title") && has("password") {
+        Some(ImportFormat::OnePasswordCsv)
+    } else if has("name") && has("url") && has("password") {
+        Some(ImportFormat::Chrome)
+    } else {
+        None
+    }
+}
+
+fn read_row(record: &StringRecord, columns: &[(usize, Column)]) -> Option<RawEntry> {
+    let mut raw = RawEntry::default();
+    for &(position, column) in columns {
+        let value = record.get(position).unwrap_or_default();
+        let text = || Some(value.to_string());
+        match column {
+            Column::Title => raw.title = value.to_string(),
+            Column::Folder => {
+                raw.folders = value
+                    .split('\\')
+                    .filter(|folder| !folder.trim().is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            Column::Url if value == LASTPASS_NOTE_URL => {}
+            Column::Url => raw.url = text(),
+            Column::Username => raw.username = text(),
+            Column::Password => raw.password = value.to_string(),
+            Column::Otp => raw.otp = text(),
+            Column::Notes => raw.notes.push(value.to_string()),
+            Column::Tags => raw.tags = EntryMetadata::parse_tags(&value.replace(';', ",")),
+            Column::Archived => {
+                if matches!(value.to_lowercase().as_str(), "true" | "yes" | "1") {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(raw)
+}
+
+/// Reads a CSV export of 1Password, LastPass, Chrome or Firefox. Columns
+/// no entry field is kept for are listed in the mapping as ignored.
+pub fn read_csv(text: &str) -> Result<(ImportFormat, Imported)> {
+    let mut reader = ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|err| Error::InvalidInput(format!("bad CSV export: {}", err)))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let format = detect(&headers).ok_or_else(|| {
+        Error::InvalidInput("unknown export format, the CSV header is not recognized".to_string())
+    })?;
+    let table = match format {
+        ImportFormat::LastPass => LASTPASS,
+        ImportFormat::Chrome => CHROME,
+        ImportFormat::Firefox => FIREFOX,
+        _ => ONEPASSWORD,
+    };
+
+    let mut columns: Vec<(usize, Column)> = vec![];
+    for &(header, column) in table {
+        let position = headers.iter().position(|h| h == header);
+        if let Some(position) = position.filter(|_| columns.iter().all(|(_, c)| *c != column)) {
+            columns.push((position, column));
+        }
+    }
+    let original = reader.headers().cloned().unwrap_or_default();
+    let mut imported = Imported {
+        mapping: original
+            .iter()
+            .enumerate()
+            .map(|(position, header)| {
+                let target = columns
+                    .iter()
+                    .find(|(p, _)| *p == position)
+                    .map_or("ignored", |(_, column)| column.label());
+                (header.trim().to_string(), target.to_string())
+            })
+            .collect(),
+        ..Imported::default()
+    };
+
+    for (row, record) in reader.records().enumerate() {
+        // Rows are numbered as in a spreadsheet, the header being row 1
+        let row = format!("row {}", row + 2);
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                imported.skipped.push((row, err.to_string()));
+                continue;
+            }
+        };
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        match read_row(&record, &columns) {
+            Some(raw) => imported.entries.push(raw.into_imported()),
+            None => imported.skipped.push((row, "archived".to_string())),
+        }
+    }
+    Ok((format, imported))
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::password::EntryKind;
+
+    use super::*;
+
+    #[test]
+    fn reads_lastpass_rows() {
+        let export = "url,username,password,totp,extra,name,grouping,fav\n\
+            https://mail.example.com,me@example.com,hunter2,JBSWY3DPEHPK3PXP,\"first\nsecond\",Mail,Personal\\Email,0\n\
+            http://sn,,,,Wifi code 1234,Wifi,,0\n";
+        let (format, imported) = read_csv(export).unwrap();
+        assert_eq!(format, ImportFormat::LastPass);
+        assert_eq!(
+            imported.mapping[7],
+            ("fav".to_string(), "ignored".to_string())
+        );
+
+        let mail = &imported.entries[0];
+        assert_eq!(mail.name, "Personal/Email/Mail");
+        assert_eq!(mail.entry.body.expose(), "hunter2");
+        assert_eq!(mail.entry.otp.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(
+            mail.entry.notes.as_ref().map(|notes| notes.expose()),
+            Some("first\nsecond")
+        );
+
+        let wifi = &imported.entries[1].entry;
+        assert_eq!(
+            (wifi.kind, wifi.body.expose()),
+            (EntryKind::Note, "Wifi code 1234")
+        );
+        assert_eq!(wifi.metadata.url, None);
+    }
+
+    #[test]
+    fn detects_browser_and_1password_exports() {
+        let firefox =
+            "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\"\n\
+            \"https://example.com\",\"me\",\"s3cret\",,\"https://example.com\",\"{1}\"\n";
+        let (format, imported) = read_csv(firefox).unwrap();
+        assert_eq!(format, ImportFormat::Firefox);
+        assert_eq!(imported.entries[0].name, "example.com");
+
+        let chrome = "name,url,username,password\nexample.com,https://example.com/,me,s3cret\n";
+        assert_eq!(read_csv(chrome).unwrap().0, ImportFormat::Chrome);
+
+        let onepassword = "Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes\n\
+            Bank,https://bank.example.com,me,s3cret,,false,false,money;home,\n\
+            Old,,me,0ld,,false,true,,\n";
+        let (format, imported) = read_csv(onepassword).unwrap();
+        assert_eq!(format, ImportFormat::OnePasswordCsv);
+        assert_eq!(imported.entries.len(), 1);
+        assert_eq!(imported.entries[0].entry.metadata.tags, ["money", "home"]);
+        assert_eq!(
+            imported.skipped,
+            [("row 3".to_string(), "archived".to_string())]
+        );
+
+        assert!(read_csv("a,b\n1,2\n").is_err());
+    }
+}
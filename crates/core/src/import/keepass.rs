@@ -0,0 +1,302 @@
+use crypto::kdbx::{InnerStream, Kdbx};
+use quick_xml::{events::Event, Reader};
+use shared::{
+    error::{Error, Result},
+    password::EntryMetadata,
+};
+
+use super::{Imported, ImportedEntry, RawEntry};
+
+/// Standard KeePass fields, everything else is a custom string.
+const TITLE: &str = "Title";
+const USERNAME: &str = "UserName";
+const PASSWORD: &str = "Password";
+const URL: &str = "URL";
+const NOTES: &str = "Notes";
+/// Custom strings holding two-factor secrets: KeePassXC's otpauth URI and
+/// KeePass' own base32 TOTP secret.
+const OTP_FIELDS: [&str; 2] = ["otp", "TimeOtp-Secret-Base32"];
+
+fn xml_error(err: impl ToString) -> Error {
+    Error::InvalidInput(format!("bad KeePass XML: {}", err.to_string()))
+}
+
+struct Group {
+    name: String,
+    recycled: bool,
+}
+
+/// Entry being read, with its strings in document order.
+#[derive(Default)]
+struct Draft {
+    strings: Vec<(String, String)>,
+    tags: Vec<String>,
+    attachments: Vec<String>,
+}
+
+impl Draft {
+    fn take(&mut self, key: &str) -> Option<String> {
+        let position = self.strings.iter().position(|(k, _)| k == key)?;
+        Some(self.strings.remove(position).1)
+    }
+
+    /// Maps the standard fields onto an entry, custom strings are kept as
+    /// `key: value` lines after the notes.
+    fn into_entry(mut self, folders: &[String]) -> ImportedEntry {
+        let title = self.take(TITLE).unwrap_or_default();
+        let password = self.take(PASSWORD).unwrap_or_default();
+        let username = self.take(USERNAME);
+        let url = self.take(URL);
+        let otp = OTP_FIELDS.iter().find_map(|field| self.take(field));
+        let mut notes: Vec<String> = self.take(NOTES).into_iter().collect();
+        notes.extend(
+            self.strings
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key, value)),
+        );
+        RawEntry {
+            folders: folders.to_vec(),
+            title,
+            username,
+            password,
+            url,
+            otp,
+            notes,
+            tags: self.tags,
+        }
+        .into_imported()
+    }
+}
+
+/// Reads the entries of a decrypted KeePass database. Groups become folders
+/// in entry names; history, the recycle bin and attachments are left out and
+/// reported as skipped.
+pub fn read_kdbx(kdbx: Kdbx) -> Result<Imported> {
+    let Kdbx {
+        xml,
+        mut inner_stream,
+        ..
+    } = kdbx;
+    read_xml(&xml, &mut inner_stream)
+}
+
+fn read_xml(xml: &str, inner_stream: &mut InnerStream) -> Result<Imported> {
+    let mut imported = Imported::default();
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<String> = vec![];
+    let mut groups: Vec<Group> = vec![];
+    let mut recycle_bin: Option<String> = None;
+    let mut draft: Option<Draft> = None;
+    let mut history_depth = 0;
+    let mut text = String::new();
+    let mut protected = false;
+    let mut key = String::new();
+    let mut string_value = String::new();
+
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        let (name, closes) = match &event {
+            Event::Start(start) | Event::Empty(start) => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+                protected = start.attributes().flatten().any(|attribute| {
+                    attribute.key.as_ref() == b"Protected" && attribute.value.as_ref() == b"True"
+                });
+                text.clear();
+                match name.as_str() {
+                    "Group" => groups.push(Group {
+                        name: String::new(),
+                        recycled: groups.last().is_some_and(|group| group.recycled),
+                    }),
+                    "Entry" if history_depth == 0 => draft = Some(Draft::default()),
+                    "History" => history_depth += 1,
+                    _ => {}
+                }
+                stack.push(name.clone());
+                (name, matches!(event, Event::Empty(_)))
+            }
+            Event::Text(content) => {
+                text.push_str(&content.unescape().map_err(xml_error)?);
+                continue;
+            }
+            Event::CData(content) => {
+                text.push_str(&String::from_utf8_lossy(content));
+                continue;
+            }
+            Event::End(end) => (
+                String::from_utf8_lossy(end.name().as_ref()).to_string(),
+                true,
+            ),
+            Event::Eof => break,
+            _ => continue,
+        };
+        if !closes {
+            continue;
+        }
+
+        stack.pop();
+        let parent = stack.last().map(String::as_str).unwrap_or_default();
+        // Protected values are decrypted in document order, history included,
+        // or every later value comes out garbled
+        let value = match protected {
+            true => String::from_utf8_lossy(&inner_stream.unprotect(&text)?).into_owned(),
+            false => text.clone(),
+        };
+        protected = false;
+        match (parent, name.as_str()) {
+            ("Meta", "RecycleBinUUID") => recycle_bin = Some(value),
+            ("Group", "Name") => {
+                if let Some(group) = groups.last_mut() {
+                    group.name = value;
+                }
+            }
+            ("Group", "UUID") => {
+                if let Some(group) = groups.last_mut() {
+                    group.recycled |= recycle_bin.as_deref() == Some(value.as_str());
+                }
+            }
+            (_, "Group") => {
+                groups.pop();
+            }
+            (_, "History") => history_depth -= 1,
+            (_, _) if history_depth > 0 => {}
+            ("String" | "Binary", "Key") => key = value,
+            ("Entry", "Tags") => {
+                if let Some(draft) = draft.as_mut() {
+                    draft.tags = EntryMetadata::parse_tags(&value.replace(';', ","));
+                }
+            }
+            ("String", "Value") => string_value = value,
+            ("Entry", "String") => {
+                let value = std::mem::take(&mut string_value);
+                if let Some(draft) = draft.as_mut() {
+                    draft.strings.push((key.clone(), value));
+                }
+            }
+            ("Entry", "Binary") => {
+                if let Some(draft) = draft.as_mut() {
+                    draft.attachments.push(key.clone());
+                }
+            }
+            (_, "Entry") => {
+                let Some(draft) = draft.take() else {
+                    continue;
+                };
+                // The root group is the database itself
+                let folders: Vec<String> = groups
+                    .iter()
+                    .skip(1)
+                    .map(|group| group.name.clone())
+                    .collect();
+                let recycled = groups.last().is_some_and(|group| group.recycled);
+                let attachments = draft.attachments.clone();
+                let imported_entry = draft.into_entry(&folders);
+                if recycled {
+                    imported
+                        .skipped
+                        .push((imported_entry.name, "in the recycle bin".to_string()));
+                    continue;
+                }
+                for attachment in attachments {
+                    imported.skipped.push((
+                        imported_entry.name.clone(),
+                        format!("attachment {} is not supported", attachment),
+                    ));
+                }
+                imported.entries.push(imported_entry);
+            }
+            _ => {}
+        }
+        text.clear();
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::password::EntryKind;
+
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<KeePassFile>
+  <Meta><RecycleBinUUID>BIN</RecycleBinUUID></Meta>
+  <Root>
+    <Group>
+      <UUID>ROOT</UUID><Name>Database</Name>
+      <Entry>
+        <String><Key>Title</Key><Value>Mail</Value></String>
+        <String><Key>UserName</Key><Value>me@example.com</Value></String>
+        <String><Key>Password</Key><Value Protected="True">aHVudGVyMg==</Value></String>
+        <String><Key>URL</Key><Value>https://mail.example.com</Value></String>
+        <String><Key>Notes</Key><Value>first &amp; only</Value></String>
+        <String><Key>PIN</Key><Value>1234</Value></String>
+        <String><Key>otp</Key><Value>otpauth://totp/Mail?secret=JBSWY3DPEHPK3PXP</Value></String>
+        <Tags>mail;personal</Tags>
+        <Binary><Key>scan.pdf</Key><Value Ref="0"/></Binary>
+        <History>
+          <Entry>
+            <String><Key>Password</Key><Value Protected="True">b2xk</Value></String>
+          </Entry>
+        </History>
+      </Entry>
+      <Group>
+        <UUID>WORK</UUID><Name>Work/Shared</Name>
+        <Entry>
+          <String><Key>Title</Key><Value>VPN</Value></String>
+          <String><Key>Password</Key><Value Protected="True">czNjcmV0</Value></String>
+        </Entry>
+        <Entry>
+          <String><Key>Title</Key><Value>Wifi</Value></String>
+          <String><Key>Notes</Key><Value>guest network</Value></String>
+        </Entry>
+      </Group>
+      <Group>
+        <UUID>BIN</UUID><Name>Recycle Bin</Name>
+        <Entry><String><Key>Title</Key><Value>Old</Value></String></Entry>
+      </Group>
+    </Group>
+  </Root>
+</KeePassFile>"#;
+
+    #[test]
+    fn maps_groups_and_fields() {
+        let imported = read_xml(XML, &mut InnerStream::new(0, &[]).unwrap()).unwrap();
+        let names: Vec<&str> = imported.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Mail", "Work-Shared/VPN", "Work-Shared/Wifi"]);
+
+        let mail = &imported.entries[0].entry;
+        assert_eq!(mail.body.expose(), "hunter2");
+        assert_eq!(mail.metadata.username.as_deref(), Some("me@example.com"));
+        assert_eq!(
+            mail.metadata.url.as_deref(),
+            Some("https://mail.example.com")
+        );
+        assert_eq!(mail.metadata.tags, ["mail", "personal"]);
+        assert_eq!(
+            mail.notes.as_ref().map(|notes| notes.expose()),
+            Some("first & only\nPIN: 1234")
+        );
+        assert!(mail.otp.as_deref().unwrap().starts_with("otpauth://"));
+
+        assert_eq!(imported.entries[1].entry.body.expose(), "s3cret");
+        let wifi = &imported.entries[2].entry;
+        assert_eq!(
+            (wifi.kind, wifi.body.expose()),
+            (EntryKind::Note, "guest network")
+        );
+
+        assert_eq!(
+            imported.skipped,
+            [
+                (
+                    "Mail".to_string(),
+                    "attachment scan.pdf is not supported".to_string()
+                ),
+                (
+                    "Recycle Bin/Old".to_string(),
+                    "in the recycle bin".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use std::{collections::HashSet, fmt, path::Path, str::FromStr};
+
+use crypto::signer::Signer;
+use shared::{
+    error::{Error, Result},
+    import::{ImportPreview, PreviewEntry},
+    password::{Entry, EntryKind, EntryMetadata},
+    secret::SecretString,
+};
+
+use crate::{files::save_entry, index::Index};
+
+pub mod bitwarden;
+pub mod csv;
+pub mod keepass;
+pub mod onepassword;
+pub mod pass;
+
+/// Entry read from another password manager, not saved yet.
+pub struct ImportedEntry {
+    pub name: String,
+    pub entry: Entry,
+}
+
+/// Everything read from an export, with the items that could not be read
+/// and why.
+#[derive(Default)]
+pub struct Imported {
+    pub entries: Vec<ImportedEntry>,
+    pub skipped: Vec<(String, String)>,
+    /// Fields of the export with the entry fields they were read into
+    pub mapping: Vec<(String, String)>,
+}
+
+/// Part of an entry name, slashes would create folders.
+fn path_part(name: &str) -> String {
+    match name.trim() {
+        "" => "Untitled".to_string(),
+        "." | ".." => "_".to_string(),
+        name => name.replace('/', "-"),
+    }
+}
+
+/// Host of a URL, used to name entries that have no title.
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Fields of an item as most exports have them, before they become an entry.
+#[derive(Default)]
+struct RawEntry {
+    folders: Vec<String>,
+    title: String,
+    username: Option<String>,
+    password: String,
+    url: Option<String>,
+    otp: Option<String>,
+    notes: Vec<String>,
+    tags: Vec<String>,
+}
+
+impl RawEntry {
+    /// Names the entry after its folders and title, or the host of its URL
+    /// when untitled. Items with nothing but text become secure notes.
+    fn into_imported(self) -> ImportedEntry {
+        let optional = |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+        let username = optional(self.username);
+        let url = optional(self.url);
+        let title = match self.title.trim().is_empty() {
+            true => url.as_deref().and_then(url_host).unwrap_or_default(),
+            false => &self.title,
+        };
+        let mut name: Vec<String> = self.folders.iter().map(|f| path_part(f)).collect();
+        name.push(path_part(title));
+
+        let notes: Vec<String> = self
+            .notes
+            .into_iter()
+            .filter(|note| !note.trim().is_empty())
+            .collect();
+        let notes = notes.join("\n");
+        let (kind, body, notes) = match self.password.is_empty() && username.is_none() {
+            true if !notes.is_empty() => (EntryKind::Note, notes, None),
+            _ => (
+                EntryKind::Password,
+                self.password,
+                Some(notes).filter(|n| !n.is_empty()),
+            ),
+        };
+        ImportedEntry {
+            name: name.join("/"),
+            entry: Entry {
+                otp: optional(self.otp),
+                notes: notes.map(SecretString::new),
+                metadata: EntryMetadata {
+                    username,
+                    url,
+                    tags: self.tags,
+                },
+                name: None,
+                kind,
+                body: SecretString::new(body),
+            },
+        }
+    }
+}
+
+/// Export formats read by [`read_export`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    Bitwarden,
+    OnePasswordPux,
+    OnePasswordCsv,
+    LastPass,
+    Chrome,
+    Firefox,
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ImportFormat::Bitwarden => "Bitwarden JSON",
+            ImportFormat::OnePasswordPux => "1Password 1PUX",
+            ImportFormat::OnePasswordCsv => "1Password CSV",
+            ImportFormat::LastPass => "LastPass CSV",
+            ImportFormat::Chrome => "Chrome CSV",
+            ImportFormat::Firefox => "Firefox CSV",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Reads an unencrypted export of another password manager, telling the
+/// format from the file itself: 1PUX archives by their zip signature,
+/// Bitwarden by its JSON and the CSV exports by their header.
+pub fn read_export(data: &[u8]) -> Result<(ImportFormat, Imported)> {
+    if data.starts_with(b"PK\x03\x04") {
+        return Ok((ImportFormat::OnePasswordPux, onepassword::read_1pux(data)?));
+    }
+    let text = std::str::from_utf8(data)
+        .map_err(|_| Error::InvalidInput("export is not a text file".to_string()))?;
+    let text = text.trim_start_matches('\u{feff}');
+    if text.trim_start().starts_with('{') {
+        return Ok((ImportFormat::Bitwarden, bitwarden::read_json(text)?));
+    }
+    self::csv::read_csv(text)
+}
+
+/// What to do with an imported entry whose name is already taken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the existing entry
+    #[default]
+    Skip,
+    /// Replace the existing entry
+    Overwrite,
+    /// Save under the first free `name (N)`
+    Rename,
+}
+
+impl ConflictPolicy {
+    pub fn next(self) -> Self {
+        match self {
+            ConflictPolicy::Skip => ConflictPolicy::Overwrite,
+            ConflictPolicy::Overwrite => ConflictPolicy::Rename,
+            ConflictPolicy::Rename => ConflictPolicy::Skip,
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let policy = match self {
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::Rename => "rename",
+        };
+        write!(f, "{}", policy)
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = Error;
+
+    fn from_str(policy: &str) -> Result<Self> {
+        match policy {
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "rename" => Ok(ConflictPolicy::Rename),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown conflict policy {}, expected skip, overwrite or rename",
+                policy
+            ))),
+        }
+    }
+}
+
+/// Outcome of an import, or of a dry run when nothing was written.
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub added: Vec<String>,
+    pub overwritten: Vec<String>,
+    /// Imported names with the names they were saved under
+    pub renamed: Vec<(String, String)>,
+    /// Names with the reason they were not imported
+    pub skipped: Vec<(String, String)>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        let imported = self.added.len() + self.overwritten.len() + self.renamed.len();
+        let mut summary = match self.dry_run {
+            true => format!("Would import {} entries", imported),
+            false => format!("Imported {} entries", imported),
+        };
+        summary.push_str(&format!(
+            " ({} overwritten, {} renamed), skipped {}",
+            self.overwritten.len(),
+            self.renamed.len(),
+            self.skipped.len()
+        ));
+        if self.dry_run {
+            summary.push_str(", nothing was written");
+        }
+        summary
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = |done: &'static str, planned: &'static str| match self.dry_run {
+            true => planned,
+            false => done,
+        };
+        let sections: [(&str, Vec<String>); 4] = [
+            (verb("Added", "To add"), self.added.clone()),
+            (
+                verb("Overwritten", "To overwrite"),
+                self.overwritten.clone(),
+            ),
+            (
+                verb("Renamed", "To rename"),
+                self.renamed
+                    .iter()
+                    .map(|(name, saved)| format!("{} -> {}", name, saved))
+                    .collect(),
+            ),
+            (
+                "Skipped",
+                self.skipped
+                    .iter()
+                    .map(|(name, reason)| format!("{}: {}", name, reason))
+                    .collect(),
+            ),
+        ];
+        for (title, lines) in sections {
+            if lines.is_empty() {
+                continue;
+            }
+            writeln!(f, "{} ({})", title, lines.len())?;
+            for line in lines {
+                writeln!(f, "  {}", line)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "{}", self.summary())
+    }
+}
+
+/// Where an imported entry goes.
+enum Target {
+    Add(String),
+    Overwrite(String),
+    Rename(String),
+    Skip(String),
+}
+
+/// Picks the name to save an entry under. `taken` holds names used by the
+/// vault and by entries planned earlier in the same import.
+fn plan(name: &str, taken: &HashSet<String>, policy: ConflictPolicy) -> Target {
+    if !taken.contains(name) {
+        return Target::Add(name.to_string());
+    }
+    match policy {
+        ConflictPolicy::Skip => Target::Skip("name already taken".to_string()),
+        ConflictPolicy::Overwrite => Target::Overwrite(name.to_string()),
+        ConflictPolicy::Rename => {
+            let free = (2..)
+                .map(|n| format!("{} ({})", name, n))
+                .find(|candidate| !taken.contains(candidate))
+                .unwrap_or_default();
+            Target::Rename(free)
+        }
+    }
+}
+
+/// Shows what importing would do with the names already in `index`,
+/// without writing anything.
+pub fn preview_import(
+    format: ImportFormat,
+    imported: &Imported,
+    index: &Index,
+    policy: ConflictPolicy,
+) -> ImportPreview {
+    let mut taken: HashSet<String> = index.names().cloned().collect();
+    let entries = imported
+        .entries
+        .iter()
+        .map(|ImportedEntry { name, entry }| {
+            let conflict = taken.contains(name);
+            let action = match plan(name, &taken, policy) {
+                Target::Add(saved) => {
+                    taken.insert(saved);
+                    "add".to_string()
+                }
+                Target::Overwrite(saved) => {
+                    taken.insert(saved);
+                    "overwrite".to_string()
+                }
+                Target::Rename(saved) => {
+                    let action = format!("save as {}", saved);
+                    taken.insert(saved);
+                    action
+                }
+                Target::Skip(reason) => format!("skip, {}", reason),
+            };
+            PreviewEntry {
+                name: name.clone(),
+                kind: entry.kind,
+                action,
+                conflict,
+            }
+        })
+        .collect();
+    ImportPreview {
+        format: format.to_string(),
+        policy: policy.to_string(),
+        mapping: imported.mapping.clone(),
+        entries,
+        skipped: imported.skipped.clone(),
+    }
+}
+
+/// Saves imported entries into a namespace, resolving name conflicts with
+/// `policy`. With `dry_run` the report is built without writing anything.
+pub async fn import_entries(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &mut Index,
+    imported: Imported,
+    policy: ConflictPolicy,
+    opaque_names: bool,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let mut report = ImportReport {
+        dry_run,
+        skipped: imported.skipped,
+        ..ImportReport::default()
+    };
+    let mut taken: HashSet<String> = index.names().cloned().collect();
+    for ImportedEntry { name, mut entry } in imported.entries {
+        let target = plan(&name, &taken, policy);
+        let saved_name = match &target {
+            Target::Add(saved) | Target::Overwrite(saved) | Target::Rename(saved) => saved,
+            Target::Skip(reason) => {
+                report.skipped.push((name, reason.clone()));
+                continue;
+            }
+        };
+        if !dry_run {
+            let saved = save_entry(
+                signer,
+                passwords_dir,
+                index,
+                saved_name,
+                &mut entry,
+                opaque_names,
+            )
+            .await;
+            if let Err(err) = saved {
+                report.skipped.push((name, err.to_string()));
+                continue;
+            }
+        }
+        taken.insert(saved_name.clone());
+        match target {
+            Target::Add(_) => report.added.push(name),
+            Target::Overwrite(_) => report.overwritten.push(name),
+            Target::Rename(saved) => report.renamed.push((name, saved)),
+            Target::Skip(_) => {}
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{plan, url_host, ConflictPolicy, RawEntry, Target};
+    use shared::password::EntryKind;
+
+    #[test]
+    fn plans_conflicting_names() {
+        let taken: HashSet<String> = ["mail", "mail (2)"].map(String::from).into();
+        assert!(
+            matches!(plan("bank", &taken, ConflictPolicy::Skip), Target::Add(name) if name == "bank")
+        );
+        assert!(matches!(
+            plan("mail", &taken, ConflictPolicy::Skip),
+            Target::Skip(_)
+        ));
+        assert!(
+            matches!(plan("mail", &taken, ConflictPolicy::Overwrite), Target::Overwrite(name) if name == "mail")
+        );
+        assert!(
+            matches!(plan("mail", &taken, ConflictPolicy::Rename), Target::Rename(name) if name == "mail (3)")
+        );
+        assert!("merge".parse::<ConflictPolicy>().is_err());
+    }
+
+    #[test]
+    fn names_untitled_entries_after_the_host() {
+        assert_eq!(
+            url_host("https://me@mail.example.com:443/inbox"),
+            Some("mail.example.com")
+        );
+        assert_eq!(url_host("example.com/login"), Some("example.com"));
+        assert_eq!(url_host("https://"), None);
+
+        let imported = RawEntry {
+            folders: vec!["Work/Shared".to_string()],
+            url: Some("https://vpn.example.com".to_string()),
+            password: "s3cret".to_string(),
+            notes: vec![String::new()],
+            ..RawEntry::default()
+        }
+        .into_imported();
+        assert_eq!(imported.name, "Work-Shared/vpn.example.com");
+        assert!(imported.entry.notes.is_none());
+
+        let note = RawEntry {
+            notes: vec!["guest network".to_string()],
+            ..RawEntry::default()
+        }
+        .into_imported();
+        assert_eq!(note.name, "Untitled");
+        assert_eq!(note.entry.kind, EntryKind::Note);
+    }
+}
@@ -0,0 +1,237 @@
+use std::io::{Cursor, Read};
+
+use serde::Deserialize;
+use serde_json::Value;
+use shared::error::{Error, Result};
+use zip::ZipArchive;
+
+use super::{Imported, RawEntry};
+
+/// File of a 1PUX archive holding the vaults and their items.
+const EXPORT_DATA: &str = "export.data";
+const DOCUMENT_CATEGORY: &str = "006";
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    accounts: Vec<Account>,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    #[serde(default)]
+    vaults: Vec<Vault>,
+}
+
+#[derive(Deserialize)]
+struct Vault {
+    attrs: VaultAttrs,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct VaultAttrs {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    category_uuid: String,
+    #[serde(default)]
+    details: Details,
+    #[serde(default)]
+    overview: Overview,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Details {
+    #[serde(default)]
+    login_fields: Vec<LoginField>,
+    notes_plain: Option<String>,
+    #[serde(default)]
+    sections: Vec<Section>,
+    /// Password of items in the password category
+    password: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LoginField {
+    #[serde(default)]
+    value: String,
+    designation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Section {
+    #[serde(default)]
+    fields: Vec<SectionField>,
+}
+
+#[derive(Deserialize)]
+struct SectionField {
+    #[serde(default)]
+    title: String,
+    /// Single key object naming the kind of the value, like `{"totp": "..."}`
+    #[serde(default)]
+    value: Value,
+}
+
+#[derive(Default, Deserialize)]
+struct Overview {
+    #[serde(default)]
+    title: String,
+    url: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Kind and text of a section field value. Values without a text form,
+/// like addresses, are left out.
+fn field_value(value: &Value) -> Option<(&str, String)> {
+    let (kind, value) = value.as_object()?.iter().next()?;
+    let text = match value {
+        Value::String(text) if !text.is_empty() => text.clone(),
+        Value::Number(number) => number.to_string(),
+        _ => return None,
+    };
+    Some((kind, text))
+}
+
+/// Reads a 1Password 1PUX export. Vaults become folders, login fields and
+/// the first one-time password are mapped to entry fields and other section
+/// fields are kept in the notes. Archived items and documents are skipped.
+pub fn read_1pux(data: &[u8]) -> Result<Imported> {
+    let bad_export = |err: String| Error::InvalidInput(format!("bad 1PUX export: {}", err));
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|e| bad_export(e.to_string()))?;
+    let mut json = String::new();
+    archive
+        .by_name(EXPORT_DATA)
+        .map_err(|e| bad_export(e.to_string()))?
+        .read_to_string(&mut json)?;
+    let export: Export = serde_json::from_str(&json).map_err(|e| bad_export(e.to_string()))?;
+
+    let mut imported = Imported {
+        mapping: [
+            ("vault", "folder"),
+            ("overview.title", "name"),
+            ("overview.url", "url"),
+            ("overview.tags", "tags"),
+            ("loginFields username", "username"),
+            ("loginFields password", "password"),
+            ("section totp", "otp"),
+            ("notesPlain", "notes"),
+            ("section fields", "notes"),
+        ]
+        .map(|(from, to)| (from.to_string(), to.to_string()))
+        .into(),
+        ..Imported::default()
+    };
+    let vaults = export
+        .accounts
+        .into_iter()
+        .flat_map(|account| account.vaults);
+    for vault in vaults {
+        for item in vault.items {
+            let mut raw = RawEntry {
+                folders: vec![vault.attrs.name.clone()],
+                title: item.overview.title,
+                url: item.overview.url,
+                tags: item.overview.tags,
+                ..RawEntry::default()
+            };
+            if item.state == "archived" || item.category_uuid == DOCUMENT_CATEGORY {
+                let reason = match item.state.as_str() {
+                    "archived" => "archived",
+                    _ => "documents are not supported",
+                };
+                let name = raw.into_imported().name;
+                imported.skipped.push((name, reason.to_string()));
+                continue;
+            }
+            for field in item.details.login_fields {
+                match field.designation.as_deref() {
+                    Some("username") => raw.username = Some(field.value),
+                    Some("password") => raw.password = field.value,
+                    _ => {}
+                }
+            }
+            if raw.password.is_empty() {
+                raw.password = item.details.password.unwrap_or_default();
+            }
+            raw.notes.extend(item.details.notes_plain);
+            let fields = item.details.sections.into_iter().flat_map(|s| s.fields);
+            for field in fields {
+                match field_value(&field.value) {
+                    Some(("totp", text)) if raw.otp.is_none() => raw.otp = Some(text),
+                    Some((_, text)) => raw.notes.push(format!("{}: {}", field.title, text)),
+                    None => {}
+                }
+            }
+            imported.entries.push(raw.into_imported());
+        }
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::read_1pux;
+
+    #[test]
+    fn reads_vault_items() {
+        let data = r#"{"accounts": [{"attrs": {"name": "Me"}, "vaults": [{
+            "attrs": {"uuid": "v1", "name": "Private"},
+            "items": [
+                {"uuid": "i1", "state": "active", "categoryUuid": "001",
+                 "overview": {"title": "Mail", "url": "https://mail.example.com", "tags": ["mail"]},
+                 "details": {
+                    "loginFields": [
+                        {"value": "me", "designation": "username"},
+                        {"value": "hunter2", "designation": "password"}
+                    ],
+                    "notesPlain": "first",
+                    "sections": [{"title": "", "fields": [
+                        {"title": "one-time password", "value": {"totp": "otpauth://totp/Mail?secret=JBSWY3DPEHPK3PXP"}},
+                        {"title": "PIN", "value": {"concealed": "1234"}},
+                        {"title": "Home", "value": {"address": {"city": "Berlin"}}}
+                    ]}]
+                 }},
+                {"uuid": "i2", "state": "archived", "categoryUuid": "001",
+                 "overview": {"title": "Old"}, "details": {}}
+            ]
+        }]}]}"#;
+        let mut archive = ZipWriter::new(Cursor::new(vec![]));
+        archive
+            .start_file("export.data", FileOptions::default())
+            .unwrap();
+        archive.write_all(data.as_bytes()).unwrap();
+        let archive = archive.finish().unwrap().into_inner();
+
+        let imported = read_1pux(&archive).unwrap();
+        let mail = &imported.entries[0];
+        assert_eq!(mail.name, "Private/Mail");
+        assert_eq!(mail.entry.body.expose(), "hunter2");
+        assert_eq!(mail.entry.metadata.username.as_deref(), Some("me"));
+        assert_eq!(mail.entry.metadata.tags, ["mail"]);
+        assert!(mail.entry.otp.as_deref().unwrap().starts_with("otpauth://"));
+        assert_eq!(
+            mail.entry.notes.as_ref().map(|notes| notes.expose()),
+            Some("first\nPIN: 1234")
+        );
+        assert_eq!(
+            imported.skipped,
+            [("Private/Old".to_string(), "archived".to_string())]
+        );
+    }
+}
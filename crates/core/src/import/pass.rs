@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use crypto::gpg::GpgKey;
+use shared::{
+    error::Result,
+    password::{Entry, EntryKind},
+    secret::SecretString,
+};
+use tokio::fs;
+
+use super::{Imported, ImportedEntry};
+
+const GPG_EXTENSION: &str = ".gpg";
+
+/// Turns a decrypted pass file into an entry: the first line is the
+/// password, the lines after it are kept as notes.
+fn parse_entry(content: &str) -> Entry {
+    let (password, notes) = content.split_once('\n').unwrap_or((content, ""));
+    let notes = notes.trim_end();
+    let mut entry = Entry::new(
+        EntryKind::Password,
+        password.trim_end_matches('\r').to_string(),
+    );
+    entry.notes = (!notes.is_empty()).then(|| SecretString::new(notes.replace("\r\n", "\n")));
+    entry
+}
+
+/// `.gpg` files of a password store with their entry names, `work/mail`
+/// for `work/mail.gpg`. Hidden files and folders, like `.git`, are left out.
+async fn list_store(root: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+    let mut dirs = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir_path, prefix)) = dirs.pop() {
+        let mut dir = fs::read_dir(&dir_path).await?;
+        while let Some(item) = dir.next_entry().await? {
+            let Some(file_name) = item.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if file_name.starts_with('.') {
+                continue;
+            }
+            let metadata = item.metadata().await?;
+            if metadata.is_dir() {
+                dirs.push((item.path(), format!("{}{}/", prefix, file_name)));
+            } else if let Some(name) = file_name.strip_suffix(GPG_EXTENSION) {
+                files.push((format!("{}{}", prefix, name), item.path()));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Reads a `pass` store, decrypting every entry in memory with the owner's key.
+/// Entries that can not be decrypted are reported as skipped.
+pub async fn read_password_store(root: &Path, key: &GpgKey) -> Result<Imported> {
+    let mut imported = Imported::default();
+    for (name, path) in list_store(root).await? {
+        let content = fs::read(&path)
+            .await
+            .map_err(Into::into)
+            .and_then(|data| key.decrypt(&data))
+            .and_then(|content| Ok(String::from_utf8(content)?));
+        match content {
+            Ok(content) => imported.entries.push(ImportedEntry {
+                name,
+                entry: parse_entry(&content),
+            }),
+            Err(err) => imported.skipped.push((name, err.to_string())),
+        }
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_entry;
+
+    #[test]
+    fn splits_password_and_notes() {
+        let entry = parse_entry("hunter2\r\nlogin: me\r\nurl: example.com\r\n");
+        assert_eq!(entry.body.expose(), "hunter2");
+        assert_eq!(
+            entry.notes.as_ref().map(|notes| notes.expose()),
+            Some("login: me\nurl: example.com")
+        );
+
+        let entry = parse_entry("hunter2\n");
+        assert_eq!(entry.body.expose(), "hunter2");
+        assert!(entry.notes.is_none());
+        assert_eq!(parse_entry("hunter2").body.expose(), "hunter2");
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crypto::signer::Signer;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use shared::{
+    error::{Error, Result},
+    password::{Entry, EntryKind, EntryMetadata, Password},
+};
+use tokio::fs;
+
+use crate::files::{list_entry_files, read_password_bytes, write_atomically};
+
+const INDEX_VERSION: u32 = 1;
+pub const INDEX_FILE_NAME: &str = ".passm-index";
+
+/// What the index knows about an entry: everything but the secret.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexRecord {
+    /// Random file name of the entry when names are hidden on disk,
+    /// otherwise the file is named after the entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub kind: EntryKind,
+    #[serde(default, flatten)]
+    pub metadata: EntryMetadata,
+}
+
+impl IndexRecord {
+    pub fn new(entry: &Entry, file: Option<String>) -> Self {
+        Self {
+            file,
+            kind: entry.kind,
+            metadata: entry.metadata.clone(),
+        }
+    }
+}
+
+/// Metadata of all entries of a namespace, stored in one file encrypted with
+/// the namespace key, so the list can be searched after a single decryption.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    entries: BTreeMap<String, IndexRecord>,
+}
+
+impl Index {
+    pub fn get(&self, name: &str) -> Option<&IndexRecord> {
+        self.entries.get(name)
+    }
+
+    /// Names of all indexed entries.
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.entries.keys()
+    }
+
+    /// Name of the file holding an entry.
+    pub fn file_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.entries
+            .get(name)
+            .and_then(|record| record.file.as_deref())
+            .unwrap_or(name)
+    }
+
+    /// Whether some entries are not stored the way `opaque_names` asks for.
+    pub fn needs_file_name_migration(&self, opaque_names: bool) -> bool {
+        self.entries
+            .values()
+            .any(|record| record.file.is_some() != opaque_names)
+    }
+
+    pub fn insert(&mut self, name: String, record: IndexRecord) {
+        self.entries.insert(name, record);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<IndexRecord> {
+        self.entries.remove(name)
+    }
+
+    /// Turns listed files into entries: names files after the entries they
+    /// hold and fills kind and metadata. Files unknown to the index are dropped.
+    pub fn resolve(&self, files: Vec<Password>) -> Vec<Password> {
+        let names: HashMap<&str, (&String, &IndexRecord)> = self
+            .entries
+            .iter()
+            .map(|(name, record)| (record.file.as_deref().unwrap_or(name), (name, record)))
+            .collect();
+        files
+            .into_iter()
+            .filter_map(|file| {
+                let (name, record) = names.get(file.name.as_str())?;
+                Some(Password {
+                    name: name.to_string(),
+                    kind: Some(record.kind),
+                    metadata: record.metadata.clone(),
+                    ..file
+                })
+            })
+            .collect()
+    }
+
+    /// Drops records of entries whose file is gone and returns the files missing from the index.
+    fn reconcile(&mut self, files: &BTreeSet<String>) -> Vec<String> {
+        self.entries
+            .retain(|name, record| files.contains(record.file.as_deref().unwrap_or(name.as_str())));
+        let indexed: BTreeSet<&str> = self
+            .entries
+            .iter()
+            .map(|(name, record)| record.file.as_deref().unwrap_or(name.as_str()))
+            .collect();
+        files
+            .iter()
+            .filter(|file| !indexed.contains(file.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Adds the entry read from `file`. The name stored in the payload wins
+    /// over the file name, so entries with hidden names keep them.
+    fn insert_file(&mut self, file: String, entry: &Entry) {
+        let mut name = entry.name.clone().unwrap_or_else(|| file.clone());
+        if self.entries.contains_key(&name) {
+            warn!(
+                "Entry {} is stored twice, indexing {} separately",
+                name, file
+            );
+            name = format!("{} ({})", name, file);
+        }
+        let file = (file != name).then_some(file);
+        self.insert(name, IndexRecord::new(entry, file));
+    }
+}
+
+/// Index file of a passwords directory. It is kept inside the directory, so it
+/// moves and gets deleted together with the entries, and is never listed.
+pub fn index_path(passwords_dir: &Path) -> PathBuf {
+    passwords_dir.join(INDEX_FILE_NAME)
+}
+
+async fn read_index(signer: &Signer, passwords_dir: &Path) -> Result<Index> {
+    let encrypted = fs::read(index_path(passwords_dir)).await?;
+    let index: Index = serde_json::from_slice(&signer.decrypt(&encrypted)?)
+        .map_err(|e| Error::Decryption(format!("metadata index: {}", e)))?;
+    if index.version != INDEX_VERSION {
+        return Err(Error::Decryption(format!(
+            "metadata index has unknown version {}",
+            index.version
+        )));
+    }
+    Ok(index)
+}
+
+/// Encrypts and writes the index. The file is replaced atomically.
+pub async fn write_index(signer: &Signer, passwords_dir: &Path, index: &Index) -> Result<()> {
+    let index = Index {
+        version: INDEX_VERSION,
+        ..index.clone()
+    };
+    let plain = serde_json::to_vec(&index).map_err(|e| Error::Encryption(e.to_string()))?;
+    write_atomically(&index_path(passwords_dir), &signer.encrypt(&plain)?).await
+}
+
+/// Reads the index and brings it in line with the entries on disk. A missing
+/// or unreadable index is rebuilt by decrypting every entry. Returns the index
+/// and whether it had to be written again.
+pub async fn load_index(signer: &Signer, passwords_dir: &Path) -> Result<(Index, bool)> {
+    let mut index = match read_index(signer, passwords_dir).await {
+        Ok(index) => index,
+        Err(err) => {
+            warn!("Rebuilding metadata index: {}", err);
+            Index::default()
+        }
+    };
+    let files = list_entry_files(passwords_dir).await?;
+    let before = index.entries.len();
+    let missing = index.reconcile(&files);
+    let changed = !missing.is_empty() || index.entries.len() != before;
+
+    for file in missing {
+        let bytes = read_password_bytes(&passwords_dir.join(&file)).await?;
+        match signer
+            .decrypt(&bytes)
+            .and_then(|plain| Entry::from_bytes(&plain))
+        {
+            Ok(entry) => index.insert_file(file, &entry),
+            Err(err) => {
+                // Still listed under its file name, so it can be deleted
+                warn!("Unable to index {}: {}", file, err);
+                index.insert_file(file, &Entry::new(EntryKind::Password, String::new()));
+            }
+        }
+    }
+    if changed {
+        write_index(signer, passwords_dir, &index).await?;
+    }
+    Ok((index, changed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, path::Path};
+
+    use shared::password::{Entry, EntryKind, Password};
+
+    use super::{index_path, Index, IndexRecord, INDEX_FILE_NAME};
+    use crate::files::is_reserved_name;
+
+    fn hidden(file: &str) -> IndexRecord {
+        IndexRecord {
+            file: Some(file.to_string()),
+            ..IndexRecord::default()
+        }
+    }
+
+    #[test]
+    fn reconciles_with_entries_on_disk() {
+        let mut index = Index::default();
+        index.insert("github".to_string(), IndexRecord::default());
+        index.insert("removed".to_string(), IndexRecord::default());
+        index.insert("bank".to_string(), hidden("3f9a"));
+        let files: BTreeSet<String> = ["github", "mail", "3f9a"]
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+
+        assert_eq!(index.reconcile(&files), vec!["mail".to_string()]);
+        assert!(index.get("removed").is_none());
+        assert!(index.get("github").is_some());
+        assert_eq!(index.file_name("bank"), "3f9a");
+        assert_eq!(index.file_name("github"), "github");
+    }
+
+    #[test]
+    fn resolves_hidden_names() {
+        let mut index = Index::default();
+        index.insert("github".to_string(), IndexRecord::default());
+        let mut entry = Entry::new(EntryKind::Note, String::new());
+        entry.name = Some("bank".to_string());
+        index.insert_file("3f9a".to_string(), &entry);
+        // A second file claiming the same name is kept apart
+        index.insert_file("77c1".to_string(), &entry);
+        assert!(index.needs_file_name_migration(true));
+        assert!(index.needs_file_name_migration(false));
+
+        let files = ["3f9a", "github", "77c1", "unknown"]
+            .iter()
+            .map(|file| Password {
+                name: file.to_string(),
+                ..Password::default()
+            })
+            .collect();
+        let names: Vec<String> = index
+            .resolve(files)
+            .into_iter()
+            .map(|password| password.name)
+            .collect();
+        assert_eq!(names, vec!["bank", "github", "bank (77c1)"]);
+    }
+
+    #[test]
+    fn index_lives_in_passwords_dir() {
+        assert_eq!(
+            index_path(Path::new("/vault/namespaces/work/passwds")),
+            Path::new("/vault/namespaces/work/passwds/.passm-index")
+        );
+        assert!(is_reserved_name(INDEX_FILE_NAME));
+        assert!(is_reserved_name("github.passm-tmp"));
+        assert!(!is_reserved_name("github"));
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use crypto::{
+    pgp::{KeyType, SignedSecretKey},
+    signer::Signer,
+};
+use shared::{
+    error::{Error, Result},
+    secret::SecretString,
+};
+use tokio::fs;
+
+pub async fn read_signed_key(path: &Path) -> Result<SignedSecretKey> {
+    let file = fs::read_to_string(path)
+        .await
+        .map_err(|_| Error::Key(format!("no key found at {}", path.display())))?;
+    Signer::parse_signed_secret_from_string(file)
+}
+
+pub async fn create_signed_key(path: &Path, passphrase: String) -> Result<SignedSecretKey> {
+    let signed = tokio::task::spawn_blocking(move || {
+        let sk = Signer::generate_key(KeyType::Rsa(2048), Some(passphrase.clone()))?;
+        Signer::sign_key(sk, Some(passphrase))
+    })
+    .await
+    .map_err(|e| Error::Key(e.to_string()))??;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let armored = signed
+        .to_armored_string(None)
+        .map_err(|e| Error::Key(e.to_string()))?;
+    fs::write(path, armored).await?;
+    Ok(signed)
+}
+
+/// Reads the key at `path` and returns a signer if `passphrase` unlocks it.
+pub async fn unlock_signer(path: &Path, passphrase: SecretString) -> Result<Signer> {
+    let ssk = read_signed_key(path).await?;
+    Signer::verify_key_passphrase(&ssk, Some(passphrase.expose().to_string()))?;
+    Ok(Signer::new(ssk, Some(passphrase)))
+}
@@ -1,3 +1,12 @@
 pub mod app;
-pub mod files;
+pub mod audit;
+pub mod backup;
+pub mod breach;
+pub mod copy;
 pub mod exporter;
+pub mod files;
+pub mod import;
+pub mod index;
+pub mod keys;
+pub mod otp;
+pub mod paper;
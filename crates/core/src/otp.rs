@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use crypto::{otp::Otp, signer::Signer};
+use shared::{
+    error::{Error, Result},
+    password::{Entry, EntryKind, OtpCode},
+    secret::SecretString,
+};
+
+use crate::{
+    files::{read_entry, save_entry},
+    index::Index,
+};
+
+fn entry_otp(name: &str, entry: &Entry) -> Result<Otp> {
+    let otp = entry
+        .otp
+        .as_deref()
+        .ok_or_else(|| Error::InvalidInput(format!("{} has no one-time password", name)))?;
+    Otp::parse(otp)
+}
+
+/// Current code of an entry's one-time password. Counter based passwords are
+/// moved on and saved, so every code is handed out once.
+pub async fn next_code(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &mut Index,
+    name: &str,
+    opaque_names: bool,
+) -> Result<OtpCode> {
+    let mut entry = read_entry(signer, passwords_dir, index, name).await?;
+    let mut otp = entry_otp(name, &entry)?;
+    let code = otp.now();
+    if code.remaining.is_none() {
+        otp.advance();
+        entry.otp = Some(otp.to_uri());
+        save_entry(signer, passwords_dir, index, name, &mut entry, opaque_names).await?;
+    }
+    Ok(code)
+}
+
+/// Stores an `otpauth://` URI or base32 secret on an entry, creating an
+/// entry without password if there is none with that name.
+pub async fn import_otp(
+    signer: &Signer,
+    passwords_dir: &Path,
+    index: &mut Index,
+    name: &str,
+    otp: &str,
+    opaque_names: bool,
+) -> Result<()> {
+    Otp::parse(otp)?;
+    let mut entry = match index.get(name) {
+        Some(_) => read_entry(signer, passwords_dir, index, name).await?,
+        None => Entry::new(EntryKind::Password, String::new()),
+    };
+    entry.otp = Some(otp.trim().to_string());
+    save_entry(signer, passwords_dir, index, name, &mut entry, opaque_names).await
+}
+
+/// Code shown for a revealed entry. It is computed again only once its
+/// period rolls over or the one-time password of the entry changes, not on
+/// every redraw.
+#[derive(Default)]
+pub struct CachedCode {
+    /// One-time password the code was computed from
+    source: Option<SecretString>,
+    /// None when the one-time password does not parse
+    code: Option<String>,
+    /// When a time based code runs out, in seconds since the Unix epoch
+    expires: Option<u64>,
+}
+
+impl CachedCode {
+    /// Code of `otp` at `unix_time`, with the seconds it stays valid.
+    pub fn get(&mut self, otp: Option<&str>, unix_time: u64) -> Option<OtpCode> {
+        let Some(otp) = otp else {
+            *self = Self::default();
+            return None;
+        };
+        let same = self
+            .source
+            .as_ref()
+            .is_some_and(|source| source.expose() == otp);
+        let expired = self.expires.is_some_and(|expires| unix_time >= expires);
+        if !same || expired {
+            let code = Otp::parse(otp).ok().map(|otp| otp.code_at(unix_time));
+            self.expires = code
+                .as_ref()
+                .and_then(|code| code.remaining)
+                .map(|remaining| unix_time + remaining);
+            self.code = code.map(|code| code.code);
+            self.source = Some(SecretString::new(otp.to_string()));
+        }
+        self.code.as_ref().map(|code| OtpCode {
+            code: code.clone(),
+            remaining: self.expires.map(|expires| expires - unix_time),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachedCode;
+
+    #[test]
+    fn computes_codes_once_per_period() {
+        let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+        let mut cached = CachedCode::default();
+        let code = cached.get(Some(secret), 31).unwrap();
+        assert_eq!(code.code, "287082");
+        assert_eq!(code.remaining, Some(29));
+        assert_eq!(cached.get(Some(secret), 59).unwrap().remaining, Some(1));
+        assert_eq!(cached.get(Some(secret), 60).unwrap().code, "359152");
+
+        let counter = format!("otpauth://hotp/x?secret={}&counter=3", secret);
+        assert_eq!(cached.get(Some(&counter), 60).unwrap().code, "969429");
+        assert!(cached.get(Some("not base32!"), 60).is_none());
+        assert!(cached.get(None, 60).is_none());
+    }
+}
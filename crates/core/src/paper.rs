@@ -0,0 +1,409 @@
+use std::collections::BTreeMap;
+
+use base32::Alphabet;
+use crypto::hash::sha256;
+use qrcode::{render::unicode::Dense1x2, EcLevel, QrCode};
+use shared::error::{Error, Result};
+
+const TITLE: &str = "PASSM PAPER BACKUP 1";
+const BYTES_PER_LINE: usize = 25;
+const GROUP_LEN: usize = 5;
+const ALPHABET: Alphabet = Alphabet::RFC4648 { padding: false };
+const BASE32_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+/// Paper lines per QR code, small enough for a terminal
+const LINES_PER_QR: usize = 8;
+
+fn encode(data: &[u8]) -> String {
+    base32::encode(ALPHABET, data)
+}
+
+/// Checksum of a line, covering its number so swapped lines are caught too.
+fn checksum(number: usize, chars: &str) -> String {
+    let hash = sha256(format!("{}:{}", number, chars).as_bytes());
+    encode(&hash[..3])[..4].to_string()
+}
+
+fn digest(data: &[u8]) -> String {
+    encode(&sha256(data)[..5])
+}
+
+/// Base32 characters a line of `bytes` bytes is written with.
+fn line_chars(bytes: usize) -> usize {
+    (bytes * 8).div_ceil(5)
+}
+
+/// Renders data, like the encrypted key export, as a document to print:
+/// numbered base32 lines in groups of five characters, each with its own
+/// checksum, and a header with the size and a digest of the whole data.
+pub fn to_paper(data: &[u8], description: &str) -> String {
+    let chunks: Vec<&[u8]> = data.chunks(BYTES_PER_LINE).collect();
+    let mut paper = format!(
+        "{}\n{}\nLines: {}  Bytes: {}  Digest: {}\n\n",
+        TITLE,
+        description,
+        chunks.len(),
+        data.len(),
+        digest(data)
+    );
+    for (position, chunk) in chunks.iter().enumerate() {
+        let number = position + 1;
+        let chars = encode(chunk);
+        let groups: Vec<&str> = chars
+            .as_bytes()
+            .chunks(GROUP_LEN)
+            .map(|group| std::str::from_utf8(group).unwrap_or_default())
+            .collect();
+        paper.push_str(&format!(
+            "{:03}  {}  {}\n",
+            number,
+            groups.join(" "),
+            checksum(number, &chars)
+        ));
+    }
+    paper
+}
+
+/// Terminal renderings of QR codes holding the paper lines, a few lines per
+/// code. Scanned codes can be pasted into the restore like typed lines.
+pub fn to_qr_codes(paper: &str) -> Result<Vec<String>> {
+    let header = paper
+        .lines()
+        .find(|line| line.starts_with("Lines:"))
+        .unwrap_or_default();
+    let lines: Vec<&str> = paper
+        .lines()
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_digit()))
+        .collect();
+    let count = lines.len().div_ceil(LINES_PER_QR);
+    lines
+        .chunks(LINES_PER_QR)
+        .enumerate()
+        .map(|(position, lines)| {
+            let content = format!(
+                "PASSM PAPER {}/{}\n{}\n{}",
+                position + 1,
+                count,
+                header,
+                lines.join("\n")
+            );
+            let code = QrCode::with_error_correction_level(content, EcLevel::M)
+                .map_err(|e| Error::InvalidInput(format!("failed to render QR code: {}", e)))?;
+            // Light modules are drawn, which reads right on dark terminals
+            Ok(code
+                .render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .build())
+        })
+        .collect()
+}
+
+/// Uppercases typed characters and maps digits base32 does not use to the
+/// letters they are mistaken for.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            '0' => 'O',
+            '1' => 'I',
+            '8' => 'B',
+            c => c,
+        })
+        .collect()
+}
+
+/// Position of a character as printed, like `group 3 character 2`.
+fn position(index: usize) -> String {
+    format!(
+        "group {} character {}",
+        index / GROUP_LEN + 1,
+        index % GROUP_LEN + 1
+    )
+}
+
+/// Finds the single character change that makes a line match its checksum.
+fn correction(number: usize, chars: &str, expected: &str) -> Option<(usize, char)> {
+    let mut found = None;
+    let mut candidate = chars.as_bytes().to_vec();
+    for index in 0..candidate.len() {
+        let original = candidate[index];
+        for &replacement in BASE32_CHARS.iter().filter(|&&c| c != original) {
+            candidate[index] = replacement;
+            let text = std::str::from_utf8(&candidate).unwrap_or_default();
+            if checksum(number, text) == expected {
+                // More than one fix means the typo can not be told apart
+                if found.is_some() {
+                    return None;
+                }
+                found = Some((index, replacement as char));
+            }
+        }
+        candidate[index] = original;
+    }
+    found
+}
+
+/// What a line given to [`PaperReader::push`] turned out to be.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PaperLine {
+    /// Header with the line count, size and digest
+    Header,
+    /// Data line that matched its checksum
+    Data(usize),
+    /// Title, description or empty line
+    Ignored,
+}
+
+/// Puts data back together from paper lines typed or pasted in any order,
+/// telling exactly which line, and where possible which character, is wrong.
+#[derive(Default)]
+pub struct PaperReader {
+    lines: BTreeMap<usize, Vec<u8>>,
+    /// Line count, size and digest from the header
+    expected: Option<(usize, usize, String)>,
+}
+
+impl PaperReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read_header(&mut self, line: &str) -> Result<PaperLine> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let value = |key: &str| {
+            tokens
+                .iter()
+                .position(|token| token.eq_ignore_ascii_case(key))
+                .and_then(|position| tokens.get(position + 1))
+        };
+        let number = |key: &str| value(key).and_then(|value| value.parse::<usize>().ok());
+        match (number("Lines:"), number("Bytes:"), value("Digest:")) {
+            (Some(lines), Some(bytes), Some(digest)) => {
+                let fits = |count: usize| count.checked_mul(BYTES_PER_LINE);
+                match (fits(lines.saturating_sub(1)), fits(lines)) {
+                    (Some(full), Some(capacity))
+                        if lines > 0 && full < bytes && bytes <= capacity => {}
+                    _ => {
+                        return Err(Error::InvalidInput(format!(
+                            "header does not add up, {} lines can not hold {} bytes",
+                            lines, bytes
+                        )))
+                    }
+                }
+                self.expected = Some((lines, bytes, normalize(digest)));
+                Ok(PaperLine::Header)
+            }
+            _ => Err(Error::InvalidInput(
+                "header should read `Lines: N  Bytes: N  Digest: XXXXXXXX`".to_string(),
+            )),
+        }
+    }
+
+    /// Characters expected on a line, when the header is known.
+    fn expected_chars(&self, number: usize) -> Result<Option<usize>> {
+        let Some((lines, bytes, _)) = self.expected.as_ref() else {
+            return Ok(None);
+        };
+        match number.cmp(lines) {
+            std::cmp::Ordering::Less => Ok(Some(line_chars(BYTES_PER_LINE))),
+            std::cmp::Ordering::Equal => (lines - 1)
+                .checked_mul(BYTES_PER_LINE)
+                .and_then(|full| bytes.checked_sub(full))
+                .map(|last| Some(line_chars(last)))
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!("{} lines can not hold {} bytes", lines, bytes))
+                }),
+            std::cmp::Ordering::Greater => Ok(None),
+        }
+    }
+
+    /// Reads one line. Errors name the line and what is wrong with it, the
+    /// line can then be typed again.
+    pub fn push(&mut self, line: &str) -> Result<PaperLine> {
+        let line = line.trim();
+        if line.to_lowercase().starts_with("lines:") {
+            return self.read_header(line);
+        }
+        let mut tokens = line.split_whitespace();
+        let Some(number) = tokens
+            .next()
+            .filter(|token| token.chars().all(|c| c.is_ascii_digit()))
+            .and_then(|token| token.parse::<usize>().ok())
+        else {
+            return Ok(PaperLine::Ignored);
+        };
+        let invalid =
+            |message: String| Error::InvalidInput(format!("line {}: {}", number, message));
+        let mut tokens: Vec<String> = tokens.map(normalize).collect();
+        let expected = tokens
+            .pop()
+            .ok_or_else(|| invalid("the line has no checksum".to_string()))?;
+        let chars = tokens.concat();
+
+        if let Some((lines, _, _)) = &self.expected {
+            if number == 0 || number > *lines {
+                return Err(invalid(format!("there are only {} lines", lines)));
+            }
+        }
+        if let Some(index) = chars.bytes().position(|c| !BASE32_CHARS.contains(&c)) {
+            return Err(invalid(format!(
+                "{} is {:?}, only letters and digits 2 to 7 are used",
+                position(index),
+                chars.as_bytes()[index] as char
+            )));
+        }
+        if let Some(length) = self.expected_chars(number)? {
+            if chars.len() != length {
+                return Err(invalid(format!(
+                    "{} characters instead of {}, one was left out or typed twice",
+                    chars.len(),
+                    length
+                )));
+            }
+        }
+        if checksum(number, &chars) != expected {
+            return Err(invalid(match correction(number, &chars, &expected) {
+                Some((index, replacement)) => format!(
+                    "checksum does not match, {} is probably {:?}, not {:?}",
+                    position(index),
+                    replacement,
+                    chars.as_bytes()[index] as char
+                ),
+                None => {
+                    "checksum does not match, check the whole line and its checksum".to_string()
+                }
+            }));
+        }
+        let data = base32::decode(ALPHABET, &chars)
+            .ok_or_else(|| invalid("the characters do not decode".to_string()))?;
+        self.lines.insert(number, data);
+        Ok(PaperLine::Data(number))
+    }
+
+    /// Line numbers not read yet, all of them while the header is unknown.
+    pub fn missing(&self) -> Vec<usize> {
+        let lines = self.expected.as_ref().map_or(0, |(lines, _, _)| *lines);
+        (1..=lines)
+            .filter(|number| !self.lines.contains_key(number))
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.expected.is_some() && self.missing().is_empty()
+    }
+
+    /// Joins the lines and checks them against the size and digest of the header.
+    pub fn finish(&self) -> Result<Vec<u8>> {
+        let Some((_, bytes, expected_digest)) = &self.expected else {
+            return Err(Error::InvalidInput(
+                "the `Lines:` header line is missing".to_string(),
+            ));
+        };
+        let missing = self.missing();
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing.iter().map(|n| n.to_string()).collect();
+            return Err(Error::InvalidInput(format!(
+                "lines {} are missing",
+                missing.join(", ")
+            )));
+        }
+        let data: Vec<u8> = self.lines.values().flatten().copied().collect();
+        if data.len() != *bytes || digest(&data) != *expected_digest {
+            return Err(Error::InvalidInput(
+                "every line matches its checksum but the whole does not match the digest, check the header"
+                    .to_string(),
+            ));
+        }
+        Ok(data)
+    }
+}
+
+/// Reads a whole paper backup at once, reporting every bad line.
+pub fn read_paper(text: &str) -> Result<Vec<u8>> {
+    let mut reader = PaperReader::new();
+    let errors: Vec<String> = text
+        .lines()
+        .filter_map(|line| reader.push(line).err())
+        .map(|err| err.to_string())
+        .collect();
+    if !errors.is_empty() {
+        return Err(Error::InvalidInput(errors.join("\n")));
+    }
+    reader.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> Vec<u8> {
+        (0..=255u8).cycle().take(60).collect()
+    }
+
+    #[test]
+    fn reads_printed_lines_back() {
+        let paper = to_paper(&data(), "Key export of namespace default");
+        assert!(paper.starts_with(TITLE));
+        assert!(paper.contains("Lines: 3  Bytes: 60"));
+        assert_eq!(read_paper(&paper).unwrap(), data());
+        // Lowercase, extra spaces and misread digits are fine
+        let typed = paper.to_lowercase().replace("  ", "   ");
+        assert_eq!(read_paper(&typed).unwrap(), data());
+
+        let codes = to_qr_codes(&paper).unwrap();
+        assert_eq!(codes.len(), 1);
+        assert!(codes[0].contains('\u{2588}'));
+    }
+
+    #[test]
+    fn pinpoints_typos() {
+        let paper = to_paper(&data(), "");
+        let line = paper.lines().find(|line| line.starts_with("002")).unwrap();
+        let mut reader = PaperReader::new();
+        reader.push(paper.lines().nth(2).unwrap()).unwrap();
+
+        // Swap the third character of the second group for another letter
+        let mut typo = line.to_string();
+        let index = 5 + 6 + 2;
+        let original = typo.as_bytes()[index] as char;
+        let wrong = if original == 'A' { "B" } else { "A" };
+        typo.replace_range(index..index + 1, wrong);
+        let err = reader.push(&typo).unwrap_err().to_string();
+        assert!(err.starts_with("line 2: checksum does not match, group 2 character 3"));
+        assert!(err.contains(&format!(
+            "{:?}, not {:?}",
+            original,
+            wrong.chars().next().unwrap()
+        )));
+
+        // Drop the first character of the first group
+        let short = format!("{}{}", &line[..5], &line[6..]);
+        assert!(reader
+            .push(&short)
+            .unwrap_err()
+            .to_string()
+            .contains("characters instead of 40"));
+        assert!(reader
+            .push("002 ABC9D X")
+            .unwrap_err()
+            .to_string()
+            .contains("group 1 character 4"));
+
+        assert_eq!(reader.push(line).unwrap(), PaperLine::Data(2));
+        assert_eq!(reader.missing(), [1, 3]);
+        assert!(reader
+            .finish()
+            .unwrap_err()
+            .to_string()
+            .contains("lines 1, 3"));
+        assert!(reader.push("004 AAAAA AAAA").is_err());
+
+        // A header that does not add up is refused before any line is read
+        for header in [
+            "Lines: 3 Bytes: 6 Digest: AAAAAAAA",
+            "Lines: 0 Bytes: 0 Digest: A",
+        ] {
+            let err = PaperReader::new().push(header).unwrap_err().to_string();
+            assert!(err.contains("header does not add up"));
+        }
+    }
+}
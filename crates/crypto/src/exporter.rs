@@ -1,4 +1,3 @@
-use anyhow::{anyhow, Ok, Result};
 use log::debug;
 use rust_crypto::pbkdf2::pbkdf2;
 use rust_crypto::{
@@ -7,6 +6,7 @@ use rust_crypto::{
     hmac::Hmac,
     sha2::Sha256,
 };
+use shared::error::{Error, Result};
 
 fn hash_master_password(master_password: String) -> [u8; 32] {
     let salt = [0u8; 16];
@@ -18,13 +18,6 @@ fn hash_master_password(master_password: String) -> [u8; 32] {
     let mut mac = Hmac::new(Sha256::new(), master_password.as_bytes());
 
     pbkdf2(&mut mac, &salt[..], 256, &mut key);
-    debug!(
-        "{:?}",
-        key.to_vec()
-            .iter()
-            .map(|e| format!("{:02X}", e))
-            .collect::<String>()
-    );
     key
 }
 
@@ -32,14 +25,12 @@ pub fn export_private_key_bytes(plain: String, master_password: String) -> Resul
     let armored = plain.as_bytes();
     debug!("Got armored key");
     let key = hash_master_password(master_password);
-    debug!("Key:{:?}", key);
-    Ok(encrypt(armored, &key, &[0u8; 16])?)
+    encrypt(armored, &key, &[0u8; 16])
 }
 
 pub fn import_private_key_bytes(encripted: Vec<u8>, master_password: String) -> Result<Vec<u8>> {
     let key = hash_master_password(master_password);
-    debug!("Key:{:?}", key);
-    Ok(decrypt(&encripted, &key, &[0u8; 16])?)
+    decrypt(&encripted, &key, &[0u8; 16])
 }
 
 fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
@@ -53,7 +44,7 @@ fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     loop {
         let result = encryptor
             .encrypt(&mut read_buffer, &mut write_buffer, true)
-            .map_err(|_| anyhow!("Error encrypting pass pgp secret"))?;
+            .map_err(|e| Error::Encryption(format!("{:?}", e)))?;
         final_result.extend(
             write_buffer
                 .take_read_buffer()
@@ -83,7 +74,7 @@ fn decrypt(encrypted_data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     loop {
         let result = decryptor
             .decrypt(&mut read_buffer, &mut write_buffer, true)
-            .map_err(|e| anyhow!("Error decrypting pass pgp secret: {:?}", e))?;
+            .map_err(|e| Error::Decryption(format!("{:?}", e)))?;
 
         final_result.extend(
             write_buffer
@@ -101,6 +92,7 @@ fn decrypt(encrypted_data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     Ok(final_result)
 }
 
+#[cfg(test)]
 mod tests {
     use simple_logger::SimpleLogger;
 
@@ -108,10 +100,9 @@ mod tests {
 
     #[test]
     fn export_pgp_key() {
-        SimpleLogger::new()
+        let _ = SimpleLogger::new()
             .with_level(log::LevelFilter::Debug)
-            .init()
-            .unwrap();
+            .init();
 
         let cyphertext = export_private_key_bytes("pgp".to_string(), "secret".to_string())
             .expect("error encrypting");
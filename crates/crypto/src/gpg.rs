@@ -0,0 +1,160 @@
+use std::io::Cursor;
+
+use pgp::{
+    composed::{SignedPublicKey, SignedSecretKey},
+    crypto::SymmetricKeyAlgorithm,
+    types::{CompressionAlgorithm, KeyTrait},
+    Deserializable, Message,
+};
+use rand::thread_rng;
+use shared::error::{Error, Result};
+
+use crate::signer::Signer;
+
+const ARMOR_HEADER: &[u8] = b"-----BEGIN PGP";
+
+/// Secret key exported from GnuPG, used to read files other tools encrypted
+/// with OpenPGP. Entries of passm itself are never encrypted with it.
+pub struct GpgKey {
+    key: SignedSecretKey,
+    passphrase: String,
+}
+
+impl GpgKey {
+    /// Parses an armored or binary secret key, as written by `gpg --export-secret-keys`,
+    /// and checks its passphrase.
+    pub fn parse(data: &[u8], passphrase: Option<String>) -> Result<Self> {
+        let key = match data.starts_with(ARMOR_HEADER) {
+            true => SignedSecretKey::from_armor_single(Cursor::new(data)).map(|(key, _)| key),
+            false => SignedSecretKey::from_bytes(data),
+        }
+        .map_err(|e| Error::Key(e.to_string()))?;
+        key.verify().map_err(|e| Error::Key(e.to_string()))?;
+        Signer::verify_key_passphrase(&key, passphrase.clone())?;
+        Ok(Self {
+            key,
+            passphrase: passphrase.unwrap_or_default(),
+        })
+    }
+
+    /// Decrypts an armored or binary OpenPGP message to its literal content.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let decryption = |e: pgp::errors::Error| Error::Decryption(e.to_string());
+        let message = match data.starts_with(ARMOR_HEADER) {
+            true => Message::from_armor_single(Cursor::new(data)).map(|(message, _)| message),
+            false => Message::from_bytes(data),
+        }
+        .map_err(decryption)?;
+        let passphrase = self.passphrase.clone();
+        let (mut decrypter, _) = message
+            .decrypt(String::new, || passphrase, &[&self.key])
+            .map_err(decryption)?;
+        decrypter
+            .next()
+            .ok_or_else(|| Error::Decryption("message holds no data".to_string()))?
+            .and_then(Message::decompress)
+            .and_then(|message| message.get_content())
+            .map_err(decryption)?
+            .ok_or_else(|| Error::Decryption("message holds no data".to_string()))
+    }
+}
+
+/// Public key of someone data is handed over to, as written by `gpg --export`.
+pub struct Recipient {
+    key: SignedPublicKey,
+}
+
+impl Recipient {
+    /// Parses an armored or binary public key.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let key = match data.starts_with(ARMOR_HEADER) {
+            true => SignedPublicKey::from_armor_single(Cursor::new(data)).map(|(key, _)| key),
+            false => SignedPublicKey::from_bytes(data),
+        }
+        .map_err(|e| Error::Key(e.to_string()))?;
+        key.verify().map_err(|e| Error::Key(e.to_string()))?;
+        Ok(Self { key })
+    }
+
+    /// Encrypts `data` into an armored OpenPGP message only the recipient can
+    /// read, with their encryption subkey if they have one.
+    pub fn encrypt(&self, file_name: &str, data: &[u8]) -> Result<String> {
+        let encryption = |e: pgp::errors::Error| Error::Encryption(e.to_string());
+        let message = Message::new_literal_bytes(file_name, data)
+            .compress(CompressionAlgorithm::ZLIB)
+            .map_err(encryption)?;
+        let (rng, algorithm) = (&mut thread_rng(), SymmetricKeyAlgorithm::AES256);
+        let subkey = self
+            .key
+            .public_subkeys
+            .iter()
+            .find(|subkey| subkey.key.is_encryption_key());
+        match subkey {
+            Some(subkey) => message.encrypt_to_keys(rng, algorithm, &[&subkey.key]),
+            None => message.encrypt_to_keys(rng, algorithm, &[&self.key]),
+        }
+        .and_then(|message| message.to_armored_string(None))
+        .map_err(encryption)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pgp::{
+        crypto::SymmetricKeyAlgorithm,
+        ser::Serialize,
+        types::{CompressionAlgorithm, SecretKeyTrait},
+        KeyType, Message,
+    };
+    use rand::thread_rng;
+
+    use super::{GpgKey, Recipient};
+    use crate::signer::Signer;
+
+    #[test]
+    fn decrypts_gpg_messages() {
+        let pass = "gpg pass".to_string();
+        let key = Signer::generate_key(KeyType::Rsa(2048), Some(pass.clone())).unwrap();
+        let key = Signer::sign_key(key, Some(pass.clone())).unwrap();
+        let armored = key.to_armored_bytes(None).unwrap();
+        assert!(GpgKey::parse(&armored, Some("wrong".to_string())).is_err());
+        let gpg_key = GpgKey::parse(&armored, Some(pass.clone())).unwrap();
+        assert!(GpgKey::parse(&key.to_bytes().unwrap(), Some(pass)).is_ok());
+
+        let message = Message::new_literal_bytes("mail.gpg", b"hunter2\nuser: me\n")
+            .compress(CompressionAlgorithm::ZLIB)
+            .unwrap()
+            .encrypt_to_keys(
+                &mut thread_rng(),
+                SymmetricKeyAlgorithm::AES256,
+                &[&key.primary_key.public_key()],
+            )
+            .unwrap();
+        let expected = b"hunter2\nuser: me\n".to_vec();
+        assert_eq!(
+            gpg_key.decrypt(&message.to_bytes().unwrap()).unwrap(),
+            expected
+        );
+        let armored_message = message.to_armored_bytes(None).unwrap();
+        assert_eq!(gpg_key.decrypt(&armored_message).unwrap(), expected);
+        assert!(gpg_key.decrypt(b"not a message").is_err());
+    }
+
+    #[test]
+    fn encrypts_to_recipients() {
+        let pass = "gpg pass".to_string();
+        let key = Signer::generate_key(KeyType::Rsa(2048), Some(pass.clone())).unwrap();
+        let key = Signer::sign_key(key, Some(pass.clone())).unwrap();
+        let public = key.public_key().sign(&key, || pass.clone()).unwrap();
+        let recipient = Recipient::parse(&public.to_armored_bytes(None).unwrap()).unwrap();
+        assert!(Recipient::parse(b"not a key").is_err());
+
+        let message = recipient.encrypt("export.csv", b"name,password").unwrap();
+        assert!(message.starts_with("-----BEGIN PGP MESSAGE-----"));
+        let gpg_key = GpgKey::parse(&key.to_armored_bytes(None).unwrap(), Some(pass)).unwrap();
+        assert_eq!(
+            gpg_key.decrypt(message.as_bytes()).unwrap(),
+            b"name,password"
+        );
+    }
+}
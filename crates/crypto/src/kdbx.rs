@@ -0,0 +1,771 @@
+use std::{collections::HashMap, io::Read};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use flate2::read::GzDecoder;
+use rust_crypto::{
+    aes::{cbc_decryptor, KeySize},
+    aessafe::AesSafe256Encryptor,
+    blockmodes::PkcsPadding,
+    buffer::{BufferResult, ReadBuffer, RefReadBuffer, RefWriteBuffer, WriteBuffer},
+    chacha20::ChaCha20,
+    digest::Digest,
+    hmac::Hmac,
+    mac::Mac,
+    salsa20::Salsa20,
+    sha2::{Sha256, Sha512},
+    symmetriccipher::{BlockEncryptor, SynchronousStreamCipher},
+};
+use shared::error::{Error, Result};
+
+use crate::hash::sha256;
+
+const SIGNATURE: [u32; 2] = [0x9AA2_D903, 0xB54B_FB67];
+
+const AES_CIPHER: [u8; 16] = uuid(0x31c1f2e6_bf71_4350_be58_05216afc5aff);
+const CHACHA20_CIPHER: [u8; 16] = uuid(0xd6038a2b_8b6f_4cb5_a524_339a31dbb59a);
+const AES_KDF: [u8; 16] = uuid(0xc9d9f39a_628a_4460_bf74_0d08c18a4fea);
+const AES_KDF_4: [u8; 16] = uuid(0x7c02bb82_79a7_4ac0_927d_114a00648238);
+const ARGON2D_KDF: [u8; 16] = uuid(0xef636ddf_8c29_444b_91f7_a9a403e30a0c);
+const ARGON2ID_KDF: [u8; 16] = uuid(0x9e298b19_56db_4773_b23d_fc3ec6f0a1e6);
+
+/// Nonce KeePass uses for Salsa20 protected values.
+const SALSA20_NONCE: [u8; 8] = [0xE8, 0x30, 0x09, 0x4B, 0x97, 0x20, 0x5D, 0x2A];
+const NO_PROTECTION_STREAM: u32 = 0;
+const SALSA20_STREAM: u32 = 2;
+const CHACHA20_STREAM: u32 = 3;
+
+const fn uuid(value: u128) -> [u8; 16] {
+    value.to_be_bytes()
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidInput(format!("not a readable KeePass database: {}", message))
+}
+
+/// Reads the little endian fields of a KDBX file.
+struct Bytes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(invalid("file is truncated"));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap_or([0; N]))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> Result<u32> {
+    Ok(u32::from_le_bytes(
+        bytes.try_into().map_err(|_| invalid("bad number"))?,
+    ))
+}
+
+fn le_u64(bytes: &[u8]) -> Result<u64> {
+    Ok(u64::from_le_bytes(
+        bytes.try_into().map_err(|_| invalid("bad number"))?,
+    ))
+}
+
+fn sha512(parts: &[&[u8]]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.input(part);
+    }
+    let mut out = [0u8; 64];
+    hasher.result(&mut out);
+    out
+}
+
+fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    for part in parts {
+        hmac.input(part);
+    }
+    hmac.result().code().to_vec()
+}
+
+/// Runs a rust-crypto block mode over the whole input.
+fn run_cipher(
+    mut step: impl FnMut(&mut RefReadBuffer, &mut RefWriteBuffer) -> Result<BufferResult>,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let mut output = vec![];
+    let mut read = RefReadBuffer::new(input);
+    let mut buffer = [0u8; 4096];
+    loop {
+        let mut write = RefWriteBuffer::new(&mut buffer);
+        let result = step(&mut read, &mut write)?;
+        output.extend_from_slice(write.take_read_buffer().take_remaining());
+        if let BufferResult::BufferUnderflow = result {
+            return Ok(output);
+        }
+    }
+}
+
+/// AES-256 block cipher, with AES-NI when the CPU has it. The AES key
+/// derivation runs it millions of times.
+fn aes_encryptor(key: &[u8]) -> Box<dyn BlockEncryptor> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if rust_crypto::util::supports_aesni() {
+        return Box::new(rust_crypto::aesni::AesNiEncryptor::new(
+            KeySize::KeySize256,
+            key,
+        ));
+    }
+    Box::new(AesSafe256Encryptor::new(key))
+}
+
+/// Key derivation settings of a database.
+enum Kdf {
+    Aes {
+        seed: Vec<u8>,
+        rounds: u64,
+    },
+    Argon2 {
+        algorithm: Algorithm,
+        salt: Vec<u8>,
+        memory: u64,
+        iterations: u64,
+        parallelism: u32,
+        version: u32,
+    },
+}
+
+impl Kdf {
+    /// Reads the KDBX 4 variant dictionary of KDF parameters.
+    fn from_parameters(data: &[u8]) -> Result<Self> {
+        let mut bytes = Bytes { data };
+        bytes.u16()?;
+        let mut values: HashMap<String, &[u8]> = HashMap::new();
+        loop {
+            let kind = bytes.u8()?;
+            if kind == 0 {
+                break;
+            }
+            let key_len = bytes.u32()? as usize;
+            let key = String::from_utf8_lossy(bytes.take(key_len)?).to_string();
+            let value_len = bytes.u32()? as usize;
+            values.insert(key, bytes.take(value_len)?);
+        }
+        let value = |key: &str| {
+            values
+                .get(key)
+                .copied()
+                .ok_or_else(|| invalid(&format!("KDF parameter {} is missing", key)))
+        };
+        let algorithm = match <[u8; 16]>::try_from(value("$UUID")?) {
+            Ok(AES_KDF) | Ok(AES_KDF_4) => {
+                return Ok(Kdf::Aes {
+                    seed: value("S")?.to_vec(),
+                    rounds: le_u64(value("R")?)?,
+                })
+            }
+            Ok(ARGON2D_KDF) => Algorithm::Argon2d,
+            Ok(ARGON2ID_KDF) => Algorithm::Argon2id,
+            _ => return Err(invalid("unsupported key derivation function")),
+        };
+        Ok(Kdf::Argon2 {
+            algorithm,
+            salt: value("S")?.to_vec(),
+            memory: le_u64(value("M")?)?,
+            iterations: le_u64(value("I")?)?,
+            parallelism: le_u32(value("P")?)?,
+            version: le_u32(value("V")?)?,
+        })
+    }
+
+    fn transform(&self, composite_key: &[u8; 32]) -> Result<[u8; 32]> {
+        match self {
+            Kdf::Aes { seed, rounds } => {
+                if seed.len() != 32 {
+                    return Err(invalid("bad key transformation seed"));
+                }
+                let aes = aes_encryptor(seed);
+                let mut key = *composite_key;
+                let mut block = [0u8; 16];
+                for _ in 0..*rounds {
+                    for half in key.chunks_exact_mut(16) {
+                        aes.encrypt_block(half, &mut block);
+                        half.copy_from_slice(&block);
+                    }
+                }
+                Ok(sha256(&key))
+            }
+            Kdf::Argon2 {
+                algorithm,
+                salt,
+                memory,
+                iterations,
+                parallelism,
+                version,
+            } => {
+                let version = match version {
+                    0x10 => Version::V0x10,
+                    _ => Version::V0x13,
+                };
+                let params = Params::new(
+                    (*memory / 1024) as u32,
+                    *iterations as u32,
+                    *parallelism,
+                    Some(32),
+                )
+                .map_err(|e| invalid(&e.to_string()))?;
+                let mut key = [0u8; 32];
+                Argon2::new(*algorithm, version, params)
+                    .hash_password_into(composite_key, salt, &mut key)
+                    .map_err(|e| invalid(&e.to_string()))?;
+                Ok(key)
+            }
+        }
+    }
+}
+
+/// Outer header fields of both KDBX versions.
+#[derive(Default)]
+struct Header {
+    cipher: Vec<u8>,
+    compressed: bool,
+    master_seed: Vec<u8>,
+    iv: Vec<u8>,
+    kdf_parameters: Vec<u8>,
+    transform_seed: Vec<u8>,
+    transform_rounds: u64,
+    stream_start: Vec<u8>,
+    stream_id: u32,
+    stream_key: Vec<u8>,
+}
+
+impl Header {
+    fn read(bytes: &mut Bytes, major: u16) -> Result<Self> {
+        let mut header = Header::default();
+        loop {
+            let id = bytes.u8()?;
+            let len = match major {
+                3 => bytes.u16()? as usize,
+                _ => bytes.u32()? as usize,
+            };
+            let data = bytes.take(len)?;
+            match id {
+                0 => return Ok(header),
+                2 => header.cipher = data.to_vec(),
+                3 => header.compressed = le_u32(data)? == 1,
+                4 => header.master_seed = data.to_vec(),
+                5 => header.transform_seed = data.to_vec(),
+                6 => header.transform_rounds = le_u64(data)?,
+                7 => header.iv = data.to_vec(),
+                8 => header.stream_key = data.to_vec(),
+                9 => header.stream_start = data.to_vec(),
+                10 => header.stream_id = le_u32(data)?,
+                11 => header.kdf_parameters = data.to_vec(),
+                _ => {}
+            }
+        }
+    }
+
+    fn kdf(&self, major: u16) -> Result<Kdf> {
+        match major {
+            3 => Ok(Kdf::Aes {
+                seed: self.transform_seed.clone(),
+                rounds: self.transform_rounds,
+            }),
+            _ => Kdf::from_parameters(&self.kdf_parameters),
+        }
+    }
+
+    fn decrypt(&self, key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>> {
+        match <[u8; 16]>::try_from(self.cipher.as_slice()) {
+            Ok(AES_CIPHER) => {
+                let mut aes = cbc_decryptor(KeySize::KeySize256, key, &self.iv, PkcsPadding);
+                run_cipher(
+                    |read, write| {
+                        aes.decrypt(read, write, true)
+                            .map_err(|_| Error::WrongPassphrase)
+                    },
+                    data,
+                )
+            }
+            Ok(CHACHA20_CIPHER) if self.iv.len() == 12 => {
+                let mut output = vec![0u8; data.len()];
+                ChaCha20::new(key, &self.iv).process(data, &mut output);
+                Ok(output)
+            }
+            _ => Err(invalid(
+                "only AES and ChaCha20 encrypted databases are supported",
+            )),
+        }
+    }
+}
+
+/// Key of the KDBX 4 HMAC of a block, `u64::MAX` for the header.
+fn block_key(hmac_key: &[u8; 64], index: u64) -> [u8; 64] {
+    sha512(&[&index.to_le_bytes(), hmac_key])
+}
+
+/// KDBX 3.1 payload: blocks of index, SHA-256, length and data.
+fn read_hashed_blocks(bytes: &mut Bytes) -> Result<Vec<u8>> {
+    let mut content = vec![];
+    loop {
+        bytes.u32()?;
+        let hash = bytes.take(32)?;
+        let len = bytes.u32()? as usize;
+        if len == 0 {
+            return Ok(content);
+        }
+        let data = bytes.take(len)?;
+        if sha256(data) != hash {
+            return Err(invalid("block checksum mismatch"));
+        }
+        content.extend_from_slice(data);
+    }
+}
+
+/// KDBX 4 payload: blocks of HMAC, length and data.
+fn read_hmac_blocks(bytes: &mut Bytes, hmac_key: &[u8; 64]) -> Result<Vec<u8>> {
+    let mut content = vec![];
+    for index in 0u64.. {
+        let hmac = bytes.take(32)?;
+        let len = bytes.u32()?;
+        let data = bytes.take(len as usize)?;
+        let expected = hmac_sha256(
+            &block_key(hmac_key, index),
+            &[&index.to_le_bytes(), &len.to_le_bytes(), data],
+        );
+        if expected != hmac {
+            return Err(invalid("block authentication failed"));
+        }
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(data);
+    }
+    Ok(content)
+}
+
+/// Hash of a key file: XML key files hold the key, 32 byte and 64 hex
+/// character files are the key, anything else is hashed.
+fn key_file_hash(key_file: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(key_file);
+    if let Some(data) = text
+        .split_once("<Data")
+        .and_then(|(_, rest)| rest.split_once('>'))
+        .and_then(|(attributes, rest)| Some((attributes, rest.split_once("</Data>")?.0)))
+    {
+        let (attributes, data) = data;
+        let data: String = data.split_whitespace().collect();
+        // Version 2 key files store hex with a Hash attribute, version 1 base64
+        let decoded = match attributes.contains("Hash=") {
+            true => decode_hex(&data),
+            false => base64::decode(&data).ok(),
+        };
+        if let Some(key) = decoded {
+            return key;
+        }
+    }
+    match key_file.len() {
+        32 => key_file.to_vec(),
+        64 => decode_hex(&text).unwrap_or_else(|| sha256(key_file).to_vec()),
+        _ => sha256(key_file).to_vec(),
+    }
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Stream of databases whose values are not protected.
+struct NoProtection;
+
+impl SynchronousStreamCipher for NoProtection {
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        output.copy_from_slice(input);
+    }
+}
+
+/// Key stream protecting secret values inside the XML, consumed in document order.
+pub struct InnerStream(Box<dyn SynchronousStreamCipher>);
+
+impl InnerStream {
+    /// Stream with the id and key from the database header, id 0 means no protection.
+    pub fn new(id: u32, key: &[u8]) -> Result<Self> {
+        match id {
+            NO_PROTECTION_STREAM => Ok(Self(Box::new(NoProtection))),
+            SALSA20_STREAM => Ok(Self(Box::new(Salsa20::new(&sha256(key), &SALSA20_NONCE)))),
+            CHACHA20_STREAM => {
+                let hash = sha512(&[key]);
+                Ok(Self(Box::new(ChaCha20::new(&hash[..32], &hash[32..44]))))
+            }
+            _ => Err(invalid("unsupported protection of values")),
+        }
+    }
+
+    /// Decrypts the base64 text of a value marked `Protected="True"`.
+    pub fn unprotect(&mut self, value: &str) -> Result<Vec<u8>> {
+        let data = base64::decode(value.trim()).map_err(|_| invalid("bad protected value"))?;
+        let mut plain = vec![0u8; data.len()];
+        self.0.process(&data, &mut plain);
+        Ok(plain)
+    }
+}
+
+/// Decrypted KeePass database: its XML document and the stream needed to
+/// read protected values in it.
+pub struct Kdbx {
+    pub version: u16,
+    pub xml: String,
+    pub inner_stream: InnerStream,
+    /// Attachments stored outside the XML, KDBX 4 only
+    pub binaries: usize,
+}
+
+impl Kdbx {
+    /// Decrypts a KDBX 3.1 or 4 database with its master password and key file.
+    pub fn open(data: &[u8], password: Option<&str>, key_file: Option<&[u8]>) -> Result<Self> {
+        let mut bytes = Bytes { data };
+        if [bytes.u32()?, bytes.u32()?] != SIGNATURE {
+            return Err(invalid("unknown file signature"));
+        }
+        bytes.u16()?;
+        let major = bytes.u16()?;
+        if major != 3 && major != 4 {
+            return Err(invalid(&format!("unsupported version {}", major)));
+        }
+        let header_start = data.len() - bytes.data.len() - 12;
+        let header = Header::read(&mut bytes, major)?;
+        let header_data = &data[header_start..data.len() - bytes.data.len()];
+
+        let mut composite = vec![];
+        if let Some(password) = password {
+            composite.extend_from_slice(&sha256(password.as_bytes()));
+        }
+        if let Some(key_file) = key_file {
+            composite.extend_from_slice(&key_file_hash(key_file));
+        }
+        let transformed = header.kdf(major)?.transform(&sha256(&composite))?;
+        let key = sha256(&[header.master_seed.as_slice(), &transformed].concat());
+
+        let (content, inner_stream, binaries) = match major {
+            3 => {
+                let decrypted = header.decrypt(&key, bytes.data)?;
+                let mut payload = Bytes { data: &decrypted };
+                if payload.take(header.stream_start.len())? != header.stream_start {
+                    return Err(Error::WrongPassphrase);
+                }
+                let content = read_hashed_blocks(&mut payload)?;
+                let stream = InnerStream::new(header.stream_id, &header.stream_key)?;
+                (decompress(content, header.compressed)?, stream, 0)
+            }
+            _ => {
+                let hmac_key = sha512(&[header.master_seed.as_slice(), &transformed, &[1]]);
+                if bytes.take(32)? != sha256(header_data) {
+                    return Err(invalid("header checksum mismatch"));
+                }
+                let header_hmac = hmac_sha256(&block_key(&hmac_key, u64::MAX), &[header_data]);
+                if bytes.take(32)? != header_hmac {
+                    return Err(Error::WrongPassphrase);
+                }
+                let encrypted = read_hmac_blocks(&mut bytes, &hmac_key)?;
+                let content = decompress(header.decrypt(&key, &encrypted)?, header.compressed)?;
+                read_inner_header(content)?
+            }
+        };
+        let xml = String::from_utf8(content)?;
+        Ok(Self {
+            version: major,
+            xml,
+            inner_stream,
+            binaries,
+        })
+    }
+}
+
+fn decompress(content: Vec<u8>, compressed: bool) -> Result<Vec<u8>> {
+    if !compressed {
+        return Ok(content);
+    }
+    let mut decompressed = vec![];
+    GzDecoder::new(content.as_slice())
+        .read_to_end(&mut decompressed)
+        .map_err(|_| invalid("bad compressed data"))?;
+    Ok(decompressed)
+}
+
+/// Splits the KDBX 4 inner header, holding the value protection and
+/// attachments, from the XML.
+fn read_inner_header(content: Vec<u8>) -> Result<(Vec<u8>, InnerStream, usize)> {
+    let mut bytes = Bytes { data: &content };
+    let (mut stream_id, mut stream_key, mut binaries) = (0, vec![], 0);
+    loop {
+        let id = bytes.u8()?;
+        let len = bytes.u32()? as usize;
+        let data = bytes.take(len)?;
+        match id {
+            0 => break,
+            1 => stream_id = le_u32(data)?,
+            2 => stream_key = data.to_vec(),
+            3 => binaries += 1,
+            _ => {}
+        }
+    }
+    let xml = bytes.data.to_vec();
+    Ok((xml, InnerStream::new(stream_id, &stream_key)?, binaries))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use rust_crypto::{
+        aes::{cbc_encryptor, KeySize},
+        blockmodes::PkcsPadding,
+        chacha20::ChaCha20,
+        salsa20::Salsa20,
+        symmetriccipher::SynchronousStreamCipher,
+    };
+
+    use super::{
+        block_key, hmac_sha256, run_cipher, sha512, Kdbx, Kdf, AES_CIPHER, AES_KDF_4, ARGON2D_KDF,
+        CHACHA20_CIPHER, SALSA20_NONCE, SIGNATURE,
+    };
+    use crate::hash::sha256;
+    use shared::error::Error;
+
+    const PASSWORD: &str = "master";
+    const SECRET: &str = "hunter2";
+
+    fn field(out: &mut Vec<u8>, major: u16, id: u8, data: &[u8]) {
+        out.push(id);
+        match major {
+            3 => out.extend_from_slice(&(data.len() as u16).to_le_bytes()),
+            _ => out.extend_from_slice(&(data.len() as u32).to_le_bytes()),
+        }
+        out.extend_from_slice(data);
+    }
+
+    fn variant(out: &mut Vec<u8>, kind: u8, key: &str, value: &[u8]) {
+        out.push(kind);
+        out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        out.extend_from_slice(key.as_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        out.extend_from_slice(value);
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn aes_cbc(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut aes = cbc_encryptor(KeySize::KeySize256, key, iv, PkcsPadding);
+        run_cipher(
+            |read, write| Ok(aes.encrypt(read, write, true).unwrap()),
+            data,
+        )
+        .unwrap()
+    }
+
+    fn xml(protected: &str) -> String {
+        format!(
+            "<KeePassFile><Root><Group><Entry><String><Key>Password</Key>\
+             <Value Protected=\"True\">{}</Value></String></Entry></Group></Root></KeePassFile>",
+            protected
+        )
+    }
+
+    /// Writes a KDBX 3.1 database with AES-KDF, AES and Salsa20 protected values.
+    fn kdbx3(key_file: Option<&[u8]>) -> Vec<u8> {
+        let (master_seed, seed, iv, stream_key, start) =
+            ([1u8; 32], [2u8; 32], [3u8; 16], [4u8; 32], [5u8; 32]);
+        let mut protected = vec![0u8; SECRET.len()];
+        Salsa20::new(&sha256(&stream_key), &SALSA20_NONCE)
+            .process(SECRET.as_bytes(), &mut protected);
+        let content = gzip(xml(&base64::encode(&protected)).as_bytes());
+
+        let mut out = vec![];
+        for part in SIGNATURE {
+            out.extend_from_slice(&part.to_le_bytes());
+        }
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&3u16.to_le_bytes());
+        field(&mut out, 3, 2, &AES_CIPHER);
+        field(&mut out, 3, 3, &1u32.to_le_bytes());
+        field(&mut out, 3, 4, &master_seed);
+        field(&mut out, 3, 5, &seed);
+        field(&mut out, 3, 6, &10u64.to_le_bytes());
+        field(&mut out, 3, 7, &iv);
+        field(&mut out, 3, 8, &stream_key);
+        field(&mut out, 3, 9, &start);
+        field(&mut out, 3, 10, &2u32.to_le_bytes());
+        field(&mut out, 3, 0, b"\r\n\r\n");
+
+        let mut payload = start.to_vec();
+        payload.extend_from_slice(&0u32.to_le_bytes());
+        payload.extend_from_slice(&sha256(&content));
+        payload.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        payload.extend_from_slice(&content);
+        payload.extend_from_slice(&1u32.to_le_bytes());
+        payload.extend_from_slice(&[0u8; 32]);
+        payload.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut composite = sha256(PASSWORD.as_bytes()).to_vec();
+        if let Some(key_file) = key_file {
+            composite.extend_from_slice(&super::key_file_hash(key_file));
+        }
+        let kdf = Kdf::Aes {
+            seed: seed.to_vec(),
+            rounds: 10,
+        };
+        let transformed = kdf.transform(&sha256(&composite)).unwrap();
+        let key = sha256(&[master_seed.as_slice(), &transformed].concat());
+        out.extend_from_slice(&aes_cbc(&key, &iv, &payload));
+        out
+    }
+
+    /// Writes a KDBX 4 database with ChaCha20 encryption and protected values.
+    fn kdbx4(kdf_id: [u8; 16]) -> Vec<u8> {
+        let (master_seed, salt, iv, stream_key) = ([1u8; 32], [2u8; 32], [3u8; 12], [4u8; 64]);
+        let mut parameters = 0x0100u16.to_le_bytes().to_vec();
+        variant(&mut parameters, 0x42, "$UUID", &kdf_id);
+        variant(&mut parameters, 0x42, "S", &salt);
+        variant(&mut parameters, 0x05, "R", &10u64.to_le_bytes());
+        variant(&mut parameters, 0x05, "M", &(64 * 1024u64).to_le_bytes());
+        variant(&mut parameters, 0x05, "I", &2u64.to_le_bytes());
+        variant(&mut parameters, 0x04, "P", &1u32.to_le_bytes());
+        variant(&mut parameters, 0x04, "V", &0x13u32.to_le_bytes());
+        parameters.push(0);
+
+        let mut out = vec![];
+        for part in SIGNATURE {
+            out.extend_from_slice(&part.to_le_bytes());
+        }
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&4u16.to_le_bytes());
+        field(&mut out, 4, 2, &CHACHA20_CIPHER);
+        field(&mut out, 4, 3, &1u32.to_le_bytes());
+        field(&mut out, 4, 4, &master_seed);
+        field(&mut out, 4, 7, &iv);
+        field(&mut out, 4, 11, &parameters);
+        field(&mut out, 4, 0, b"\r\n\r\n");
+        let header = out.clone();
+
+        let hash = sha512(&[&stream_key]);
+        let mut protected = vec![0u8; SECRET.len()];
+        ChaCha20::new(&hash[..32], &hash[32..44]).process(SECRET.as_bytes(), &mut protected);
+        let mut inner = vec![];
+        field(&mut inner, 4, 1, &3u32.to_le_bytes());
+        field(&mut inner, 4, 2, &stream_key);
+        field(&mut inner, 4, 3, b"\x01attachment");
+        field(&mut inner, 4, 0, &[]);
+        inner.extend_from_slice(xml(&base64::encode(&protected)).as_bytes());
+
+        let kdf = Kdf::from_parameters(&parameters).unwrap();
+        let transformed = kdf
+            .transform(&sha256(&sha256(PASSWORD.as_bytes())))
+            .unwrap();
+        let key = sha256(&[master_seed.as_slice(), &transformed].concat());
+        let compressed = gzip(&inner);
+        let mut encrypted = vec![0u8; compressed.len()];
+        ChaCha20::new(&key, &iv).process(&compressed, &mut encrypted);
+
+        let hmac_key = sha512(&[master_seed.as_slice(), &transformed, &[1]]);
+        out.extend_from_slice(&sha256(&header));
+        out.extend_from_slice(&hmac_sha256(&block_key(&hmac_key, u64::MAX), &[&header]));
+        for (index, block) in [encrypted.as_slice(), &[]].into_iter().enumerate() {
+            let index = index as u64;
+            let len = block.len() as u32;
+            out.extend_from_slice(&hmac_sha256(
+                &block_key(&hmac_key, index),
+                &[&index.to_le_bytes(), &len.to_le_bytes(), block],
+            ));
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(block);
+        }
+        out
+    }
+
+    fn protected_value(kdbx: &mut Kdbx) -> Vec<u8> {
+        let value = kdbx
+            .xml
+            .split("Protected=\"True\">")
+            .nth(1)
+            .and_then(|rest| rest.split('<').next())
+            .unwrap()
+            .to_string();
+        kdbx.inner_stream.unprotect(&value).unwrap()
+    }
+
+    #[test]
+    fn opens_kdbx3() {
+        let mut kdbx = Kdbx::open(&kdbx3(None), Some(PASSWORD), None).unwrap();
+        assert_eq!(kdbx.version, 3);
+        assert_eq!(protected_value(&mut kdbx), SECRET.as_bytes());
+        assert!(matches!(
+            Kdbx::open(&kdbx3(None), Some("wrong"), None),
+            Err(Error::WrongPassphrase)
+        ));
+
+        let key_file = b"<KeyFile><Key><Data>AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=</Data></Key></KeyFile>";
+        let data = kdbx3(Some(key_file));
+        assert!(Kdbx::open(&data, Some(PASSWORD), None).is_err());
+        let mut kdbx = Kdbx::open(&data, Some(PASSWORD), Some(key_file)).unwrap();
+        assert_eq!(protected_value(&mut kdbx), SECRET.as_bytes());
+        assert!(Kdbx::open(b"not a database", Some(PASSWORD), None).is_err());
+    }
+
+    #[test]
+    fn opens_kdbx4() {
+        for kdf in [AES_KDF_4, ARGON2D_KDF] {
+            let mut kdbx = Kdbx::open(&kdbx4(kdf), Some(PASSWORD), None).unwrap();
+            assert_eq!((kdbx.version, kdbx.binaries), (4, 1));
+            assert_eq!(protected_value(&mut kdbx), SECRET.as_bytes());
+            assert!(matches!(
+                Kdbx::open(&kdbx4(kdf), Some("wrong"), None),
+                Err(Error::WrongPassphrase)
+            ));
+        }
+    }
+
+    #[test]
+    fn hashes_key_files() {
+        let raw = [7u8; 32];
+        assert_eq!(super::key_file_hash(&raw), raw.to_vec());
+        let hex = "07".repeat(32);
+        assert_eq!(super::key_file_hash(hex.as_bytes()), raw.to_vec());
+        let v2 = format!(
+            "<KeyFile><Meta><Version>2.0</Version></Meta><Key><Data Hash=\"00000000\">\n{}\n</Data></Key></KeyFile>",
+            "0707 0707 ".repeat(8)
+        );
+        assert_eq!(super::key_file_hash(v2.as_bytes()), raw.to_vec());
+        assert_eq!(
+            super::key_file_hash(b"anything"),
+            sha256(b"anything").to_vec()
+        );
+    }
+}
@@ -1,4 +1,9 @@
-pub mod signer;
 pub mod exporter;
+pub mod gpg;
+pub mod hash;
+pub mod kdbx;
+pub mod otp;
+pub mod sealed;
+pub mod shamir;
+pub mod signer;
 pub use pgp;
-
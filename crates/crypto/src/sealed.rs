@@ -0,0 +1,143 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::{thread_rng, RngCore};
+use rust_crypto::{
+    aead::{AeadDecryptor, AeadEncryptor},
+    chacha20poly1305::ChaCha20Poly1305,
+};
+use shared::error::{Error, Result};
+
+/// File signature and format version of sealed data.
+const MAGIC: &[u8; 8] = b"PASSMSL1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 8;
+const TAG_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 3 * 4 + SALT_LEN + NONCE_LEN;
+
+/// Argon2id cost of new sealed data: 64 MiB, 3 passes.
+const MEMORY_KIB: u32 = 64 * 1024;
+const ITERATIONS: u32 = 3;
+const LANES: u32 = 1;
+/// Most a sealed file may ask for: 1 GiB, 16 passes and 16 lanes. Anything
+/// more is damaged or crafted to exhaust memory or time before failing.
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 16;
+const MAX_LANES: u32 = 16;
+
+fn derive_key(password: &str, salt: &[u8], params: (u32, u32, u32)) -> Result<[u8; 32]> {
+    let (memory, iterations, lanes) = params;
+    let invalid = |e: argon2::Error| Error::Key(format!("bad key derivation parameters: {}", e));
+    let params = Params::new(memory, iterations, lanes, Some(32)).map_err(invalid)?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(invalid)?;
+    Ok(key)
+}
+
+fn seal_with(data: &[u8], password: &str, params: (u32, u32, u32)) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    thread_rng().fill_bytes(&mut salt);
+    thread_rng().fill_bytes(&mut nonce);
+    let key = derive_key(password, &salt, params)?;
+
+    let mut sealed = Vec::with_capacity(HEADER_LEN + data.len() + TAG_LEN);
+    sealed.extend_from_slice(MAGIC);
+    for value in [params.0, params.1, params.2] {
+        sealed.extend_from_slice(&value.to_le_bytes());
+    }
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+
+    // The header is authenticated too, so the parameters can not be swapped
+    let mut cipher = ChaCha20Poly1305::new(&key, &nonce, &sealed);
+    let mut encrypted = vec![0u8; data.len()];
+    let mut tag = [0u8; TAG_LEN];
+    cipher.encrypt(data, &mut encrypted, &mut tag);
+    sealed.extend_from_slice(&encrypted);
+    sealed.extend_from_slice(&tag);
+    Ok(sealed)
+}
+
+/// Encrypts `data` with a key derived from `password` by Argon2id, with
+/// ChaCha20-Poly1305 so any change to the sealed bytes is detected.
+pub fn seal(data: &[u8], password: &str) -> Result<Vec<u8>> {
+    seal_with(data, password, (MEMORY_KIB, ITERATIONS, LANES))
+}
+
+/// Decrypts data written by [`seal`]. A wrong password and modified data
+/// can not be told apart, both fail authentication.
+pub fn open(sealed: &[u8], password: &str) -> Result<Vec<u8>> {
+    if sealed.len() < HEADER_LEN + TAG_LEN || !sealed.starts_with(MAGIC) {
+        return Err(Error::Decryption("not a passm sealed file".to_string()));
+    }
+    let (header, body) = sealed.split_at(HEADER_LEN);
+    let number = |position: usize| {
+        let start = MAGIC.len() + position * 4;
+        u32::from_le_bytes(header[start..start + 4].try_into().unwrap_or_default())
+    };
+    let params = (number(0), number(1), number(2));
+    if params.0 > MAX_MEMORY_KIB || params.1 > MAX_ITERATIONS || params.2 > MAX_LANES {
+        return Err(Error::Decryption(format!(
+            "key derivation parameters of {} KiB, {} passes and {} lanes are too costly",
+            params.0, params.1, params.2
+        )));
+    }
+    let salt = &header[HEADER_LEN - NONCE_LEN - SALT_LEN..HEADER_LEN - NONCE_LEN];
+    let nonce = &header[HEADER_LEN - NONCE_LEN..];
+    let key = derive_key(password, salt, params)?;
+
+    let (encrypted, tag) = body.split_at(body.len() - TAG_LEN);
+    let mut cipher = ChaCha20Poly1305::new(&key, nonce, header);
+    let mut data = vec![0u8; encrypted.len()];
+    if !cipher.decrypt(encrypted, &mut data, tag) {
+        return Err(Error::Decryption(
+            "wrong password, or the file was damaged or modified".to_string(),
+        ));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::error::Error;
+
+    use super::{open, seal_with};
+
+    /// Cheap parameters, the real ones take a while in debug builds
+    const PARAMS: (u32, u32, u32) = (64, 1, 1);
+
+    #[test]
+    fn opens_sealed_data() {
+        let sealed = seal_with(b"vault", "secret", PARAMS).unwrap();
+        assert_eq!(open(&sealed, "secret").unwrap(), b"vault");
+        assert!(open(&sealed, "wrong").is_err());
+        // Salt and nonce are random
+        assert_ne!(seal_with(b"vault", "secret", PARAMS).unwrap(), sealed);
+    }
+
+    #[test]
+    fn rejects_modified_data() {
+        let sealed = seal_with(b"vault", "secret", PARAMS).unwrap();
+        for position in [9, sealed.len() - 20, sealed.len() - 1] {
+            let mut modified = sealed.clone();
+            modified[position] ^= 1;
+            assert!(open(&modified, "secret").is_err());
+        }
+        assert!(open(&sealed[..10], "secret").is_err());
+    }
+
+    #[test]
+    fn rejects_costly_parameters() {
+        let sealed = seal_with(b"vault", "secret", PARAMS).unwrap();
+        // Memory, passes and lanes in turn
+        for start in [8, 12, 16] {
+            let mut costly = sealed.clone();
+            costly[start..start + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(matches!(
+                open(&costly, "secret"),
+                Err(Error::Decryption(message)) if message.contains("too costly")
+            ));
+        }
+    }
+}
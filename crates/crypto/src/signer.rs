@@ -1,43 +1,61 @@
-use anyhow::{Ok, Result};
 use pgp::composed::{KeyType, SecretKey, SecretKeyParamsBuilder, SignedSecretKey};
 use pgp::crypto::{HashAlgorithm, SymmetricKeyAlgorithm};
 
 use pgp::types::{CompressionAlgorithm, SecretKeyRepr, SecretKeyTrait};
 use pgp::Deserializable;
-use rand::thread_rng;
+use rand::{thread_rng, RngCore};
 use rsa::{PaddingScheme, PublicKey};
+use rust_crypto::{
+    aead::{AeadDecryptor, AeadEncryptor},
+    aes::KeySize,
+    aes_gcm::AesGcm,
+};
+use shared::{
+    error::{Error, Result},
+    secret::{SecretBytes, SecretString},
+};
 use smallvec::smallvec;
+use zeroize::Zeroize;
+
+/// Header of payloads encrypted with a per entry AES key.
+const PAYLOAD_MAGIC: &[u8] = b"PASSM\x01";
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
 
 pub struct Signer {
     signing_key: SignedSecretKey,
-    passphrase: Option<String>,
+    passphrase: Option<SecretString>,
 }
 
 impl Signer {
-    pub fn new(signing_key: SignedSecretKey, passphrase: Option<String>) -> Self {
+    pub fn new(signing_key: SignedSecretKey, passphrase: Option<SecretString>) -> Self {
         Self {
             passphrase,
             signing_key,
         }
     }
 
-    pub fn verify_key_passphrase(
-        ssk: &SignedSecretKey,
-        passphrase: Option<String>,
-    ) -> Result<(), pgp::errors::Error> {
+    pub fn verify_key_passphrase(ssk: &SignedSecretKey, passphrase: Option<String>) -> Result<()> {
         ssk.unlock(
             || passphrase.unwrap_or_else(|| "".to_owned()),
             |_| std::result::Result::Ok(()),
         )
+        .map_err(|_| Error::WrongPassphrase)
+    }
+
+    /// Checks a passphrase typed again, before an action that exposes secrets.
+    pub fn check_passphrase(&self, passphrase: &str) -> Result<()> {
+        Signer::verify_key_passphrase(&self.signing_key, Some(passphrase.to_string()))
     }
 
     pub fn parse_signed_secret_from_string(key: String) -> Result<SignedSecretKey> {
-        let (secret, _header) = SignedSecretKey::from_string(&key)?;
-        secret.verify()?;
+        let (secret, _header) =
+            SignedSecretKey::from_string(&key).map_err(|e| Error::Key(e.to_string()))?;
+        secret.verify().map_err(|e| Error::Key(e.to_string()))?;
         Ok(secret)
     }
 
-    pub fn generate_key(kt: KeyType, passphrase: Option<String>) -> SecretKey {
+    pub fn generate_key(kt: KeyType, passphrase: Option<String>) -> Result<SecretKey> {
         let key_params = SecretKeyParamsBuilder::default()
             .key_type(kt)
             .can_create_certificates(true)
@@ -61,67 +79,129 @@ impl Signer {
             ])
             .passphrase(passphrase)
             .build()
-            .unwrap();
-        key_params
-            .generate()
-            .expect("failed to generate secret key, encrypted")
+            .map_err(|e| Error::Key(e.to_string()))?;
+        key_params.generate().map_err(|e| Error::Key(e.to_string()))
     }
 
-    pub fn sign_key(sk: SecretKey, passphrase: Option<String>) -> SignedSecretKey {
-        sk.sign(|| passphrase.unwrap_or_else(|| "".to_string()))
-            .unwrap()
+    pub fn sign_key(sk: SecretKey, passphrase: Option<String>) -> Result<SignedSecretKey> {
+        sk.sign(|| passphrase.unwrap_or_default())
+            .map_err(|e| Error::Key(e.to_string()))
     }
 
     pub fn export_private_key(&self) -> Result<String> {
-        Ok(self.signing_key.to_armored_string(None)?)
+        self.signing_key
+            .to_armored_string(None)
+            .map_err(|e| Error::Key(e.to_string()))
     }
 
-    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        let mut decrypted: Vec<u8> = vec![];
-
-        self.signing_key
-            .unlock(
-                || self.passphrase.clone().unwrap_or_else(|| "".to_owned()),
-                |unlocked| {
-                    match unlocked {
-                        SecretKeyRepr::RSA(k) => {
-                            let plain = k
-                                .decrypt(PaddingScheme::new_pkcs1v15_encrypt(), data)
-                                .expect("failed to decrypt");
-                            decrypted = plain;
-                        }
-                        _ => panic!("unexpected params type {:?}", unlocked),
-                    }
-                    std::result::Result::Ok(())
-                },
-            )
-            .unwrap();
-        Ok(decrypted)
+    /// Decrypts a payload written by [`Signer::encrypt`]. Payloads without the
+    /// header are entries written by older versions with plain RSA.
+    pub fn decrypt(&self, data: &[u8]) -> Result<SecretBytes> {
+        match data.strip_prefix(PAYLOAD_MAGIC) {
+            Some(payload) => self.decrypt_hybrid(payload),
+            None => self.with_private_key(|k| {
+                k.decrypt(PaddingScheme::new_pkcs1v15_encrypt(), data)
+                    .map(SecretBytes::from)
+                    .map_err(|e| Error::Decryption(e.to_string()))
+            }),
+        }
     }
+
+    /// Encrypts data of any size: the data is sealed with a random AES-256-GCM key
+    /// and only that key is encrypted with RSA.
+    /// Layout: magic | wrapped key length (u16 BE) | wrapped key | nonce | tag | ciphertext.
     pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        let mut encrypted: Vec<u8> = vec![];
+        let mut rng = thread_rng();
+        let mut key = [0u8; 32];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut key);
+        rng.fill_bytes(&mut nonce);
+
+        let wrapped_key = self.with_private_key(|k| {
+            let pk: rsa::RsaPublicKey = k.clone().into();
+            pk.encrypt(&mut rng, PaddingScheme::new_pkcs1v15_encrypt(), &key)
+                .map_err(|e| Error::Encryption(e.to_string()))
+        })?;
+        let wrapped_key_len = u16::try_from(wrapped_key.len())
+            .map_err(|_| Error::Encryption("wrapped key is too long".to_string()))?;
+
+        let mut ciphertext = vec![0u8; data.len()];
+        let mut tag = [0u8; TAG_LEN];
+        AesGcm::new(KeySize::KeySize256, &key, &nonce, PAYLOAD_MAGIC).encrypt(
+            data,
+            &mut ciphertext,
+            &mut tag,
+        );
+        key.zeroize();
+
+        let mut payload = Vec::with_capacity(
+            PAYLOAD_MAGIC.len() + 2 + wrapped_key.len() + NONCE_LEN + TAG_LEN + data.len(),
+        );
+        payload.extend_from_slice(PAYLOAD_MAGIC);
+        payload.extend_from_slice(&wrapped_key_len.to_be_bytes());
+        payload.extend_from_slice(&wrapped_key);
+        payload.extend_from_slice(&nonce);
+        payload.extend_from_slice(&tag);
+        payload.extend_from_slice(&ciphertext);
+        Ok(payload)
+    }
+
+    fn decrypt_hybrid(&self, payload: &[u8]) -> Result<SecretBytes> {
+        let truncated = || Error::Decryption("payload is truncated".to_string());
+        let (len, rest) = payload.split_at_checked(2).ok_or_else(truncated)?;
+        let wrapped_key_len = u16::from_be_bytes([len[0], len[1]]) as usize;
+        let (wrapped_key, rest) = rest
+            .split_at_checked(wrapped_key_len)
+            .ok_or_else(truncated)?;
+        let (nonce, rest) = rest.split_at_checked(NONCE_LEN).ok_or_else(truncated)?;
+        let (tag, ciphertext) = rest.split_at_checked(TAG_LEN).ok_or_else(truncated)?;
+
+        let key = self.with_private_key(|k| {
+            k.decrypt(PaddingScheme::new_pkcs1v15_encrypt(), wrapped_key)
+                .map(SecretBytes::from)
+                .map_err(|e| Error::Decryption(e.to_string()))
+        })?;
+        if key.len() != 32 {
+            return Err(Error::Decryption("unexpected key length".to_string()));
+        }
+
+        let mut plain = SecretBytes::from(vec![0u8; ciphertext.len()]);
+        if !AesGcm::new(KeySize::KeySize256, &key, nonce, PAYLOAD_MAGIC).decrypt(
+            ciphertext,
+            &mut plain[..],
+            tag,
+        ) {
+            return Err(Error::Decryption(
+                "payload failed authentication".to_string(),
+            ));
+        }
+        Ok(plain)
+    }
+
+    fn with_private_key<T>(&self, f: impl FnOnce(&rsa::RsaPrivateKey) -> Result<T>) -> Result<T> {
+        let mut f = Some(f);
+        let mut result = Err(Error::Decryption("key is locked".to_string()));
 
         self.signing_key
             .unlock(
-                || self.passphrase.clone().unwrap_or_else(|| "".to_owned()),
+                // pgp wants an owned copy and frees it without zeroizing, passm
+                // wipes it through `shared::secret::WipingAllocator`
+                || {
+                    self.passphrase
+                        .as_ref()
+                        .map(|passphrase| passphrase.expose().to_string())
+                        .unwrap_or_default()
+                },
                 |unlocked| {
-                    let mut rng = thread_rng();
-                    match unlocked {
-                        SecretKeyRepr::RSA(k) => {
-                            encrypted = {
-                                let k: rsa::RsaPrivateKey = k.clone();
-                                let pk: rsa::RsaPublicKey = k.into();
-                                pk.encrypt(&mut rng, PaddingScheme::new_pkcs1v15_encrypt(), data)
-                                    .expect("failed to encrypt")
-                            };
-                        }
-                        _ => panic!("unexpected params type {:?}", unlocked),
-                    }
+                    result = match (unlocked, f.take()) {
+                        (SecretKeyRepr::RSA(k), Some(f)) => f(k),
+                        _ => Err(Error::Key(format!("unexpected params type {:?}", unlocked))),
+                    };
                     std::result::Result::Ok(())
                 },
             )
-            .unwrap();
-        Ok(encrypted)
+            .map_err(|_| Error::WrongPassphrase)?;
+        result
     }
 }
 
@@ -134,10 +214,10 @@ mod tests {
 
     #[test]
     fn generate_key() {
-        let _ = simple_logger::init().unwrap();
+        let _ = simple_logger::init();
         let pass = String::from("pass");
         debug!("Generating key");
-        let secret_key = Signer::generate_key(KeyType::Rsa(2048), Some(pass.clone()));
+        let secret_key = Signer::generate_key(KeyType::Rsa(2048), Some(pass.clone())).unwrap();
         debug!("Key generated");
         let signed_sk = secret_key.sign(|| pass.clone()).unwrap();
         let private_armored_string = signed_sk.to_armored_string(None).unwrap();
@@ -152,10 +232,19 @@ mod tests {
 
         let test_string_content = "My Test Data".to_owned();
 
-        let signer = Signer::new(signed_sk, Some(pass));
+        let signer = Signer::new(signed_sk, Some(pass.into()));
         let encrypted: Vec<u8> = signer.encrypt(test_string_content.as_bytes()).unwrap();
         let decrypted = signer.decrypt(&encrypted).unwrap();
 
-        assert_eq!(decrypted, test_string_content.as_bytes());
+        assert_eq!(&decrypted[..], test_string_content.as_bytes());
+
+        // Well above what a single RSA block can hold
+        let note = "line of a long secure note\n".repeat(1000);
+        let encrypted = signer.encrypt(note.as_bytes()).unwrap();
+        assert_eq!(&signer.decrypt(&encrypted).unwrap()[..], note.as_bytes());
+
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(signer.decrypt(&tampered).is_err());
     }
 }
@@ -0,0 +1,154 @@
+use std::fmt;
+
+use crate::strength::Strength;
+
+/// Findings of a vault audit. Holds names only, never secrets.
+#[derive(Clone, Debug, Default)]
+pub struct AuditReport {
+    /// Number of audited entries
+    pub entries: usize,
+    /// Number of audited entries holding a password
+    pub passwords: usize,
+    pub weak: Vec<(String, Strength)>,
+    /// Passwords found in a breach list, with how often they were seen
+    pub breached: Vec<(String, u64)>,
+    /// Groups of entries sharing the same password
+    pub reused: Vec<Vec<String>>,
+    /// Passwords not changed for a long time, with their age in days
+    pub old: Vec<(String, i64)>,
+    /// Entries with the metadata fields they lack
+    pub missing_metadata: Vec<(String, Vec<&'static str>)>,
+    /// Entries that could not be decrypted
+    pub unreadable: Vec<String>,
+}
+
+impl AuditReport {
+    /// Part of the passwords affected by `count` findings.
+    fn share(&self, count: usize) -> f64 {
+        match self.passwords {
+            0 => 0.0,
+            passwords => count.min(passwords) as f64 / passwords as f64,
+        }
+    }
+
+    /// Health of the vault from 0 to 100.
+    pub fn score(&self) -> u8 {
+        let reused: usize = self.reused.iter().map(Vec::len).sum();
+        let penalty = 40.0 * self.share(self.weak.len())
+            + 40.0 * self.share(self.breached.len())
+            + 30.0 * self.share(reused)
+            + 15.0 * self.share(self.old.len())
+            + 15.0 * self.share(self.missing_metadata.len());
+        (100.0 - penalty).round().clamp(0.0, 100.0) as u8
+    }
+
+    pub fn grade(&self) -> &'static str {
+        match self.score() {
+            90.. => "excellent",
+            75..=89 => "good",
+            50..=74 => "needs work",
+            _ => "poor",
+        }
+    }
+
+    /// Report as titled sections of lines, shared by the CLI and the TUI.
+    pub fn sections(&self) -> Vec<(String, Vec<String>)> {
+        let mut sections = vec![];
+        let mut section = |title: String, lines: Vec<String>| {
+            if !lines.is_empty() {
+                sections.push((title, lines));
+            }
+        };
+        section(
+            format!("Weak passwords ({})", self.weak.len()),
+            self.weak
+                .iter()
+                .map(|(name, strength)| {
+                    format!("{}: {}, ~{:.0} bits", name, strength.label(), strength.bits)
+                })
+                .collect(),
+        );
+        section(
+            format!("Breached passwords ({})", self.breached.len()),
+            self.breached
+                .iter()
+                .map(|(name, count)| format!("{}: seen {} times in breaches", name, count))
+                .collect(),
+        );
+        section(
+            format!("Reused passwords ({} groups)", self.reused.len()),
+            self.reused.iter().map(|names| names.join(", ")).collect(),
+        );
+        section(
+            format!("Old passwords ({})", self.old.len()),
+            self.old
+                .iter()
+                .map(|(name, days)| format!("{}: unchanged for {} days", name, days))
+                .collect(),
+        );
+        section(
+            format!("Missing metadata ({})", self.missing_metadata.len()),
+            self.missing_metadata
+                .iter()
+                .map(|(name, fields)| format!("{}: no {}", name, fields.join(", ")))
+                .collect(),
+        );
+        section(
+            format!("Unreadable entries ({})", self.unreadable.len()),
+            self.unreadable.clone(),
+        );
+        sections
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Score {}/100 ({}), {} entries, {} passwords",
+            self.score(),
+            self.grade(),
+            self.entries,
+            self.passwords
+        )
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (title, lines) in self.sections() {
+            writeln!(f, "{}", title)?;
+            for line in lines {
+                writeln!(f, "  {}", line)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "{}", self.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AuditReport;
+    use crate::strength::estimate;
+
+    #[test]
+    fn scores_findings() {
+        let mut report = AuditReport {
+            entries: 5,
+            passwords: 4,
+            ..AuditReport::default()
+        };
+        assert_eq!(report.score(), 100);
+        report.weak.push(("mail".to_string(), estimate("1234")));
+        report
+            .reused
+            .push(vec!["mail".to_string(), "bank".to_string()]);
+        assert_eq!(report.score(), 75);
+        report.breached.push(("mail".to_string(), 3));
+        assert_eq!(report.score(), 65);
+        assert!(report.to_string().contains("mail: seen 3 times"));
+        report.breached.clear();
+        assert!(report.to_string().contains("mail, bank"));
+        assert!(report
+            .to_string()
+            .ends_with("Score 75/100 (good), 5 entries, 4 passwords"));
+    }
+}
@@ -1,20 +1,558 @@
-use std::io::stdin;
+use std::{
+    env,
+    fs::File,
+    io::{stdin, BufRead, BufReader, IsTerminal, Read},
+    path::PathBuf,
+    str::FromStr,
+    thread,
+    time::Duration,
+};
 
-use anyhow::{anyhow, Result};
+use zeroize::Zeroize;
 
-pub fn read_passphrase(with_confirmation: bool) -> Result<String> {
-    println!("Enter your passphrase:");
-    let mut buffer = String::new();
-    stdin().read_line(&mut buffer).unwrap();
+use crate::{
+    error::{Error, Result},
+    pinentry::Pinentry,
+    secret::{normalize_passphrase, SecretString},
+    strength::estimate,
+};
+
+/// Attempts at typing the passphrase before giving up.
+const ATTEMPTS: u32 = 3;
+/// Wait after the first wrong passphrase, doubled after every other one.
+const BACKOFF: Duration = Duration::from_secs(1);
+/// Room reserved for a typed line, so it is not copied around while growing.
+const LINE_CAPACITY: usize = 256;
+
+/// Where the passphrase of the key comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PassphraseSource {
+    /// Typed in, without echo when reading from a terminal
+    Prompt,
+    /// First line read from an inherited file descriptor, `fd:3`
+    Fd(i32),
+    /// First line of a file, `file:/run/secrets/passm`
+    File(PathBuf),
+    /// Content of an environment variable, `env:PASSM_PASSPHRASE`
+    Env(String),
+    /// Dialog of a pinentry program, `pinentry` finds one and
+    /// `pinentry:/usr/bin/pinentry-gnome3` names it
+    Pinentry(Option<PathBuf>),
+}
+
+impl FromStr for PassphraseSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            Error::InvalidInput(format!(
+                "unknown passphrase source {}, use fd:N, file:PATH, env:NAME or pinentry[:PROGRAM]",
+                s
+            ))
+        };
+        if s == "pinentry" {
+            return Ok(Self::Pinentry(None));
+        }
+        match s.split_once(':').ok_or_else(invalid)? {
+            ("fd", fd) => fd.parse().map(Self::Fd).map_err(|_| invalid()),
+            ("file", path) if !path.is_empty() => Ok(Self::File(path.into())),
+            ("env", name) if !name.is_empty() => Ok(Self::Env(name.to_string())),
+            ("pinentry", program) if !program.is_empty() => {
+                Ok(Self::Pinentry(Some(program.into())))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl PassphraseSource {
+    /// Whether someone types the passphrase, who can try again.
+    pub fn is_interactive(&self) -> bool {
+        matches!(self, Self::Prompt | Self::Pinentry(_))
+    }
+
+    /// Reads the passphrase of a source that is not typed in, warning about
+    /// how exposed it is. `take_var` returns an environment variable and
+    /// removes it. None for sources that are typed in.
+    fn read_once(
+        &self,
+        take_var: impl FnOnce(&str) -> Option<String>,
+    ) -> Result<Option<SecretString>> {
+        let mut line = match self {
+            Self::Prompt | Self::Pinentry(_) => return Ok(None),
+            Self::Fd(fd) => {
+                eprintln!(
+                    "Warning: reading the passphrase from file descriptor {}, anything that can write to it can feed one",
+                    fd
+                );
+                read_fd(*fd)?
+            }
+            Self::File(path) => {
+                eprintln!(
+                    "Warning: the passphrase is stored in plain text in {}, keep it on an encrypted disk",
+                    path.display()
+                );
+                warn_if_shared(path);
+                first_line(File::open(path)?)?
+            }
+            Self::Env(name) => {
+                eprintln!(
+                    "Warning: reading the passphrase from ${}, other processes of your user can read the environment",
+                    name
+                );
+                take_var(name).ok_or_else(|| {
+                    Error::InvalidInput(format!("environment variable {} is not set", name))
+                })?
+            }
+        };
+        let len = line.trim_end().len();
+        line.truncate(len);
+        Ok(Some(SecretString::new(line)))
+    }
+}
+
+/// Reads an environment variable and removes it, keeping it from child
+/// processes like a clipboard helper. Not safe while other threads may read
+/// the environment.
+fn take_var(name: &str) -> Option<String> {
+    let value = env::var(name).ok();
+    env::remove_var(name);
+    value
+}
+
+/// Answers the secret prompts of a command. The passphrase of the key comes
+/// from a [`PassphraseSource`], other secrets like the password of a backup
+/// are typed, in the pinentry dialog when that is the source.
+pub struct Secrets {
+    source: PassphraseSource,
+    /// Passphrase of a source that is not typed in, read once
+    preset: Option<SecretString>,
+}
+
+impl Secrets {
+    /// Reads a source that is not typed in right away. Removing an
+    /// environment variable is not safe while other threads may read the
+    /// environment, so this is meant to run before any are started.
+    pub fn new(source: PassphraseSource) -> Result<Self> {
+        Self::with_env(source, take_var)
+    }
 
-    if with_confirmation {
-        println!("Enter your passphrase again:");
-        let mut buffer_repeat = String::new();
-        stdin().read_line(&mut buffer_repeat).unwrap();
+    fn with_env(
+        source: PassphraseSource,
+        take_var: impl FnOnce(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let preset = source.read_once(take_var)?;
+        Ok(Self { source, preset })
+    }
+
+    /// Asks for a secret in the pinentry dialog or on the terminal.
+    fn ask(&self, question: &str, error: Option<&str>) -> Result<SecretString> {
+        match &self.source {
+            PassphraseSource::Pinentry(program) => {
+                let pinentry = match program {
+                    Some(program) => Pinentry::new(program.clone()),
+                    None => Pinentry::find()?,
+                };
+                let description = question.trim_end_matches(':');
+                pinentry.ask(description, error)
+            }
+            _ => {
+                if self.preset.is_some() {
+                    require_terminal(question)?;
+                }
+                read_hidden(question)
+            }
+        }
+    }
+
+    fn ask_passphrase(&self, question: &str, error: Option<&str>) -> Result<SecretString> {
+        match &self.preset {
+            Some(preset) => Ok(SecretString::new(preset.expose().to_string())),
+            None => self.ask(question, error).map(normalize_passphrase),
+        }
+    }
+
+    /// Asks for a secret that is not the passphrase, like the password of a backup.
+    pub fn read(&self, question: &str) -> Result<SecretString> {
+        self.ask(question, None)
+    }
+
+    /// Asks for a new secret twice, showing how strong it is on the
+    /// terminal and warning about a weak one in the pinentry dialog.
+    pub fn read_new(&self, question: &str) -> Result<SecretString> {
+        let PassphraseSource::Pinentry(_) = self.source else {
+            if self.preset.is_some() {
+                require_terminal(question)?;
+            }
+            return read_new_secret(question);
+        };
+        let secret = self.ask(question, None)?;
+        if self.ask("Enter it again:", None)?.expose() != secret.expose() {
+            return Err(Error::InvalidInput("Passphrases do not match".to_string()));
+        }
+        warn_if_weak(&secret);
+        Ok(secret)
+    }
+
+    /// Asks for the passphrase of the key.
+    pub fn passphrase(&self, question: &str) -> Result<SecretString> {
+        self.ask_passphrase(question, None)
+    }
+
+    /// Asks for the passphrase of a key about to be created.
+    pub fn new_passphrase(&self) -> Result<SecretString> {
+        let passphrase = match &self.preset {
+            Some(preset) => SecretString::new(preset.expose().to_string()),
+            None => self.read_new("Enter a passphrase for the new key:")?,
+        };
+        if self.preset.is_some() {
+            warn_if_weak(&passphrase);
+        }
+        Ok(normalize_passphrase(passphrase))
+    }
+
+    /// Reads the passphrase until `check` accepts it, at most three times and
+    /// waiting longer after every wrong one. A passphrase that is not typed in
+    /// is only tried once, it would be wrong again.
+    pub fn unlock_with_retries(
+        &self,
+        check: impl Fn(&SecretString) -> Result<()>,
+    ) -> Result<SecretString> {
+        let attempts = if self.preset.is_none() { ATTEMPTS } else { 1 };
+        let read = |attempt| {
+            let error = (attempt > 1).then_some("Wrong passphrase, try again");
+            self.ask_passphrase("Enter your passphrase:", error)
+        };
+        with_retries(attempts, BACKOFF, read, check)
+    }
+}
+
+/// Fails when no one is at a terminal to answer `question`. Secrets other
+/// than the passphrase never come from `--passphrase-from`, reusing the
+/// passphrase for them would tie a backup or export to it unnoticed.
+fn require_terminal(question: &str) -> Result<()> {
+    if stdin().is_terminal() {
+        return Ok(());
+    }
+    Err(Error::InvalidInput(format!(
+        "no terminal to answer \"{}\", only the passphrase of the key is read from its source",
+        question.trim_end_matches(':')
+    )))
+}
+
+fn warn_if_weak(secret: &SecretString) {
+    let strength = estimate(secret.expose());
+    if strength.is_weak() {
+        eprintln!("Warning: the new secret is {}", strength.meter());
+    }
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String> {
+    use std::{mem::ManuallyDrop, os::unix::io::FromRawFd};
+    if fd < 0 {
+        return Err(Error::InvalidInput(format!(
+            "invalid file descriptor {}",
+            fd
+        )));
+    }
+    // The descriptor stays open, it may be stdin or still be used by the caller
+    let file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    first_line(&*file)
+}
+
+#[cfg(not(unix))]
+fn read_fd(fd: i32) -> Result<String> {
+    Err(Error::InvalidInput(format!(
+        "reading file descriptor {} is only supported on unix",
+        fd
+    )))
+}
+
+#[cfg(unix)]
+fn warn_if_shared(path: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Ok(metadata) = path.metadata() {
+        if metadata.permissions().mode() & 0o077 != 0 {
+            eprintln!(
+                "Warning: {} can be read by other users, restrict it with chmod 600",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_shared(_path: &std::path::Path) {}
+
+fn first_line(input: impl Read) -> Result<String> {
+    let mut line = String::with_capacity(LINE_CAPACITY);
+    BufReader::new(input).read_line(&mut line)?;
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(line)
+}
+
+/// Applies typed bytes like a terminal in canonical mode would, up to Enter:
+/// backspace removes a character and Ctrl-U the whole line.
+fn edit_line(input: impl BufRead) -> Result<String> {
+    let mut line: Vec<u8> = Vec::with_capacity(LINE_CAPACITY);
+    let mut result = Err(Error::InvalidInput("no passphrase entered".to_string()));
+    for byte in input.bytes() {
+        match byte? {
+            b'\r' | b'\n' => {
+                result = Ok(());
+                break;
+            }
+            // Backspace or delete, dropping a whole UTF-8 character
+            0x7f | 0x08 => while line.pop().is_some_and(|byte| byte & 0xc0 == 0x80) {},
+            // Ctrl-U
+            0x15 => line.zeroize(),
+            // Ctrl-C or Ctrl-D
+            0x03 | 0x04 => {
+                result = Err(Error::InvalidInput("cancelled".to_string()));
+                break;
+            }
+            byte => line.push(byte),
+        }
+    }
+    if let Err(err) = result {
+        line.zeroize();
+        return Err(err);
+    }
+    String::from_utf8(line).map_err(|err| {
+        let mut bytes = err.into_bytes();
+        bytes.zeroize();
+        Error::InvalidInput("the passphrase is not valid UTF-8".to_string())
+    })
+}
+
+/// Terminal settings restored when reading a hidden line ends, also on errors.
+#[cfg(unix)]
+struct TerminalMode {
+    fd: i32,
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl TerminalMode {
+    /// Switches the terminal to raw input without echo.
+    fn raw(fd: i32) -> Result<Self> {
+        let mut original = std::mem::MaybeUninit::<libc::termios>::uninit();
+        if unsafe { libc::tcgetattr(fd, original.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let original = unsafe { original.assume_init() };
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ECHO | libc::ICANON | libc::ISIG | libc::IEXTEN);
+        raw.c_iflag &= !(libc::ICRNL | libc::IXON);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &raw) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self { fd, original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for TerminalMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSAFLUSH, &self.original);
+        }
+    }
+}
+
+/// Asks for a secret without showing it. Input that is not a terminal, like
+/// a pipe, is read line by line.
+pub fn read_hidden(question: &str) -> Result<SecretString> {
+    println!("{}", question);
+    #[cfg(unix)]
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+        let line = {
+            let _mode = TerminalMode::raw(libc::STDIN_FILENO)?;
+            edit_line(stdin().lock())
+        };
+        // Enter was not echoed either
         println!();
-        if buffer_repeat != buffer {
-            return Err(anyhow!("Passphrases do not match"));
+        return line.map(SecretString::new);
+    }
+    let mut line = String::with_capacity(LINE_CAPACITY);
+    stdin().read_line(&mut line)?;
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(SecretString::new(line))
+}
+
+/// Asks for a new passphrase or password twice, showing how strong it is.
+pub fn read_new_secret(question: &str) -> Result<SecretString> {
+    let secret = read_hidden(question)?;
+    let strength = estimate(secret.expose());
+    println!("Strength: {}", strength.meter());
+    if strength.is_weak() && !confirm("This is easy to guess. Use it anyway?")? {
+        return Err(Error::InvalidInput("Choose a stronger one".to_string()));
+    }
+    if read_hidden("Enter it again:")?.expose() != secret.expose() {
+        return Err(Error::InvalidInput("Passphrases do not match".to_string()));
+    }
+    Ok(secret)
+}
+
+fn with_retries(
+    attempts: u32,
+    backoff: Duration,
+    mut read: impl FnMut(u32) -> Result<SecretString>,
+    check: impl Fn(&SecretString) -> Result<()>,
+) -> Result<SecretString> {
+    let mut wait = backoff;
+    for attempt in 1..=attempts {
+        let passphrase = read(attempt)?;
+        match check(&passphrase) {
+            Ok(()) => return Ok(passphrase),
+            Err(Error::WrongPassphrase) if attempt < attempts => {
+                thread::sleep(wait);
+                wait *= 2;
+                let left = attempts - attempt;
+                let plural = if left == 1 { "" } else { "s" };
+                println!("Wrong passphrase, {} attempt{} left", left, plural);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Err(Error::WrongPassphrase)
+}
+
+/// Asks for a single line. None once the input is closed.
+pub fn prompt(question: &str) -> Result<Option<String>> {
+    println!("{}", question);
+    let mut buffer = String::new();
+    if stdin().read_line(&mut buffer)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(buffer.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+pub fn confirm(question: &str) -> Result<bool> {
+    println!("{} [y/N]", question);
+    let mut buffer = String::new();
+    stdin().read_line(&mut buffer)?;
+    Ok(matches!(buffer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn parses_sources() {
+        assert_eq!(
+            "fd:3".parse::<PassphraseSource>().unwrap(),
+            PassphraseSource::Fd(3)
+        );
+        assert_eq!(
+            "file:/run/secrets/passm"
+                .parse::<PassphraseSource>()
+                .unwrap(),
+            PassphraseSource::File("/run/secrets/passm".into())
+        );
+        assert_eq!(
+            "env:PASSM_PASSPHRASE".parse::<PassphraseSource>().unwrap(),
+            PassphraseSource::Env("PASSM_PASSPHRASE".to_string())
+        );
+        assert_eq!(
+            "pinentry".parse::<PassphraseSource>().unwrap(),
+            PassphraseSource::Pinentry(None)
+        );
+        assert_eq!(
+            "pinentry:/usr/bin/pinentry-tty"
+                .parse::<PassphraseSource>()
+                .unwrap(),
+            PassphraseSource::Pinentry(Some("/usr/bin/pinentry-tty".into()))
+        );
+        for invalid in [
+            "fd:three",
+            "env:",
+            "file:",
+            "pinentry:",
+            "stdin",
+            "url:http://x",
+        ] {
+            assert!(invalid.parse::<PassphraseSource>().is_err(), "{}", invalid);
         }
     }
-    Ok(buffer.trim_end().to_string())
+
+    #[test]
+    fn reads_non_interactive_sources() {
+        let path = env::temp_dir().join(format!("passm-passphrase-{}", std::process::id()));
+        std::fs::write(&path, "correct horse  \nsecond line\n").unwrap();
+        let secrets = Secrets::new(PassphraseSource::File(path.clone())).unwrap();
+        assert_eq!(secrets.passphrase("").unwrap().expose(), "correct horse");
+        // Other secrets are never answered with the passphrase
+        if !stdin().is_terminal() {
+            assert!(secrets.read("Password of the backup:").is_err());
+            assert!(secrets.read_new("Password of the backup:").is_err());
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            let file = File::open(&path).unwrap();
+            assert_eq!(read_fd(file.as_raw_fd()).unwrap(), "correct horse  ");
+            // Still open for its owner
+            assert!(file.metadata().is_ok());
+        }
+        std::fs::remove_file(path).unwrap();
+
+        let mut taken = Vec::new();
+        let take_var = |name: &str| {
+            taken.push(name.to_string());
+            Some("battery staple ".to_string())
+        };
+        let secrets = Secrets::with_env(PassphraseSource::Env("PW".to_string()), take_var).unwrap();
+        assert_eq!(secrets.passphrase("").unwrap().expose(), "battery staple");
+        assert_eq!(taken, ["PW"]);
+        assert!(Secrets::with_env(PassphraseSource::Env("PW".to_string()), |_| None).is_err());
+    }
+
+    #[test]
+    fn edits_typed_lines() {
+        assert_eq!(edit_line(&b"ab\x7fc\rignored"[..]).unwrap(), "ac");
+        assert_eq!(edit_line(&b"wrong\x15right\n"[..]).unwrap(), "right");
+        assert_eq!(edit_line("p\u{e4}\x7f\x7fok\r".as_bytes()).unwrap(), "ok");
+        assert!(edit_line(&b"secret\x03"[..]).is_err());
+        assert!(edit_line(&b"no enter"[..]).is_err());
+    }
+
+    #[test]
+    fn retries_wrong_passphrases() {
+        let check = |passphrase: &SecretString| match passphrase.expose() {
+            "right" => Ok(()),
+            _ => Err(Error::WrongPassphrase),
+        };
+        let typed = Cell::new(0);
+        let read = |answers: &'static [&'static str]| {
+            let typed = &typed;
+            move |attempt| {
+                typed.set(typed.get() + 1);
+                assert_eq!(attempt as usize, typed.get());
+                Ok(SecretString::new(answers[typed.get() - 1].to_string()))
+            }
+        };
+
+        let passphrase = with_retries(3, Duration::ZERO, read(&["wrong", "right"]), check).unwrap();
+        assert_eq!(passphrase.expose(), "right");
+        assert_eq!(typed.get(), 2);
+
+        typed.set(0);
+        let err = with_retries(3, Duration::ZERO, read(&["a", "b", "c", "right"]), check);
+        assert!(matches!(err, Err(Error::WrongPassphrase)));
+        assert_eq!(typed.get(), 3);
+
+        typed.set(0);
+        assert!(with_retries(1, Duration::ZERO, read(&["a", "right"]), check).is_err());
+        assert_eq!(typed.get(), 1);
+    }
 }
@@ -0,0 +1,31 @@
+use std::string::FromUtf8Error;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("Configuration error: {0}")]
+    Config(String),
+    #[error("Key error: {0}")]
+    Key(String),
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+    #[error("Failed to encrypt: {0}")]
+    Encryption(String),
+    #[error("Failed to decrypt: {0}")]
+    Decryption(String),
+    #[error("Clipboard is not available: {0}")]
+    Clipboard(String),
+    #[error("Entry is not valid UTF-8")]
+    InvalidUtf8(#[from] FromUtf8Error),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("No entry selected")]
+    NothingSelected,
+    #[error("Terminal error: {0}")]
+    Terminal(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -5,6 +5,7 @@ pub enum Event {
     Tick,
     Terminate,
     KeyEvent(KeyCode),
+    Paste(String),
 }
 
 pub enum StateChange {
@@ -21,6 +22,12 @@ pub enum KeyCode {
     Backspace,
     Left,
     Right,
+    CtrlLeft,
+    CtrlRight,
+    ShiftLeft,
+    ShiftRight,
+    ShiftHome,
+    ShiftEnd,
     Up,
     Down,
     Home,
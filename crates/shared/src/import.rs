@@ -0,0 +1,47 @@
+use crate::password::EntryKind;
+
+/// Entry of an import preview with what would happen to it.
+#[derive(Clone, Debug)]
+pub struct PreviewEntry {
+    pub name: String,
+    pub kind: EntryKind,
+    /// Planned action, like `add` or `save as mail (2)`
+    pub action: String,
+    /// Whether the name is already taken in the namespace
+    pub conflict: bool,
+}
+
+/// What an import would do, shown before anything is written.
+#[derive(Clone, Debug, Default)]
+pub struct ImportPreview {
+    pub format: String,
+    /// Conflict policy the actions were planned with
+    pub policy: String,
+    /// Fields of the export with the entry fields they are read into
+    pub mapping: Vec<(String, String)>,
+    pub entries: Vec<PreviewEntry>,
+    /// Items that can not be imported with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+impl ImportPreview {
+    pub fn conflicts(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.conflict).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: {} entries, {} with taken names, {} skipped, on conflict {}",
+            self.format,
+            self.entries.len(),
+            self.conflicts(),
+            self.skipped.len(),
+            self.policy
+        )
+    }
+
+    /// Number of lines rendered by the preview, to bound scrolling.
+    pub fn lines(&self) -> usize {
+        self.mapping.len() + self.entries.len() + self.skipped.len() + 8
+    }
+}
@@ -0,0 +1,408 @@
+use unicode_segmentation::UnicodeSegmentation;
+use zeroize::Zeroize;
+
+use crate::{
+    events::KeyCode,
+    secret::{lock, unlock},
+};
+
+/// Bytes reserved for the text up front, so typing a passphrase does not
+/// reallocate the buffer.
+const RESERVED: usize = 256;
+
+/// Text input with a cursor and an optional selection, single line unless
+/// created with [`TextInput::multiline`].
+/// Cursor and selection are measured in grapheme clusters, so multi code
+/// point characters are moved over and deleted as a whole.
+/// The text is kept in a locked buffer that is wiped whenever it is
+/// replaced, it can not be cloned or printed.
+#[derive(Default)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    selection_anchor: Option<usize>,
+    multiline: bool,
+}
+
+impl TextInput {
+    /// Creates an input holding `text` with the cursor placed at its end.
+    pub fn new(mut text: String) -> Self {
+        let cursor = text.graphemes(true).count();
+        let mut input = Self::default();
+        input.reserve(text.len());
+        input.text.push_str(&text);
+        input.cursor = cursor;
+        text.zeroize();
+        input
+    }
+
+    /// Creates an input where Enter inserts a line break and Up/Down move between lines.
+    pub fn multiline(text: String) -> Self {
+        let mut input = Self::new(text);
+        input.multiline = true;
+        input
+    }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Selected range of graphemes, start inclusive and end exclusive.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        self.selection_anchor
+            .filter(|anchor| *anchor != self.cursor)
+            .map(|anchor| (anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    /// Removes the text, keeping the input single or multiline.
+    pub fn clear(&mut self) {
+        self.text.zeroize();
+        self.cursor = 0;
+        self.selection_anchor = None;
+    }
+
+    /// Applies an editing key. Returns false when the key is not an editing key.
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Char('\n') if self.multiline => self.insert("\n"),
+            KeyCode::Char(c) if c != '\n' => self.insert(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Up if self.multiline => self.move_to(self.vertical_position(-1)),
+            KeyCode::Down if self.multiline => self.move_to(self.vertical_position(1)),
+            KeyCode::Backspace => self.delete_backward(),
+            KeyCode::Delete => self.delete_forward(),
+            KeyCode::CtrlBackspace | KeyCode::AltBackspace | KeyCode::Ctrl('w') => {
+                self.delete_word_backward()
+            }
+            KeyCode::CtrlDelete | KeyCode::AltDelete | KeyCode::Alt('d') => {
+                self.delete_word_forward()
+            }
+            KeyCode::Ctrl('u') => {
+                self.select_to(self.line_bounds().0);
+                self.delete_selection();
+            }
+            KeyCode::Ctrl('k') => {
+                self.select_to(self.line_bounds().1);
+                self.delete_selection();
+            }
+            KeyCode::Left | KeyCode::Ctrl('b') => self.move_to(self.cursor.saturating_sub(1)),
+            KeyCode::Right | KeyCode::Ctrl('f') => self.move_to(self.cursor + 1),
+            KeyCode::Home | KeyCode::Ctrl('a') => self.move_to(self.line_bounds().0),
+            KeyCode::End | KeyCode::Ctrl('e') => self.move_to(self.line_bounds().1),
+            KeyCode::CtrlLeft | KeyCode::Alt('b') => self.move_to(self.previous_word_start()),
+            KeyCode::CtrlRight | KeyCode::Alt('f') => self.move_to(self.next_word_end()),
+            KeyCode::ShiftLeft => self.select_to(self.cursor.saturating_sub(1)),
+            KeyCode::ShiftRight => self.select_to(self.cursor + 1),
+            KeyCode::ShiftHome => self.select_to(self.line_bounds().0),
+            KeyCode::ShiftEnd => self.select_to(self.line_bounds().1),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Inserts `text` at the cursor, replacing the selection if there is one.
+    pub fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        self.reserve(text.len());
+        let offset = self.byte_offset(self.cursor);
+        self.text.insert_str(offset, text);
+        self.cursor += text.graphemes(true).count();
+    }
+
+    /// Inserts pasted text. Line breaks are normalized to `\n` in multiline
+    /// inputs and dropped otherwise.
+    pub fn paste(&mut self, text: &str) {
+        let mut normalized = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let c = match c {
+                '\r' if chars.peek() == Some(&'\n') => continue,
+                '\r' => '\n',
+                c => c,
+            };
+            if c != '\n' || self.multiline {
+                normalized.push(c);
+            }
+        }
+        self.insert(&normalized);
+        normalized.zeroize();
+    }
+
+    /// Makes room for `additional` bytes. A larger buffer is allocated and
+    /// the old one wiped, instead of letting the string reallocate.
+    fn reserve(&mut self, additional: usize) {
+        let needed = self.text.len() + additional;
+        if needed <= self.text.capacity() {
+            return;
+        }
+        let capacity = needed.max(self.text.capacity() * 2).max(RESERVED);
+        let mut grown = String::with_capacity(capacity);
+        lock(grown.as_ptr(), grown.capacity());
+        grown.push_str(&self.text);
+        self.wipe();
+        self.text = grown;
+    }
+
+    fn wipe(&mut self) {
+        let (ptr, capacity) = (self.text.as_ptr(), self.text.capacity());
+        self.text.zeroize();
+        unlock(ptr, capacity);
+    }
+
+    fn len(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    fn byte_offset(&self, grapheme: usize) -> usize {
+        self.text
+            .grapheme_indices(true)
+            .nth(grapheme)
+            .map(|(offset, _)| offset)
+            .unwrap_or(self.text.len())
+    }
+
+    fn move_to(&mut self, position: usize) {
+        self.selection_anchor = None;
+        self.cursor = position.min(self.len());
+    }
+
+    fn select_to(&mut self, position: usize) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.cursor);
+        }
+        self.cursor = position.min(self.len());
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        let (start_offset, end_offset) = (self.byte_offset(start), self.byte_offset(end));
+        self.text.replace_range(start_offset..end_offset, "");
+        self.cursor = start;
+        self.selection_anchor = None;
+    }
+
+    /// Deletes the selection. Returns false if nothing was selected.
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some((start, end)) => {
+                self.delete_range(start, end);
+                true
+            }
+            None => {
+                self.selection_anchor = None;
+                false
+            }
+        }
+    }
+
+    fn delete_backward(&mut self) {
+        if !self.delete_selection() && self.cursor > 0 {
+            self.delete_range(self.cursor - 1, self.cursor);
+        }
+    }
+
+    fn delete_forward(&mut self) {
+        if !self.delete_selection() && self.cursor < self.len() {
+            self.delete_range(self.cursor, self.cursor + 1);
+        }
+    }
+
+    fn delete_word_backward(&mut self) {
+        if !self.delete_selection() {
+            self.delete_range(self.previous_word_start(), self.cursor);
+        }
+    }
+
+    fn delete_word_forward(&mut self) {
+        if !self.delete_selection() {
+            self.delete_range(self.cursor, self.next_word_end());
+        }
+    }
+
+    /// Start and end of every line, in graphemes, excluding the line break.
+    fn lines(&self) -> Vec<(usize, usize)> {
+        let mut lines = vec![];
+        let mut start = 0;
+        for (i, grapheme) in self.text.graphemes(true).enumerate() {
+            if grapheme == "\n" {
+                lines.push((start, i));
+                start = i + 1;
+            }
+        }
+        lines.push((start, self.len()));
+        lines
+    }
+
+    fn current_line(&self, lines: &[(usize, usize)]) -> usize {
+        lines
+            .iter()
+            .rposition(|(start, _)| *start <= self.cursor)
+            .unwrap_or(0)
+    }
+
+    /// Bounds of the line under the cursor, the whole text for single line inputs.
+    fn line_bounds(&self) -> (usize, usize) {
+        let lines = self.lines();
+        lines[self.current_line(&lines)]
+    }
+
+    /// Position on the line `delta` lines away, keeping the column where possible.
+    fn vertical_position(&self, delta: isize) -> usize {
+        let lines = self.lines();
+        let line = self.current_line(&lines);
+        let column = self.cursor - lines[line].0;
+        let target = line as isize + delta;
+        if target < 0 {
+            return 0;
+        }
+        match lines.get(target as usize) {
+            Some((start, end)) => (start + column).min(*end),
+            None => self.len(),
+        }
+    }
+
+    fn is_word(grapheme: &str) -> bool {
+        grapheme.chars().any(|c| c.is_alphanumeric())
+    }
+
+    fn previous_word_start(&self) -> usize {
+        let graphemes: Vec<&str> = self.text.graphemes(true).collect();
+        let mut position = self.cursor.min(graphemes.len());
+        while position > 0 && !Self::is_word(graphemes[position - 1]) {
+            position -= 1;
+        }
+        while position > 0 && Self::is_word(graphemes[position - 1]) {
+            position -= 1;
+        }
+        position
+    }
+
+    fn next_word_end(&self) -> usize {
+        let graphemes: Vec<&str> = self.text.graphemes(true).collect();
+        let mut position = self.cursor;
+        while position < graphemes.len() && !Self::is_word(graphemes[position]) {
+            position += 1;
+        }
+        while position < graphemes.len() && Self::is_word(graphemes[position]) {
+            position += 1;
+        }
+        position
+    }
+}
+
+// Inputs hold typed passwords and passphrases
+impl Drop for TextInput {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextInput;
+    use crate::events::KeyCode;
+
+    fn type_text(input: &mut TextInput, text: &str) {
+        for c in text.chars() {
+            input.handle_key(KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn edits_at_cursor() {
+        let mut input = TextInput::default();
+        type_text(&mut input, "helo");
+        input.handle_key(KeyCode::Left);
+        input.handle_key(KeyCode::Char('l'));
+        assert_eq!(input.text(), "hello");
+        input.handle_key(KeyCode::Home);
+        input.handle_key(KeyCode::Delete);
+        assert_eq!(input.text(), "ello");
+        input.handle_key(KeyCode::End);
+        input.handle_key(KeyCode::Backspace);
+        assert_eq!(input.text(), "ell");
+        assert_eq!(input.cursor(), 3);
+    }
+
+    #[test]
+    fn deletes_words() {
+        let mut input = TextInput::new("ssh prod-db root".to_string());
+        input.handle_key(KeyCode::CtrlBackspace);
+        assert_eq!(input.text(), "ssh prod-db ");
+        input.handle_key(KeyCode::AltBackspace);
+        assert_eq!(input.text(), "ssh prod-");
+        input.handle_key(KeyCode::Home);
+        input.handle_key(KeyCode::CtrlDelete);
+        assert_eq!(input.text(), " prod-");
+        input.handle_key(KeyCode::CtrlRight);
+        assert_eq!(input.cursor(), 5);
+    }
+
+    #[test]
+    fn replaces_selection() {
+        let mut input = TextInput::new("user@example.com".to_string());
+        input.handle_key(KeyCode::Home);
+        for _ in 0..4 {
+            input.handle_key(KeyCode::ShiftRight);
+        }
+        assert_eq!(input.selection(), Some((0, 4)));
+        input.paste("admin\n");
+        assert_eq!(input.text(), "admin@example.com");
+        assert_eq!(input.selection(), None);
+        input.handle_key(KeyCode::ShiftHome);
+        input.handle_key(KeyCode::Backspace);
+        assert_eq!(input.text(), "@example.com");
+    }
+
+    #[test]
+    fn edits_graphemes() {
+        let mut input = TextInput::new("cafe\u{301} 👩‍💻".to_string());
+        assert_eq!(input.cursor(), 6);
+        input.handle_key(KeyCode::Backspace);
+        assert_eq!(input.text(), "cafe\u{301} ");
+        input.handle_key(KeyCode::Left);
+        input.handle_key(KeyCode::Backspace);
+        assert_eq!(input.text(), "caf ");
+    }
+
+    #[test]
+    fn types_into_the_reserved_buffer() {
+        let mut input = TextInput::new("correct".to_string());
+        let buffer = input.text().as_ptr();
+        type_text(&mut input, " horse battery staple");
+        input.handle_key(KeyCode::Home);
+        input.paste("a\r\nvery ");
+        assert_eq!(input.text(), "avery correct horse battery staple");
+        assert_eq!(input.text().as_ptr(), buffer);
+    }
+
+    #[test]
+    fn edits_multiple_lines() {
+        let mut input = TextInput::multiline(String::new());
+        type_text(&mut input, "Host prod\nUser");
+        input.paste(" root\r\n  Port 22");
+        assert_eq!(input.text(), "Host prod\nUser root\n  Port 22");
+        input.handle_key(KeyCode::Up);
+        assert_eq!(input.cursor(), 19);
+        input.handle_key(KeyCode::Up);
+        input.handle_key(KeyCode::Up);
+        assert_eq!(input.cursor(), 0);
+        input.handle_key(KeyCode::End);
+        input.handle_key(KeyCode::Down);
+        assert_eq!(input.cursor(), 19);
+        input.handle_key(KeyCode::Ctrl('u'));
+        assert_eq!(input.text(), "Host prod\n\n  Port 22");
+        input.clear();
+        assert!(input.is_multiline());
+    }
+}
@@ -1,7 +1,16 @@
-pub mod events;
+pub mod audit;
 pub mod console;
-pub mod state;
+pub mod error;
+pub mod events;
+pub mod import;
+pub mod input;
+pub mod notification;
 pub mod password;
+pub mod pinentry;
+pub mod search;
+pub mod secret;
+pub mod state;
+pub mod strength;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Local};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NotificationLevel {
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Clone)]
+pub struct Notification {
+    pub level: NotificationLevel,
+    pub message: String,
+    pub created_at: DateTime<Local>,
+    /// Number of ticks the notification stays in the status bar
+    pub ticks_left: u32,
+}
+
+impl Notification {
+    pub fn new(level: NotificationLevel, message: String, ticks_left: u32) -> Self {
+        Self {
+            level,
+            message,
+            created_at: Local::now(),
+            ticks_left,
+        }
+    }
+}
@@ -1,4 +1,176 @@
-#[derive(Clone)]
+use std::{fmt, io};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{
+    error::{Error, Result},
+    secret::{SecretBytes, SecretString},
+};
+
+/// Entry in the passwords list. Only holds what is known without decrypting it.
+#[derive(Clone, Debug, Default)]
 pub struct Password {
     pub name: String,
+    pub modified: Option<DateTime<Local>>,
+    /// Size of the encrypted file in bytes
+    pub size: u64,
+    /// Kind of the entry, when known from the index
+    pub kind: Option<EntryKind>,
+    /// Metadata of the entry, when known from the index
+    pub metadata: EntryMetadata,
+    /// Times the password was seen in breaches, when checked against a breach list
+    pub breached: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    #[default]
+    Password,
+    /// Free form multiline text, like SSH config snippets or recovery codes
+    Note,
+}
+
+/// Non-secret details of an entry. Stored inside the encrypted entry and in
+/// the metadata index, so they can be listed and searched without decrypting
+/// every entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl EntryMetadata {
+    /// Parses a comma separated list of tags, dropping empty ones.
+    pub fn parse_tags(tags: &str) -> Vec<String> {
+        tags.split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Current one-time password of an entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtpCode {
+    pub code: String,
+    /// Seconds the code stays valid, None for counter based codes
+    pub remaining: Option<u64>,
+}
+
+/// Decrypted content of a stored entry. It can not be cloned, its secrets
+/// are left out when printed and wiped when dropped.
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Name of the entry, stored when the file name does not reveal it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub kind: EntryKind,
+    pub body: SecretString,
+    /// `otpauth://` URI or base32 secret for two-factor codes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp: Option<String>,
+    /// Free text kept next to a password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<SecretString>,
+    #[serde(default, flatten)]
+    pub metadata: EntryMetadata,
+}
+
+impl Entry {
+    pub fn new(kind: EntryKind, body: String) -> Self {
+        Self {
+            name: None,
+            kind,
+            body: SecretString::new(body),
+            otp: None,
+            notes: None,
+            metadata: EntryMetadata::default(),
+        }
+    }
+
+    /// Serializes the entry for encryption. The size is measured first, so
+    /// the buffer is never reallocated and leaves no copies behind.
+    pub fn to_bytes(&self) -> Result<SecretBytes> {
+        let mut counter = ByteCounter(0);
+        serde_json::to_writer(&mut counter, self).map_err(|e| Error::Encryption(e.to_string()))?;
+        let mut bytes = SecretBytes::new(Vec::with_capacity(counter.0));
+        serde_json::to_writer(bytes.as_vec_mut(), self)
+            .map_err(|e| Error::Encryption(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Parses a decrypted payload. Entries saved by older versions hold just
+    /// the password text and are read as passwords.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if let Ok(entry) = serde_json::from_slice(bytes) {
+            return Ok(entry);
+        }
+        Ok(Self::new(
+            EntryKind::Password,
+            String::from_utf8(bytes.to_vec())?,
+        ))
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("name", &self.name)
+            .field("kind", &self.kind)
+            .field("body", &"<redacted>")
+            .field("otp", &self.otp.as_ref().map(|_| "<redacted>"))
+            .field("notes", &self.notes.as_ref().map(|_| "<redacted>"))
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+/// Writer that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Body and notes wipe themselves, the one-time password secret is wiped here
+impl Drop for Entry {
+    fn drop(&mut self) {
+        self.otp.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, EntryKind, EntryMetadata};
+
+    #[test]
+    fn reads_legacy_and_structured_payloads() {
+        let legacy = Entry::from_bytes(b"hunter2").unwrap();
+        assert_eq!(
+            legacy,
+            Entry::new(EntryKind::Password, "hunter2".to_string())
+        );
+
+        let mut note = Entry::new(EntryKind::Note, "Host prod\n  User root\n".to_string());
+        assert_eq!(Entry::from_bytes(&note.to_bytes().unwrap()).unwrap(), note);
+        note.metadata.url = Some("ssh://prod".to_string());
+        note.metadata.tags = EntryMetadata::parse_tags("work, ssh,");
+        assert_eq!(note.metadata.tags, vec!["work", "ssh"]);
+        assert_eq!(Entry::from_bytes(&note.to_bytes().unwrap()).unwrap(), note);
+    }
 }
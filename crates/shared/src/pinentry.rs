@@ -0,0 +1,320 @@
+use std::{
+    env,
+    io::{stdin, BufRead, BufReader, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use zeroize::Zeroize;
+
+use crate::{
+    error::{Error, Result},
+    secret::SecretString,
+};
+
+/// Programs tried, in order, when none is configured and passm runs in a terminal.
+const TERMINAL_PROGRAMS: [&str; 3] = ["pinentry-curses", "pinentry-tty", "pinentry"];
+/// Programs tried without a terminal on a graphical session, where curses
+/// and tty pinentries have nothing to draw on.
+const GRAPHICAL_PROGRAMS: [&str; 3] = ["pinentry-gnome3", "pinentry-qt", "pinentry"];
+/// Error code pinentry answers with when the dialog is closed.
+const CANCELLED: &str = "83886179";
+
+/// Passphrase dialog of a pinentry program, spoken to with the Assuan
+/// protocol. It works without a terminal, like from an editor.
+pub struct Pinentry {
+    program: PathBuf,
+}
+
+/// Finds `name` in `PATH`, the way a shell would.
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Percent-encodes what can not appear in an Assuan line.
+fn escape(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Appends the percent-decoded `data` to `out`.
+fn unescape_into(data: &str, out: &mut Vec<u8>) {
+    let bytes = data.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let decoded = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// A running pinentry, closed when dropped.
+struct Session {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl Session {
+    fn start(program: &Path) -> Result<Self> {
+        let mut child = Command::new(program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                Error::InvalidInput(format!("failed to start {}: {}", program.display(), e))
+            })?;
+        let input = child.stdin.take().ok_or_else(|| closed(program))?;
+        let output = BufReader::new(child.stdout.take().ok_or_else(|| closed(program))?);
+        let mut session = Self {
+            child,
+            input,
+            output,
+        };
+        // Greeting
+        session.response(&mut Vec::new())?;
+        Ok(session)
+    }
+
+    /// Sends a command and waits for `OK`, collecting `D` lines into `data`.
+    fn command(&mut self, line: &str, data: &mut Vec<u8>) -> Result<()> {
+        writeln!(self.input, "{}", line)?;
+        self.input.flush()?;
+        self.response(data)
+    }
+
+    fn response(&mut self, data: &mut Vec<u8>) -> Result<()> {
+        let mut line = String::new();
+        loop {
+            line.zeroize();
+            if self.output.read_line(&mut line)? == 0 {
+                return Err(Error::InvalidInput(
+                    "pinentry closed the connection".to_string(),
+                ));
+            }
+            let content = line.trim_end_matches(['\r', '\n']);
+            if content == "OK" || content.starts_with("OK ") {
+                return Ok(());
+            }
+            if let Some(payload) = content.strip_prefix("D ") {
+                unescape_into(payload, data);
+            } else if let Some(error) = content.strip_prefix("ERR ") {
+                let (code, message) = error.split_once(' ').unwrap_or((error, ""));
+                let err = match code {
+                    CANCELLED => Error::InvalidInput("cancelled".to_string()),
+                    _ => Error::InvalidInput(format!("pinentry failed: {}", message)),
+                };
+                return Err(err);
+            } else if content.starts_with("INQUIRE") {
+                // Nothing is offered to inquiries, cancelling them keeps the dialog going
+                writeln!(self.input, "CAN")?;
+                self.input.flush()?;
+            }
+            // `S` status and `#` comment lines are informational
+        }
+    }
+
+    fn set(&mut self, command: &str, value: &str) -> Result<()> {
+        self.command(&format!("{} {}", command, escape(value)), &mut Vec::new())
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = writeln!(self.input, "BYE");
+        let _ = self.input.flush();
+        let _ = self.child.wait();
+    }
+}
+
+fn closed(program: &Path) -> Error {
+    Error::InvalidInput(format!("no pipe to {}", program.display()))
+}
+
+impl Pinentry {
+    pub fn new(program: PathBuf) -> Self {
+        Self { program }
+    }
+
+    /// The first of `pinentry-curses`, `pinentry-tty` and `pinentry` found in
+    /// `PATH`. Without a terminal on a graphical session, the first of
+    /// `pinentry-gnome3`, `pinentry-qt` and `pinentry` instead.
+    pub fn find() -> Result<Self> {
+        let graphical = ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|name| env::var_os(name).is_some_and(|value| !value.is_empty()));
+        let programs = candidates(graphical, stdin().is_terminal());
+        programs
+            .iter()
+            .find_map(|name| find_in_path(name))
+            .map(Self::new)
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "no pinentry found, install one of {} or name the program",
+                    programs.join(", ")
+                ))
+            })
+    }
+
+    /// Shows a dialog with `description` and returns what was typed.
+    /// `error` is shown above it, like after a wrong passphrase.
+    pub fn ask(&self, description: &str, error: Option<&str>) -> Result<SecretString> {
+        let mut session = Session::start(&self.program)?;
+        // Curses and tty pinentries draw on the terminal passm was started from
+        if let Some(tty) = env::var("GPG_TTY").ok().or_else(terminal_name) {
+            session.set("OPTION", &format!("ttyname={}", tty))?;
+        }
+        if let Ok(term) = env::var("TERM") {
+            session.set("OPTION", &format!("ttytype={}", term))?;
+        }
+        session.set("SETTITLE", "passm")?;
+        session.set("SETDESC", description)?;
+        session.set("SETPROMPT", "Passphrase:")?;
+        if let Some(error) = error {
+            session.set("SETERROR", error)?;
+        }
+        let mut pin = Vec::with_capacity(256);
+        if let Err(err) = session.command("GETPIN", &mut pin) {
+            pin.zeroize();
+            return Err(err);
+        }
+        String::from_utf8(pin)
+            .map(SecretString::new)
+            .map_err(|err| {
+                err.into_bytes().zeroize();
+                Error::InvalidInput("the passphrase is not valid UTF-8".to_string())
+            })
+    }
+}
+
+/// Pinentries to try, in order.
+fn candidates(graphical: bool, terminal: bool) -> &'static [&'static str] {
+    if graphical && !terminal {
+        &GRAPHICAL_PROGRAMS
+    } else {
+        &TERMINAL_PROGRAMS
+    }
+}
+
+#[cfg(unix)]
+fn terminal_name() -> Option<String> {
+    let name = unsafe { libc::ttyname(libc::STDIN_FILENO) };
+    if name.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(name) };
+    name.to_str().ok().map(str::to_string)
+}
+
+#[cfg(not(unix))]
+fn terminal_name() -> Option<String> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        fs,
+        os::unix::fs::PermissionsExt,
+        sync::{Mutex, MutexGuard},
+    };
+
+    use super::*;
+
+    /// Running a script while another test still has one open for writing
+    /// fails with "text file busy", so fake pinentries run one at a time.
+    static SPAWN: Mutex<()> = Mutex::new(());
+
+    fn serial() -> MutexGuard<'static, ()> {
+        SPAWN
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Writes a fake pinentry answering GETPIN with `answer` and logging
+    /// every command it gets.
+    fn fake_pinentry(name: &str, answer: &str) -> (PathBuf, PathBuf) {
+        let dir = env::temp_dir().join(format!("passm-pinentry-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("commands");
+        let program = dir.join("pinentry");
+        let script = format!(
+            r#"#!/bin/sh
+echo "OK Pleased to meet you"
+while read -r line; do
+  echo "$line" >> "{log}"
+  case "$line" in
+    GETPIN*) echo "S PASSPHRASE_SHOWN"; {answer};;
+    BYE*) echo "OK closing connection"; exit 0;;
+    *) echo "OK";;
+  esac
+done
+"#,
+            log = log.display(),
+            answer = answer
+        );
+        fs::write(&program, script).unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o700)).unwrap();
+        (program, log)
+    }
+
+    #[test]
+    fn reads_the_pin() {
+        let _serial = serial();
+        let (program, log) =
+            fake_pinentry("pin", r#"echo "D correct%25horse %0Abattery"; echo OK"#);
+        let pin = Pinentry::new(program.clone())
+            .ask("Unlock 100% of\nthe vault", Some("Wrong passphrase"))
+            .unwrap();
+        assert_eq!(pin.expose(), "correct%horse \nbattery");
+
+        let commands = fs::read_to_string(&log).unwrap();
+        assert!(commands.contains("SETDESC Unlock 100%25 of%0Athe vault\n"));
+        assert!(commands.contains("SETERROR Wrong passphrase\n"));
+        assert!(commands.ends_with("GETPIN\nBYE\n"));
+        fs::remove_dir_all(program.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reports_a_closed_dialog() {
+        let _serial = serial();
+        let (program, _) = fake_pinentry("cancel", r#"echo "ERR 83886179 Operation cancelled""#);
+        let result = Pinentry::new(program.clone()).ask("Unlock", None);
+        assert!(matches!(result, Err(Error::InvalidInput(message)) if message == "cancelled"));
+        fs::remove_dir_all(program.parent().unwrap()).unwrap();
+
+        let missing = Pinentry::new("/nonexistent/pinentry".into()).ask("Unlock", None);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn prefers_graphical_pinentries_without_a_terminal() {
+        assert_eq!(candidates(true, false)[0], "pinentry-gnome3");
+        assert_eq!(candidates(true, true)[0], "pinentry-curses");
+        assert_eq!(candidates(false, false)[0], "pinentry-curses");
+    }
+
+    #[test]
+    fn decodes_data_lines() {
+        let mut out = Vec::new();
+        unescape_into("a%20b%zz%4", &mut out);
+        assert_eq!(out, b"a b%zz%4");
+        assert_eq!(escape("50%\r\n"), "50%25%0D%0A");
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use crate::password::Password;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Most recently used entries that get a ranking bonus.
+pub const RECENT_LIMIT: usize = 50;
+const BONUS_RECENT: i64 = 32;
+
+/// Parsed search query: `tag:`, `url:` and `user:` terms filter on metadata,
+/// every other term is fuzzy matched against the name.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    pub terms: Vec<String>,
+    pub tags: Vec<String>,
+    pub urls: Vec<String>,
+    pub users: Vec<String>,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = Query::default();
+        for token in query.split_whitespace() {
+            let (target, value) = match token.split_once(':') {
+                Some(("tag", value)) => (&mut parsed.tags, value),
+                Some(("url", value)) => (&mut parsed.urls, value),
+                Some(("user", value)) => (&mut parsed.users, value),
+                _ => (&mut parsed.terms, token),
+            };
+            if !value.is_empty() {
+                target.push(value.to_lowercase());
+            }
+        }
+        parsed
+    }
+
+    fn matches_metadata(&self, password: &Password) -> bool {
+        let contains = |field: &Option<String>, value: &String| {
+            field
+                .as_ref()
+                .is_some_and(|field| field.to_lowercase().contains(value.as_str()))
+        };
+        self.urls
+            .iter()
+            .all(|url| contains(&password.metadata.url, url))
+            && self
+                .users
+                .iter()
+                .all(|user| contains(&password.metadata.username, user))
+            && self.tags.iter().all(|tag| {
+                password
+                    .metadata
+                    .tags
+                    .iter()
+                    .any(|candidate| candidate.to_lowercase() == *tag)
+            })
+    }
+}
+
+/// Password matched by a search, with the matched character positions in its name.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    pub index: usize,
+    pub positions: Vec<usize>,
+}
+
+/// Filters and ranks passwords by fuzzy score plus a bonus for recent use.
+/// `recent` holds names, most recently used first.
+pub fn search(passwords: &[Password], query: &str, recent: &[String]) -> Vec<SearchHit> {
+    let query = Query::parse(query);
+    let recent_rank: HashMap<&str, usize> = recent
+        .iter()
+        .take(RECENT_LIMIT)
+        .enumerate()
+        .map(|(rank, name)| (name.as_str(), rank))
+        .collect();
+
+    let mut hits: Vec<(i64, SearchHit)> = passwords
+        .iter()
+        .enumerate()
+        .filter(|(_, password)| query.matches_metadata(password))
+        .filter_map(|(index, password)| {
+            let mut score = 0;
+            let mut positions = vec![];
+            for term in &query.terms {
+                let (term_score, term_positions) = fuzzy_match(term, &password.name)?;
+                score += term_score;
+                positions.extend(term_positions);
+            }
+            positions.sort_unstable();
+            positions.dedup();
+            if let Some(rank) = recent_rank.get(password.name.as_str()) {
+                score += BONUS_RECENT * (RECENT_LIMIT - rank) as i64 / RECENT_LIMIT as i64;
+            }
+            Some((score, SearchHit { index, positions }))
+        })
+        .collect();
+
+    // Higher score first, shorter names win ties like in fzf
+    hits.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.cmp(a_score).then_with(|| {
+            let (a_name, b_name) = (&passwords[a.index].name, &passwords[b.index].name);
+            a_name.len().cmp(&b_name.len()).then(a_name.cmp(b_name))
+        })
+    });
+    hits.into_iter().map(|(_, hit)| hit).collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Lower,
+    Upper,
+    Number,
+    Other,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_lowercase() {
+            CharClass::Lower
+        } else if c.is_uppercase() {
+            CharClass::Upper
+        } else if c.is_numeric() {
+            CharClass::Number
+        } else if c.is_alphabetic() {
+            CharClass::Lower
+        } else {
+            CharClass::Other
+        }
+    }
+}
+
+fn bonus_at(chars: &[char], position: usize) -> i64 {
+    let current = CharClass::of(chars[position]);
+    if current == CharClass::Other {
+        return 0;
+    }
+    let previous = match position {
+        0 => CharClass::Other,
+        _ => CharClass::of(chars[position - 1]),
+    };
+    match (previous, current) {
+        (CharClass::Other, _) => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL,
+        (CharClass::Number, _) | (_, CharClass::Number) if previous != current => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// Fuzzy matches `pattern` against `text` in the style of fzf: characters must
+/// appear in order, matches at word boundaries and consecutive runs score
+/// higher, gaps cost points. Case insensitive unless the pattern has an
+/// uppercase letter. Returns the score and matched character positions.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = pattern.chars().any(|c| c.is_uppercase());
+    let normalize = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let pattern: Vec<char> = pattern.chars().map(normalize).collect();
+    let chars: Vec<char> = text.chars().collect();
+    if pattern.is_empty() {
+        return Some((0, vec![]));
+    }
+
+    // Forward scan finds where the first full occurrence ends
+    let mut pattern_index = 0;
+    let mut end = None;
+    for (i, c) in chars.iter().enumerate() {
+        if normalize(*c) == pattern[pattern_index] {
+            pattern_index += 1;
+            if pattern_index == pattern.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward scan from there finds the shortest occurrence
+    let mut positions = vec![0; pattern.len()];
+    let mut pattern_index = pattern.len();
+    for i in (0..=end).rev() {
+        if normalize(chars[i]) == pattern[pattern_index - 1] {
+            pattern_index -= 1;
+            positions[pattern_index] = i;
+            if pattern_index == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut run_bonus = 0;
+    for (k, position) in positions.iter().enumerate() {
+        let mut bonus = bonus_at(&chars, *position);
+        match previous {
+            Some(previous) if previous + 1 == *position => {
+                // Consecutive matches keep the bonus of the run start
+                run_bonus = run_bonus.max(BONUS_CONSECUTIVE);
+                bonus = bonus.max(run_bonus);
+            }
+            Some(previous) => {
+                let gap = (*position - previous - 1) as i64;
+                score += SCORE_GAP_START + SCORE_GAP_EXTENSION * (gap - 1);
+                run_bonus = bonus;
+            }
+            None => run_bonus = bonus,
+        }
+        if k == 0 {
+            bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+        }
+        score += SCORE_MATCH + bonus;
+        previous = Some(*position);
+    }
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_match, search, Query};
+    use crate::password::Password;
+
+    fn password(name: &str) -> Password {
+        Password {
+            name: name.to_string(),
+            ..Password::default()
+        }
+    }
+
+    #[test]
+    fn matches_in_order() {
+        assert_eq!(fuzzy_match("gh", "github").unwrap().1, vec![0, 3]);
+        assert_eq!(fuzzy_match("bug", "github"), None);
+        // Backward scan picks the tightest occurrence
+        assert_eq!(fuzzy_match("ab", "a-xab").unwrap().1, vec![3, 4]);
+        assert!(fuzzy_match("Git", "github").is_none());
+    }
+
+    #[test]
+    fn prefers_boundaries_and_runs() {
+        let boundary = fuzzy_match("pd", "prod-db").unwrap().0;
+        let middle = fuzzy_match("pd", "upgrade").unwrap().0;
+        assert!(boundary > middle);
+        let run = fuzzy_match("mail", "xmailx").unwrap().0;
+        let scattered = fuzzy_match("mail", "xmxaxixlx").unwrap().0;
+        assert!(run > scattered);
+    }
+
+    #[test]
+    fn parses_operators() {
+        let query = Query::parse("work tag:Work url:github user:alice url:");
+        assert_eq!(query.terms, vec!["work"]);
+        assert_eq!(query.tags, vec!["work"]);
+        assert_eq!(query.urls, vec!["github"]);
+        assert_eq!(query.users, vec!["alice"]);
+    }
+
+    #[test]
+    fn ranks_by_score_and_recent_use() {
+        let mut work_mail = password("work-mail");
+        work_mail.metadata.tags = vec!["work".to_string()];
+        work_mail.metadata.username = Some("alice".to_string());
+        let passwords = vec![password("mailbox"), password("gmail"), work_mail];
+
+        let names = |hits: Vec<super::SearchHit>| -> Vec<String> {
+            hits.iter()
+                .map(|hit| passwords[hit.index].name.clone())
+                .collect()
+        };
+        assert_eq!(
+            names(search(&passwords, "mail", &[])),
+            vec!["mailbox", "work-mail", "gmail"]
+        );
+        assert_eq!(
+            names(search(&passwords, "mail", &["gmail".to_string()])),
+            vec!["gmail", "mailbox", "work-mail"]
+        );
+        assert_eq!(
+            names(search(&passwords, "tag:work user:ali", &[])),
+            vec!["work-mail"]
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    ops::{Deref, DerefMut},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+/// Keeps the pages holding `len` bytes at `ptr` out of swap, where the
+/// process is allowed to. Failing, like over `RLIMIT_MEMLOCK`, is not an error.
+#[cfg(target_os = "linux")]
+pub(crate) fn lock(ptr: *const u8, len: usize) {
+    if len > 0 {
+        unsafe {
+            libc::mlock(ptr.cast(), len);
+        }
+    }
+}
+
+/// Pages are locked as a whole, unlocking one may also unlock a neighbouring
+/// secret. It stays zeroized on drop either way.
+#[cfg(target_os = "linux")]
+pub(crate) fn unlock(ptr: *const u8, len: usize) {
+    if len > 0 {
+        unsafe {
+            libc::munlock(ptr.cast(), len);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn lock(_ptr: *const u8, _len: usize) {}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn unlock(_ptr: *const u8, _len: usize) {}
+
+/// Stops the process from writing core dumps, which would put every secret
+/// in memory on disk, and from being attached to by other users' debuggers.
+pub fn disable_core_dumps() {
+    #[cfg(unix)]
+    unsafe {
+        let limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        libc::setrlimit(libc::RLIMIT_CORE, &limit);
+    }
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0);
+    }
+}
+
+/// Allocator wiping memory before it is freed. Libraries like pgp take
+/// owned copies of the passphrase and key material and drop them without
+/// zeroizing, this catches those copies too.
+pub struct WipingAllocator;
+
+unsafe impl GlobalAlloc for WipingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        System.alloc_zeroed(layout)
+    }
+
+    // Reallocating goes through `alloc` and `dealloc`, so the old block is wiped too
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        std::slice::from_raw_parts_mut(ptr, layout.size()).zeroize();
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Text like a passphrase. The buffer is locked in memory, wiped when
+/// dropped, and the type can not be cloned or printed by accident.
+pub struct SecretString(String);
+
+impl SecretString {
+    /// Takes over `text` without copying it.
+    pub fn new(text: String) -> Self {
+        lock(text.as_ptr(), text.capacity());
+        Self(text)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for SecretString {}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        let (ptr, capacity) = (self.0.as_ptr(), self.0.capacity());
+        self.0.zeroize();
+        unlock(ptr, capacity);
+    }
+}
+
+/// Passphrases never end in whitespace, as when they were first read by
+/// line. The terminal interface and the command line both apply this, so a
+/// passphrase set in one unlocks in the other.
+pub fn normalize_passphrase(passphrase: SecretString) -> SecretString {
+    let trimmed = passphrase.expose().trim_end();
+    if trimmed.len() == passphrase.expose().len() {
+        return passphrase;
+    }
+    SecretString::new(trimmed.to_string())
+}
+
+/// Decrypted bytes, handled like [`SecretString`].
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// Takes over `bytes` without copying them.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        lock(bytes.as_ptr(), bytes.capacity());
+        Self(bytes)
+    }
+
+    /// The buffer, to be filled within its reserved capacity. Growing it
+    /// leaves the old allocation behind unwiped and unlocked.
+    pub fn as_vec_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl DerefMut for SecretBytes {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        let (ptr, capacity) = (self.0.as_ptr(), self.0.capacity());
+        self.0.zeroize();
+        unlock(ptr, capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_keep_their_content() {
+        let passphrase = SecretString::new("correct horse".to_string());
+        assert_eq!(passphrase.expose(), "correct horse");
+        assert!(!passphrase.is_empty());
+        let typed = normalize_passphrase(SecretString::new("correct horse \t".to_string()));
+        assert_eq!(typed.expose(), "correct horse");
+
+        let bytes = SecretBytes::from(b"{\"body\":\"hunter2\"}".to_vec());
+        assert_eq!(&bytes[..7], b"{\"body\"");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn disables_core_dumps() {
+        disable_core_dumps();
+        let mut limit = libc::rlimit {
+            rlim_cur: 1,
+            rlim_max: 1,
+        };
+        assert_eq!(unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) }, 0);
+        assert_eq!((limit.rlim_cur, limit.rlim_max), (0, 0));
+        #[cfg(target_os = "linux")]
+        assert_eq!(unsafe { libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) }, 0);
+    }
+}
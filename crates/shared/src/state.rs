@@ -1,4 +1,15 @@
-use crate::password::Password;
+use std::collections::VecDeque;
+
+use crate::{
+    audit::AuditReport,
+    events::KeyCode,
+    import::ImportPreview,
+    input::TextInput,
+    notification::{Notification, NotificationLevel},
+    password::{Entry, EntryKind, EntryMetadata, OtpCode, Password},
+    search::RECENT_LIMIT,
+    secret::SecretString,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ActivePage {
@@ -6,33 +17,149 @@ pub enum ActivePage {
 
     CreateNewPasswordName,
     CreateNewPasswordBody,
+    CreateNewPasswordDetails,
 
     EditPasswordName,
     EditPasswordBody,
+    EditPasswordDetails,
 
     SearchPasswordsList,
     SearchPasswordsListName,
 
     ExportPgpLocation,
     ExportPgpMasterPassword,
+
+    NamespacesList,
+    CreateNamespaceName,
+    CreateNamespacePassphrase,
+    CreateNamespacePassphraseConfirm,
+    RenameNamespace,
+    DeleteNamespace,
+    UnlockNamespace,
+
+    MessageLog,
+
+    Audit,
+
+    ImportLocation,
+    ImportPreview,
+
+    BackupLocation,
+    BackupPassword,
+    BackupPasswordConfirm,
+
+    PlainExportLocation,
+    PlainExportPassphrase,
+}
+
+/// Field focused on the entry details page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DetailsField {
+    #[default]
+    Username,
+    Url,
+    Tags,
+    Otp,
+    Notes,
 }
 
-#[derive(Clone)]
+impl DetailsField {
+    pub fn next(self) -> Option<Self> {
+        match self {
+            DetailsField::Username => Some(DetailsField::Url),
+            DetailsField::Url => Some(DetailsField::Tags),
+            DetailsField::Tags => Some(DetailsField::Otp),
+            DetailsField::Otp => Some(DetailsField::Notes),
+            DetailsField::Notes => None,
+        }
+    }
+
+    pub fn previous(self) -> Option<Self> {
+        match self {
+            DetailsField::Username => None,
+            DetailsField::Url => Some(DetailsField::Username),
+            DetailsField::Tags => Some(DetailsField::Url),
+            DetailsField::Otp => Some(DetailsField::Tags),
+            DetailsField::Notes => Some(DetailsField::Otp),
+        }
+    }
+}
+
+/// Everything the UI draws. It holds decrypted data, so it is lent to the UI
+/// on every draw and never copied.
 pub struct State {
     pub active_page: ActivePage,
     pub passwords_list: Vec<Password>,
     pub active_password_record: usize,
+    /// Prefix typed to jump to a name, while type-ahead is active
+    pub passwords_list_jump: Option<String>,
+    /// Names of used entries, most recent first
+    pub recently_used: Vec<String>,
+    /// Decrypted entry shown in the details pane, with the name it belongs to
+    pub revealed_entry: Option<(String, Entry)>,
 
-    pub passwords_list_search_term: Option<String>,
+    pub passwords_list_search_term: TextInput,
     pub passwords_list_search: Vec<Password>,
+    /// Matched name characters of every search result
+    pub passwords_list_search_highlights: Vec<Vec<usize>>,
     pub active_password_record_search: usize,
 
-    pub password_name_input: Option<String>,
-    pub password_input: Option<String>,
+    pub password_name_input: TextInput,
+    pub password_input: TextInput,
+    /// Kind of the entry being created or edited
+    pub entry_kind: EntryKind,
+    /// Whether the focused secret input shows its text instead of bullets
+    pub secret_revealed: bool,
+    pub entry_username_input: TextInput,
+    pub entry_url_input: TextInput,
+    /// Comma separated tags of the entry being created or edited
+    pub entry_tags_input: TextInput,
+    /// `otpauth://` URI or base32 secret of the entry being created or edited
+    pub entry_otp_input: TextInput,
+    pub entry_notes_input: TextInput,
+    pub entry_details_field: DetailsField,
+    /// Live one-time password of the revealed entry, refreshed every tick
+    pub otp_code: Option<OtpCode>,
 
-    pub export_pgp_secret_location: Option<String>,
+    pub export_pgp_secret_location: TextInput,
     pub export_pgp_secret_location_error: bool,
-    pub export_pgp_secret_master_password: Option<String>,
+    pub export_pgp_secret_master_password: TextInput,
+
+    pub namespaces: Vec<String>,
+    pub active_namespace: String,
+    pub default_namespace: String,
+    pub active_namespace_record: usize,
+    pub namespace_name_input: TextInput,
+    pub namespace_passphrase_input: TextInput,
+    /// New namespace passphrase typed again
+    pub namespace_passphrase_confirm: TextInput,
+    pub namespace_error: Option<String>,
+
+    pub error: Option<String>,
+
+    pub notifications: VecDeque<Notification>,
+    pub message_log: Vec<Notification>,
+    pub message_log_scroll: usize,
+
+    pub audit_report: Option<AuditReport>,
+    pub audit_scroll: usize,
+
+    /// Path of the export file to import
+    pub import_location: TextInput,
+    pub import_preview: Option<ImportPreview>,
+    pub import_preview_scroll: usize,
+
+    pub backup_location: TextInput,
+    pub backup_password: TextInput,
+    /// Backup password typed again
+    pub backup_password_confirm: TextInput,
+    pub backup_error: Option<String>,
+
+    pub plain_export_location: TextInput,
+    /// Passphrase of the key, typed again before secrets are written out
+    pub plain_export_passphrase: TextInput,
+    /// What is about to be exported, like `12 entries of namespace work`
+    pub plain_export_scope: String,
 }
 
 impl Default for State {
@@ -41,14 +168,274 @@ impl Default for State {
             active_page: ActivePage::PasswordsList,
             passwords_list: vec![],
             active_password_record: 0,
-            passwords_list_search_term: None,
+            passwords_list_jump: None,
+            recently_used: vec![],
+            revealed_entry: None,
+            passwords_list_search_term: TextInput::default(),
             passwords_list_search: vec![],
+            passwords_list_search_highlights: vec![],
             active_password_record_search: 0,
-            password_input: None,
-            password_name_input: None,
-            export_pgp_secret_master_password: None,
-            export_pgp_secret_location: None,
+            password_input: TextInput::default(),
+            entry_kind: EntryKind::Password,
+            secret_revealed: false,
+            entry_username_input: TextInput::default(),
+            entry_url_input: TextInput::default(),
+            entry_tags_input: TextInput::default(),
+            entry_otp_input: TextInput::default(),
+            entry_notes_input: TextInput::multiline(String::new()),
+            entry_details_field: DetailsField::Username,
+            otp_code: None,
+            password_name_input: TextInput::default(),
+            export_pgp_secret_master_password: TextInput::default(),
+            export_pgp_secret_location: TextInput::default(),
             export_pgp_secret_location_error: false,
+            namespaces: vec![],
+            active_namespace: String::new(),
+            default_namespace: String::new(),
+            active_namespace_record: 0,
+            namespace_name_input: TextInput::default(),
+            namespace_passphrase_input: TextInput::default(),
+            namespace_passphrase_confirm: TextInput::default(),
+            namespace_error: None,
+            error: None,
+            notifications: VecDeque::new(),
+            message_log: vec![],
+            message_log_scroll: 0,
+            audit_report: None,
+            audit_scroll: 0,
+            import_location: TextInput::default(),
+            import_preview: None,
+            import_preview_scroll: 0,
+            backup_location: TextInput::default(),
+            backup_password: TextInput::default(),
+            backup_password_confirm: TextInput::default(),
+            backup_error: None,
+            plain_export_location: TextInput::default(),
+            plain_export_passphrase: TextInput::default(),
+            plain_export_scope: String::new(),
+        }
+    }
+}
+
+/// Rows moved by PageUp and PageDown.
+pub const PAGE_SIZE: usize = 10;
+
+/// Selection in a list of `len` items after a navigation key, None if the key
+/// does not navigate. Always 0 for an empty list.
+pub fn navigate(selected: usize, len: usize, input: KeyCode) -> Option<usize> {
+    let last = len.saturating_sub(1);
+    let next = match input {
+        KeyCode::Down | KeyCode::Char('j') => selected.saturating_add(1),
+        KeyCode::Up | KeyCode::Char('k') => selected.saturating_sub(1),
+        KeyCode::PageDown => selected.saturating_add(PAGE_SIZE),
+        KeyCode::PageUp => selected.saturating_sub(PAGE_SIZE),
+        KeyCode::Home | KeyCode::Char('g') => 0,
+        KeyCode::End | KeyCode::Char('G') => last,
+        _ => return None,
+    };
+    Some(next.min(last))
+}
+
+/// Index of the first name starting with `prefix`, ignoring case.
+pub fn find_by_prefix(passwords: &[Password], prefix: &str) -> Option<usize> {
+    let prefix = prefix.to_lowercase();
+    passwords
+        .iter()
+        .position(|p| p.name.to_lowercase().starts_with(&prefix))
+}
+
+impl State {
+    /// Whether the focused input holds a secret that is masked by default.
+    pub fn secret_input_focused(&self) -> bool {
+        match self.active_page {
+            ActivePage::CreateNewPasswordBody | ActivePage::EditPasswordBody => {
+                self.entry_kind == EntryKind::Password
+            }
+            ActivePage::CreateNewPasswordDetails | ActivePage::EditPasswordDetails => {
+                self.entry_details_field == DetailsField::Otp
+            }
+            ActivePage::ExportPgpMasterPassword
+            | ActivePage::BackupPassword
+            | ActivePage::BackupPasswordConfirm
+            | ActivePage::PlainExportPassphrase
+            | ActivePage::CreateNamespacePassphrase
+            | ActivePage::CreateNamespacePassphraseConfirm
+            | ActivePage::UnlockNamespace => true,
+            _ => false,
+        }
+    }
+
+    /// Metadata typed into the entry details fields.
+    pub fn entry_metadata_inputs(&self) -> EntryMetadata {
+        let optional = |input: &TextInput| {
+            let text = input.text().trim();
+            (!text.is_empty()).then(|| text.to_string())
+        };
+        EntryMetadata {
+            username: optional(&self.entry_username_input),
+            url: optional(&self.entry_url_input),
+            tags: EntryMetadata::parse_tags(self.entry_tags_input.text()),
         }
     }
+
+    /// One-time password secret typed into the entry form.
+    pub fn entry_otp(&self) -> Option<String> {
+        let otp = self.entry_otp_input.text().trim();
+        (!otp.is_empty()).then(|| otp.to_string())
+    }
+
+    /// Notes typed into the entry form.
+    pub fn entry_notes(&self) -> Option<SecretString> {
+        let notes = self.entry_notes_input.text().trim_end();
+        (!notes.is_empty()).then(|| SecretString::new(notes.to_string()))
+    }
+
+    /// Fills the entry form with an existing entry, or clears it for a new one.
+    pub fn fill_entry_form(&mut self, name: &str, entry: &Entry) {
+        self.password_name_input = TextInput::new(name.to_string());
+        self.entry_kind = entry.kind;
+        self.password_input = match entry.kind {
+            EntryKind::Password => TextInput::new(entry.body.expose().to_string()),
+            EntryKind::Note => TextInput::multiline(entry.body.expose().to_string()),
+        };
+        let metadata = &entry.metadata;
+        self.entry_username_input = TextInput::new(metadata.username.clone().unwrap_or_default());
+        self.entry_url_input = TextInput::new(metadata.url.clone().unwrap_or_default());
+        self.entry_tags_input = TextInput::new(metadata.tags.join(", "));
+        self.entry_otp_input = TextInput::new(entry.otp.clone().unwrap_or_default());
+        self.entry_notes_input = TextInput::multiline(
+            entry
+                .notes
+                .as_ref()
+                .map(|notes| notes.expose().to_string())
+                .unwrap_or_default(),
+        );
+        self.entry_details_field = DetailsField::Username;
+    }
+
+    /// Moves `name` to the front of the recently used entries.
+    pub fn mark_used(&mut self, name: &str) {
+        self.recently_used.retain(|used| used != name);
+        self.recently_used.insert(0, name.to_string());
+        self.recently_used.truncate(RECENT_LIMIT);
+    }
+
+    /// Revealed entry if it belongs to the selected password.
+    pub fn selected_revealed_entry(&self) -> Option<&Entry> {
+        let selected = self.passwords_list.get(self.active_password_record)?;
+        self.revealed_entry
+            .as_ref()
+            .filter(|(name, _)| *name == selected.name)
+            .map(|(_, entry)| entry)
+    }
+
+    /// Queues a message for the status bar and records it in the session log.
+    pub fn notify(&mut self, level: NotificationLevel, message: String, ticks: u32) {
+        let notification = Notification::new(level, message, ticks);
+        self.message_log.push(notification.clone());
+        self.notifications.push_back(notification);
+    }
+
+    /// Records a message in the session log without showing it in the status bar.
+    pub fn log(&mut self, level: NotificationLevel, message: String) {
+        self.message_log.push(Notification::new(level, message, 0));
+    }
+
+    /// Counts down the visible notification and drops it once it expired.
+    pub fn tick_notifications(&mut self) {
+        if let Some(current) = self.notifications.front_mut() {
+            current.ticks_left = current.ticks_left.saturating_sub(1);
+            if current.ticks_left == 0 {
+                self.notifications.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_by_prefix, navigate, State};
+    use crate::{
+        events::KeyCode,
+        notification::NotificationLevel,
+        password::{Entry, EntryKind, EntryMetadata, Password},
+    };
+
+    #[test]
+    fn notifications_expire_in_order() {
+        let mut state = State::default();
+        state.notify(NotificationLevel::Success, "first".to_string(), 2);
+        state.notify(NotificationLevel::Warning, "second".to_string(), 1);
+        state.log(NotificationLevel::Error, "logged".to_string());
+
+        state.tick_notifications();
+        assert_eq!(state.notifications.front().unwrap().message, "first");
+        state.tick_notifications();
+        assert_eq!(state.notifications.front().unwrap().message, "second");
+        state.tick_notifications();
+        assert!(state.notifications.is_empty());
+        assert_eq!(state.message_log.len(), 3);
+    }
+
+    #[test]
+    fn revealed_entry_follows_selection() {
+        let mut state = State {
+            passwords_list: ["github", "mail"]
+                .iter()
+                .map(|name| Password {
+                    name: name.to_string(),
+                    ..Password::default()
+                })
+                .collect(),
+            ..State::default()
+        };
+        let entry = || Entry::new(EntryKind::Password, "hunter2".to_string());
+        state.revealed_entry = Some(("github".to_string(), entry()));
+        assert_eq!(state.selected_revealed_entry(), Some(&entry()));
+        state.active_password_record = 1;
+        assert_eq!(state.selected_revealed_entry(), None);
+    }
+
+    #[test]
+    fn navigates_lists_safely() {
+        assert_eq!(navigate(0, 0, KeyCode::Down), Some(0));
+        assert_eq!(navigate(0, 0, KeyCode::End), Some(0));
+        assert_eq!(navigate(3, 5, KeyCode::PageDown), Some(4));
+        assert_eq!(navigate(15, 30, KeyCode::PageUp), Some(5));
+        assert_eq!(navigate(15, 30, KeyCode::Char('G')), Some(29));
+        assert_eq!(navigate(15, 30, KeyCode::Char('g')), Some(0));
+        assert_eq!(navigate(1, 30, KeyCode::Char('x')), None);
+    }
+
+    #[test]
+    fn finds_names_by_prefix() {
+        let passwords: Vec<Password> = ["aws", "GitHub", "gitlab"]
+            .iter()
+            .map(|name| Password {
+                name: name.to_string(),
+                ..Password::default()
+            })
+            .collect();
+        assert_eq!(find_by_prefix(&passwords, "git"), Some(1));
+        assert_eq!(find_by_prefix(&passwords, "gitl"), Some(2));
+        assert_eq!(find_by_prefix(&passwords, "z"), None);
+    }
+
+    #[test]
+    fn entry_form_keeps_metadata() {
+        let mut entry = Entry::new(EntryKind::Password, "hunter2".to_string());
+        entry.metadata = EntryMetadata {
+            username: Some("me@mail.com".to_string()),
+            url: None,
+            tags: vec!["mail".to_string(), "work".to_string()],
+        };
+        let mut state = State::default();
+        state.fill_entry_form("mail", &entry);
+        assert_eq!(state.entry_tags_input.text(), "mail, work");
+        assert_eq!(state.entry_metadata_inputs(), entry.metadata);
+
+        state.fill_entry_form("", &Entry::new(EntryKind::Note, String::new()));
+        assert!(state.password_input.is_multiline());
+        assert_eq!(state.entry_metadata_inputs(), EntryMetadata::default());
+    }
 }
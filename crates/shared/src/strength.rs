@@ -0,0 +1,161 @@
+/// Passwords people pick first, checked after undoing common substitutions.
+#[rustfmt::skip]
+const COMMON_PASSWORDS: [&str; 40] = [
+    "123456", "password", "12345678", "qwerty", "123456789", "12345", "1234", "111111",
+    "1234567", "dragon", "123123", "baseball", "abc123", "football", "monkey", "letmein",
+    "696969", "shadow", "master", "666666", "qwertyuiop", "123321", "mustang", "1234567890",
+    "michael", "654321", "superman", "1qaz2wsx", "7777777", "121212", "000000", "qazwsx",
+    "123qwe", "killer", "trustno1", "jordan", "jennifer", "zxcvbnm", "asdfgh", "hunter",
+];
+
+/// Keyboard rows, walking along them adds little to a password.
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// Weight of a character that repeats or continues a sequence.
+const PREDICTABLE_WEIGHT: f64 = 0.25;
+
+/// Rough estimate of how hard a password is to guess.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Strength {
+    /// Estimated entropy in bits
+    pub bits: f64,
+    /// 0 (very weak) to 4 (very strong)
+    pub score: u8,
+}
+
+impl Strength {
+    pub fn label(&self) -> &'static str {
+        match self.score {
+            0 => "very weak",
+            1 => "weak",
+            2 => "fair",
+            3 => "strong",
+            _ => "very strong",
+        }
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.score < 2
+    }
+
+    /// Bar with the label, like `[###--] fair, about 45 bits`.
+    pub fn meter(&self) -> String {
+        let filled = self.score.min(4) as usize + 1;
+        format!(
+            "[{}{}] {}, about {:.0} bits",
+            "#".repeat(filled),
+            "-".repeat(5 - filled),
+            self.label(),
+            self.bits
+        )
+    }
+}
+
+fn pool_size(password: &str) -> f64 {
+    let has = |f: fn(&char) -> bool| password.chars().any(|c| f(&c));
+    let mut pool = 0;
+    if has(char::is_ascii_lowercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_uppercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_digit) {
+        pool += 10;
+    }
+    if has(|c| c.is_ascii() && !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+    if has(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    pool.max(1) as f64
+}
+
+fn follows(previous: char, current: char) -> bool {
+    let (previous, current) = (previous.to_ascii_lowercase(), current.to_ascii_lowercase());
+    let step = current as i64 - previous as i64;
+    if previous.is_ascii_alphanumeric() && (step == 1 || step == -1) {
+        return true;
+    }
+    KEYBOARD_ROWS.iter().any(|row| {
+        let position = |c| row.find(c);
+        matches!((position(previous), position(current)), (Some(p), Some(c)) if p.abs_diff(c) == 1)
+    })
+}
+
+/// The password with leetspeak undone.
+fn base_word(password: &str) -> String {
+    password
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '@' | '4' => 'a',
+            '3' => 'e',
+            '1' | '!' => 'i',
+            '0' => 'o',
+            '$' | '5' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect::<String>()
+}
+
+fn is_common(password: &str) -> bool {
+    let lowercase = password.to_lowercase();
+    let trimmed = lowercase.trim_end_matches(|c: char| !c.is_alphabetic());
+    COMMON_PASSWORDS
+        .iter()
+        .any(|common| *common == lowercase || *common == trimmed || *common == base_word(trimmed))
+}
+
+/// Estimates strength from the characters used, discounting repeats,
+/// sequences, keyboard walks and well known passwords.
+pub fn estimate(password: &str) -> Strength {
+    let mut effective_length = 0.0;
+    let mut previous: Option<char> = None;
+    for c in password.chars() {
+        effective_length += match previous {
+            Some(p) if p == c || follows(p, c) => PREDICTABLE_WEIGHT,
+            _ => 1.0,
+        };
+        previous = Some(c);
+    }
+    let mut bits = effective_length * pool_size(password).log2();
+    if is_common(password) {
+        bits = bits.min(10.0);
+    }
+    let score = match bits {
+        b if b < 28.0 => 0,
+        b if b < 36.0 => 1,
+        b if b < 60.0 => 2,
+        b if b < 80.0 => 3,
+        _ => 4,
+    };
+    Strength { bits, score }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::estimate;
+
+    #[test]
+    fn ranks_passwords() {
+        for weak in [
+            "",
+            "password",
+            "P@ssw0rd!",
+            "qwerty123",
+            "aaaaaaaaaaaa",
+            "abcdefgh",
+        ] {
+            assert!(estimate(weak).is_weak(), "{} should be weak", weak);
+        }
+        assert!(!estimate("correct horse battery staple").is_weak());
+        assert_eq!(estimate("k7#Qm!2vXz9$Lp4w").score, 4);
+        assert!(estimate("Tr0ub4dor&3").bits > estimate("troubador").bits);
+        assert!(estimate("password")
+            .meter()
+            .starts_with("[#----] very weak"));
+    }
+}
@@ -48,12 +48,14 @@ impl EventLoop {
                    tr_event.send(Event::Tick).unwrap_or_else(|_| warn!("Unable to send Tick event"));
                },
                _ = rec_terminate.recv() => {
-                   tr_event.send(Event::Terminate).expect("Expected to send");
+                   tr_event.send(Event::Terminate).unwrap_or_else(|_| warn!("Unable to send Terminate event"));
                    break;
                },
                _ = tr_event.closed() => break,
                maybe_event = event => {
-                   if let Some(Ok(crossterm::event::Event::Key(key))) = maybe_event {
+                   if let Some(Ok(crossterm::event::Event::Paste(text))) = maybe_event {
+                       tr_event.send(Event::Paste(text)).unwrap_or_else(|_| warn!("Unable to send Paste event"));
+                   } else if let Some(Ok(crossterm::event::Event::Key(key))) = maybe_event {
                        let key = match key.code {
                            Backspace => {
                                match key.modifiers {
@@ -70,12 +72,26 @@ impl EventLoop {
                                }
                            },
                            Enter => KeyCode::Char('\n'),
-                           Left => KeyCode::Left,
-                           Right => KeyCode::Right,
+                           Left => match key.modifiers {
+                               KeyModifiers::CONTROL => KeyCode::CtrlLeft,
+                               KeyModifiers::SHIFT => KeyCode::ShiftLeft,
+                               _ => KeyCode::Left,
+                           },
+                           Right => match key.modifiers {
+                               KeyModifiers::CONTROL => KeyCode::CtrlRight,
+                               KeyModifiers::SHIFT => KeyCode::ShiftRight,
+                               _ => KeyCode::Right,
+                           },
                            Up => KeyCode::Up,
                            Down => KeyCode::Down,
-                           Home => KeyCode::Home,
-                           End => KeyCode::End,
+                           Home => match key.modifiers {
+                               KeyModifiers::SHIFT => KeyCode::ShiftHome,
+                               _ => KeyCode::Home,
+                           },
+                           End => match key.modifiers {
+                               KeyModifiers::SHIFT => KeyCode::ShiftEnd,
+                               _ => KeyCode::End,
+                           },
                            PageUp => KeyCode::PageUp,
                            PageDown => KeyCode::PageDown,
                            Tab => KeyCode::Tab,
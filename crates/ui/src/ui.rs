@@ -1,27 +1,36 @@
-use std::path::{Path, PathBuf};
+use std::panic;
 
-use anyhow::Result;
+use crossterm::cursor::Show;
+use crossterm::event::{DisableBracketedPaste, EnableBracketedPaste};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
-use crossterm::ExecutableCommand;
-use shared::state::ActivePage;
-use shared::{password::Password, state::State};
-
-use tui::style::Style;
-use tui::widgets::{Block, Borders, Paragraph};
+use crossterm::{execute, ExecutableCommand};
+use shared::error::Result;
+use shared::input::TextInput;
+use shared::state::{ActivePage, DetailsField};
+use shared::strength::estimate;
+use shared::{password::EntryKind, state::State};
+
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Block, Borders, Paragraph, TableState};
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Layout, Rect},
     Frame, Terminal,
 };
 
-use crate::widgets::PasswordsList;
-use crate::widgets::{HelpTab, LabeledInput};
+use crate::widgets::{
+    AuditReportView, EntryDetails, ErrorPopup, HelpTab, ImportPreviewView, LabeledInput,
+    MessageLog, StatusBar,
+};
+use crate::widgets::{NamespacesList, NoteEditor, PasswordsList};
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum ActivePasswordSection {
     Name,
     Body,
+    Details,
 }
 
 enum ActiveSearchPasswordListSection {
@@ -32,6 +41,9 @@ enum ActiveSearchPasswordListSection {
 pub struct UI {
     enabled: bool,
     terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
+    /// Table states outlive a draw so list viewports keep their scroll position
+    passwords_table: TableState,
+    search_table: TableState,
 }
 
 impl UI {
@@ -39,6 +51,8 @@ impl UI {
         Self {
             terminal: UI::init_terminal().unwrap(),
             enabled: false,
+            passwords_table: TableState::default(),
+            search_table: TableState::default(),
         }
     }
 
@@ -52,8 +66,25 @@ impl UI {
     }
 
     pub fn setup_terminal(&mut self) -> Result<()> {
-        enable_raw_mode().unwrap();
-        self.terminal.backend_mut().execute(EnterAlternateScreen)?;
+        // Leave raw mode and the alternate screen before the panic message is printed
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let _ = disable_raw_mode();
+            let _ = execute!(
+                std::io::stdout(),
+                DisableBracketedPaste,
+                LeaveAlternateScreen,
+                Show
+            );
+            default_hook(info);
+        }));
+
+        enable_raw_mode()?;
+        execute!(
+            self.terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableBracketedPaste
+        )?;
         self.enabled = true;
         Ok(())
     }
@@ -62,12 +93,14 @@ impl UI {
         if !self.enabled {
             return;
         }
-        disable_raw_mode().unwrap();
-        self.terminal.show_cursor().unwrap();
+        if let Err(e) = self.terminal.show_cursor() {
+            eprintln!("show_cursor failed:\n{e}");
+        }
         let leave_screen = self
             .terminal
             .backend_mut()
-            .execute(LeaveAlternateScreen)
+            .execute(DisableBracketedPaste)
+            .and_then(|backend| backend.execute(LeaveAlternateScreen))
             .map(|_f| ());
 
         if let Err(e) = leave_screen {
@@ -82,63 +115,38 @@ impl UI {
         self.enabled = false;
     }
 
-    pub async fn draw(&mut self, state: State) -> Result<(), anyhow::Error> {
+    pub async fn draw(&mut self, state: &State) -> Result<()> {
         self.terminal.draw(|f| {
-            let size = f.size();
+            let screen = Self::get_status_bar_layout(f.size());
+            let size = screen[0];
+            f.render_widget(
+                StatusBar::new(state.notifications.front(), state.notifications.len()),
+                screen[1],
+            );
             match state.active_page {
                 ActivePage::PasswordsList => {
-                    Self::render_passwords_list(
-                        f,
-                        size,
-                        state.active_password_record,
-                        &state.passwords_list,
-                    );
-                }
-                ActivePage::CreateNewPasswordName => {
-                    Self::render_create_edit_password(
-                        f,
-                        size,
-                        state.password_name_input.unwrap_or_else(|| "".to_owned()),
-                        state.password_input.unwrap_or_else(|| "".to_owned()),
-                        ActivePasswordSection::Name,
-                    );
+                    Self::render_passwords_list(f, size, state, &mut self.passwords_table);
                 }
-                ActivePage::CreateNewPasswordBody => {
-                    Self::render_create_edit_password(
-                        f,
-                        size,
-                        state.password_name_input.unwrap_or_else(|| "".to_owned()),
-                        state.password_input.unwrap_or_else(|| "".to_owned()),
-                        ActivePasswordSection::Body,
-                    );
+                ActivePage::CreateNewPasswordName | ActivePage::EditPasswordName => {
+                    Self::render_create_edit_password(f, size, state, ActivePasswordSection::Name);
                 }
-                ActivePage::EditPasswordName => {
-                    Self::render_create_edit_password(
-                        f,
-                        size,
-                        state.password_name_input.unwrap_or_else(|| "".to_owned()),
-                        state.password_input.unwrap_or_else(|| "".to_owned()),
-                        ActivePasswordSection::Name,
-                    );
+                ActivePage::CreateNewPasswordBody | ActivePage::EditPasswordBody => {
+                    Self::render_create_edit_password(f, size, state, ActivePasswordSection::Body);
                 }
-                ActivePage::EditPasswordBody => {
+                ActivePage::CreateNewPasswordDetails | ActivePage::EditPasswordDetails => {
                     Self::render_create_edit_password(
                         f,
                         size,
-                        state.password_name_input.unwrap_or_else(|| "".to_owned()),
-                        state.password_input.unwrap_or_else(|| "".to_owned()),
-                        ActivePasswordSection::Body,
+                        state,
+                        ActivePasswordSection::Details,
                     );
                 }
                 ActivePage::SearchPasswordsListName => {
                     Self::render_passwords_list_search(
                         f,
                         size,
-                        state
-                            .passwords_list_search_term
-                            .unwrap_or_else(|| "".to_owned()),
-                        state.active_password_record_search,
-                        &state.passwords_list_search,
+                        state,
+                        &mut self.search_table,
                         ActiveSearchPasswordListSection::Name,
                     );
                 }
@@ -146,11 +154,8 @@ impl UI {
                     Self::render_passwords_list_search(
                         f,
                         size,
-                        state
-                            .passwords_list_search_term
-                            .unwrap_or_else(|| "".to_owned()),
-                        state.active_password_record_search,
-                        &state.passwords_list_search,
+                        state,
+                        &mut self.search_table,
                         ActiveSearchPasswordListSection::Body,
                     );
                 }
@@ -159,49 +164,261 @@ impl UI {
                     if state.export_pgp_secret_location_error {
                         title.push_str("(wrong path)");
                     }
-                    let loc = &state.export_pgp_secret_location.clone().unwrap();
-                    let path =Path::new(loc);
                     Self::render_centered_input(
                         f,
                         size,
                         title,
-                        state
-                            .export_pgp_secret_location.clone()
-                            .unwrap_or_else(|| "".to_owned()),
+                        &state.export_pgp_secret_location,
                         ActivePage::ExportPgpLocation,
+                        false,
                         // Some(path.canonicalize().unwrap_or_default().to_str().unwrap_or("false").to_string())
                         Some("This is the location where the encrypted file will be stored.".to_string()),
                     );
                 }
+                ActivePage::NamespacesList => {
+                    Self::render_namespaces_list(f, size, state);
+                }
+                ActivePage::Audit => {
+                    let root_layout = Self::get_root_layout(size);
+                    if let Some(report) = &state.audit_report {
+                        f.render_widget(
+                            AuditReportView::new(report, state.audit_scroll),
+                            root_layout[0],
+                        );
+                    }
+                    f.render_widget(HelpTab::new(ActivePage::Audit), root_layout[1]);
+                }
+                ActivePage::ImportLocation => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        "Export file to import".to_string(),
+                        &state.import_location,
+                        ActivePage::ImportLocation,
+                        false,
+                        Some("Unencrypted Bitwarden JSON, 1Password 1PUX or CSV, LastPass, Chrome or Firefox CSV. Nothing is saved before the preview is confirmed.".to_string()),
+                    );
+                }
+                ActivePage::ImportPreview => {
+                    let root_layout = Self::get_root_layout(size);
+                    if let Some(preview) = &state.import_preview {
+                        f.render_widget(
+                            ImportPreviewView::new(preview, state.import_preview_scroll),
+                            root_layout[0],
+                        );
+                    }
+                    f.render_widget(HelpTab::new(ActivePage::ImportPreview), root_layout[1]);
+                }
+                ActivePage::MessageLog => {
+                    let root_layout = Self::get_root_layout(size);
+                    f.render_widget(
+                        MessageLog::new(&state.message_log, state.message_log_scroll),
+                        root_layout[0],
+                    );
+                    f.render_widget(HelpTab::new(ActivePage::MessageLog), root_layout[1]);
+                }
+                ActivePage::CreateNamespaceName => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("Namespace name", &state.namespace_error),
+                        &state.namespace_name_input,
+                        ActivePage::CreateNamespaceName,
+                        false,
+                        Some("A namespace is a separate vault with its own key and passwords.".to_string()),
+                    );
+                }
+                ActivePage::CreateNamespacePassphrase => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("Passphrase", &state.namespace_error),
+                        &state.namespace_passphrase_input,
+                        ActivePage::CreateNamespacePassphrase,
+                        !state.secret_revealed,
+                        Some(format!(
+                            "A new key for namespace {} will be generated and protected with this passphrase. Strength: {}",
+                            state.namespace_name_input.text(),
+                            estimate(state.namespace_passphrase_input.text()).meter()
+                        )),
+                    );
+                }
+                ActivePage::CreateNamespacePassphraseConfirm => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("Passphrase again", &state.namespace_error),
+                        &state.namespace_passphrase_confirm,
+                        ActivePage::CreateNamespacePassphraseConfirm,
+                        !state.secret_revealed,
+                        Some("Type the passphrase once more, there is no way to recover a namespace without it.".to_string()),
+                    );
+                }
+                ActivePage::RenameNamespace => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("New namespace name", &state.namespace_error),
+                        &state.namespace_name_input,
+                        ActivePage::RenameNamespace,
+                        false,
+                        None,
+                    );
+                }
+                ActivePage::DeleteNamespace => {
+                    let selected = state
+                        .namespaces
+                        .get(state.active_namespace_record)
+                        .cloned()
+                        .unwrap_or_default();
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("Namespace name", &state.namespace_error),
+                        &state.namespace_name_input,
+                        ActivePage::DeleteNamespace,
+                        false,
+                        Some(format!(
+                            "Namespace {} will be deleted together with its key and all passwords. Type its name to confirm.",
+                            selected
+                        )),
+                    );
+                }
+                ActivePage::UnlockNamespace => {
+                    let selected = state
+                        .namespaces
+                        .get(state.active_namespace_record)
+                        .cloned()
+                        .unwrap_or_default();
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("Passphrase", &state.namespace_error),
+                        &state.namespace_passphrase_input,
+                        ActivePage::UnlockNamespace,
+                        !state.secret_revealed,
+                        Some(format!("Enter the passphrase of namespace {}.", selected)),
+                    );
+                }
                 ActivePage::ExportPgpMasterPassword => {
                     Self::render_centered_input(
                         f,
                         size,
                         "Master password".to_string(),
-                        state
-                            .export_pgp_secret_master_password
-                            .unwrap_or_else(|| "".to_owned()),
+                        &state.export_pgp_secret_master_password,
                         ActivePage::ExportPgpMasterPassword,
+                        !state.secret_revealed,
                         Some("You pgp key will be encrypted with your master password using AES excryption, make sure to use a strong password.".to_string()),
                     );
                 }
+                ActivePage::PlainExportLocation => {
+                    Self::render_plain_export_input(
+                        f,
+                        size,
+                        "Export file, .json for JSON, CSV otherwise".to_string(),
+                        &state.plain_export_location,
+                        ActivePage::PlainExportLocation,
+                        false,
+                        &state.plain_export_scope,
+                    );
+                }
+                ActivePage::PlainExportPassphrase => {
+                    Self::render_plain_export_input(
+                        f,
+                        size,
+                        "Passphrase of the key, again".to_string(),
+                        &state.plain_export_passphrase,
+                        ActivePage::PlainExportPassphrase,
+                        !state.secret_revealed,
+                        &state.plain_export_scope,
+                    );
+                }
+                ActivePage::BackupLocation => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        "Backup file".to_string(),
+                        &state.backup_location,
+                        ActivePage::BackupLocation,
+                        false,
+                        Some("Key, configuration and all entries of the namespace are written to this single file.".to_string()),
+                    );
+                }
+                ActivePage::BackupPassword => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("Backup password", &state.backup_error),
+                        &state.backup_password,
+                        ActivePage::BackupPassword,
+                        !state.secret_revealed,
+                        Some(format!(
+                            "The backup is encrypted and authenticated with this password, it is needed to restore it with `passm backup restore`. Strength: {}",
+                            estimate(state.backup_password.text()).meter()
+                        )),
+                    );
+                }
+                ActivePage::BackupPasswordConfirm => {
+                    Self::render_centered_input(
+                        f,
+                        size,
+                        Self::with_error("Backup password again", &state.backup_error),
+                        &state.backup_password_confirm,
+                        ActivePage::BackupPasswordConfirm,
+                        !state.secret_revealed,
+                        Some("The file is read back and checked after writing.".to_string()),
+                    );
+                }
+            }
+            if let Some(error) = &state.error {
+                f.render_widget(ErrorPopup::new(error), size);
             }
         })?;
         Ok(())
     }
 
+    fn with_error(label: &str, error: &Option<String>) -> String {
+        match error {
+            Some(error) => format!("{} ({})", label, error),
+            None => label.to_string(),
+        }
+    }
+
+    fn render_namespaces_list<B: Backend>(f: &mut Frame<B>, size: Rect, state: &State) {
+        let mut root_layout = Self::get_root_layout(size);
+
+        let body = root_layout.get_mut(0).unwrap();
+        f.render_widget(
+            NamespacesList::new(
+                &state.namespaces,
+                &state.active_namespace,
+                &state.default_namespace,
+                state.active_namespace_record,
+                state.namespace_error.as_ref(),
+            ),
+            *body,
+        );
+
+        let help_tab = root_layout.get_mut(1).unwrap();
+        f.render_widget(HelpTab::new(ActivePage::NamespacesList), *help_tab);
+    }
+
     fn render_centered_input<B: Backend>(
         f: &mut Frame<B>,
         size: Rect,
         label: String,
-        text: String,
+        input: &TextInput,
         page: ActivePage,
+        masked: bool,
         note: Option<String>,
     ) {
         let mut root_layout = Self::get_input_with_note_layout(size);
 
         let location_frame = root_layout.get_mut(0).unwrap();
-        f.render_widget(LabeledInput::new(text, label, None), *location_frame);
+        f.render_widget(
+            LabeledInput::new(input, label, None).masked(masked),
+            *location_frame,
+        );
 
         if let Some(note) = note {
             let note_frame = root_layout.get_mut(1).unwrap();
@@ -223,62 +440,138 @@ impl UI {
         f.render_widget(HelpTab::new(page), *help_tab);
     }
 
+    /// Input of the plaintext export pages, with a warning that can not be missed.
+    fn render_plain_export_input<B: Backend>(
+        f: &mut Frame<B>,
+        size: Rect,
+        label: String,
+        input: &TextInput,
+        page: ActivePage,
+        masked: bool,
+        scope: &str,
+    ) {
+        let root_layout = Self::get_input_with_note_layout(size);
+        f.render_widget(
+            LabeledInput::new(input, label, None).masked(masked),
+            root_layout[0],
+        );
+        let warning = format!(
+            "{} will be written UNENCRYPTED. Anyone who can read the file gets every password in it. \
+            The file is created readable by you only; hand it over and delete it right away.",
+            scope
+        );
+        let red = Style::default().fg(Color::Red);
+        f.render_widget(
+            Paragraph::new(warning)
+                .style(red.add_modifier(Modifier::BOLD))
+                .block(
+                    Block::default()
+                        .title("WARNING")
+                        .border_type(tui::widgets::BorderType::Double)
+                        .border_style(red)
+                        .borders(Borders::ALL),
+                )
+                .wrap(tui::widgets::Wrap { trim: true }),
+            root_layout[2],
+        );
+        f.render_widget(HelpTab::new(page), root_layout[3]);
+    }
+
     fn render_create_edit_password<B: Backend>(
         f: &mut Frame<B>,
         size: Rect,
-        password_name_input: String,
-        password_input: String,
+        state: &State,
         active_section: ActivePasswordSection,
     ) {
-        let mut root_layout = Self::get_passwords_layout(size);
+        let root_layout = Self::get_passwords_layout(size);
+        let inactive = Some(Style::default().fg(tui::style::Color::DarkGray));
+        let style_for = |section: ActivePasswordSection| {
+            if section == active_section {
+                None
+            } else {
+                inactive
+            }
+        };
 
-        let pass_name_frame = root_layout.get_mut(0).unwrap();
         f.render_widget(
             LabeledInput::new(
-                password_name_input,
-                "Password Name".to_owned(),
-                match active_section {
-                    ActivePasswordSection::Name => None,
-                    ActivePasswordSection::Body => {
-                        Some(Style::default().fg(tui::style::Color::DarkGray))
-                    }
+                &state.password_name_input,
+                match state.entry_kind {
+                    EntryKind::Password => "Password Name".to_owned(),
+                    EntryKind::Note => "Note Name".to_owned(),
                 },
+                style_for(ActivePasswordSection::Name),
             ),
-            *pass_name_frame,
+            root_layout[0],
         );
 
-        let pass_input_frame = root_layout.get_mut(1).unwrap();
-        f.render_widget(
-            LabeledInput::new(
-                password_input,
-                "Password".to_owned(),
-                match active_section {
-                    ActivePasswordSection::Body => None,
-                    ActivePasswordSection::Name => {
-                        Some(Style::default().fg(tui::style::Color::DarkGray))
-                    }
-                },
+        let body_style = style_for(ActivePasswordSection::Body);
+        let body_layout = Self::get_entry_body_layout(root_layout[1], state.entry_kind);
+        match state.entry_kind {
+            EntryKind::Password => f.render_widget(
+                LabeledInput::new(&state.password_input, "Password".to_owned(), body_style)
+                    .masked(!state.secret_revealed),
+                body_layout[0],
+            ),
+            EntryKind::Note => f.render_widget(
+                NoteEditor::new(&state.password_input, "Note".to_owned(), body_style),
+                body_layout[0],
             ),
-            *pass_input_frame,
+        }
+        let notes_style = match state.entry_details_field {
+            DetailsField::Notes => style_for(ActivePasswordSection::Details),
+            _ => inactive,
+        };
+        f.render_widget(
+            NoteEditor::new(&state.entry_notes_input, "Notes".to_owned(), notes_style),
+            body_layout[1],
         );
 
-        // Render help tab
-        let help_tab = root_layout.get_mut(2).unwrap();
+        let details_layout = Self::get_entry_details_layout(root_layout[2]);
+        let details = [
+            (
+                &state.entry_username_input,
+                "Username",
+                DetailsField::Username,
+            ),
+            (&state.entry_url_input, "URL", DetailsField::Url),
+            (&state.entry_tags_input, "Tags", DetailsField::Tags),
+            (
+                &state.entry_otp_input,
+                "One-time password",
+                DetailsField::Otp,
+            ),
+        ];
+        for ((input, label, field), frame) in details.into_iter().zip(details_layout) {
+            let focused = state.entry_details_field == field;
+            let style = if focused {
+                style_for(ActivePasswordSection::Details)
+            } else {
+                inactive
+            };
+            // The otpauth URI holds the secret, it is revealed like a password
+            let masked = field == DetailsField::Otp && !(focused && state.secret_revealed);
+            f.render_widget(
+                LabeledInput::new(input, label.to_owned(), style).masked(masked),
+                frame,
+            );
+        }
+
         f.render_widget(
             HelpTab::new(match active_section {
                 ActivePasswordSection::Name => ActivePage::CreateNewPasswordName,
                 ActivePasswordSection::Body => ActivePage::CreateNewPasswordBody,
+                ActivePasswordSection::Details => ActivePage::CreateNewPasswordDetails,
             }),
-            *help_tab,
+            root_layout[3],
         );
     }
 
     fn render_passwords_list_search<B: Backend>(
         f: &mut Frame<B>,
         size: Rect,
-        search_term: String,
-        selected: usize,
-        passwords_list: &Vec<Password>,
+        state: &State,
+        table_state: &mut TableState,
         active_section: ActiveSearchPasswordListSection,
     ) {
         let mut root_layout = Self::get_password_list_search_layout(size);
@@ -287,7 +580,7 @@ impl UI {
         let search_frame = root_layout.get_mut(0).unwrap();
         f.render_widget(
             LabeledInput::new(
-                search_term,
+                &state.passwords_list_search_term,
                 "Search".to_owned(),
                 match active_section {
                     ActiveSearchPasswordListSection::Name => None,
@@ -301,18 +594,20 @@ impl UI {
 
         // Rendering active tab
         let body = root_layout.get_mut(1).unwrap();
-        f.render_widget(
+        f.render_stateful_widget(
             PasswordsList::new(
-                passwords_list,
-                selected,
+                &state.passwords_list_search,
+                state.active_password_record_search,
                 match active_section {
                     ActiveSearchPasswordListSection::Body => None,
                     ActiveSearchPasswordListSection::Name => {
                         Some(Style::default().fg(tui::style::Color::DarkGray))
                     }
                 },
-            ),
+            )
+            .highlights(&state.passwords_list_search_highlights),
             *body,
+            table_state,
         );
 
         // Render help tab
@@ -323,14 +618,28 @@ impl UI {
     fn render_passwords_list<B: Backend>(
         f: &mut Frame<B>,
         size: Rect,
-        selected: usize,
-        passwords_list: &Vec<Password>,
+        state: &State,
+        table_state: &mut TableState,
     ) {
         let mut root_layout = Self::get_root_layout(size);
+        let body = Self::get_list_with_details_layout(root_layout[0]);
 
         // Rendering active tab
-        let body = root_layout.get_mut(0).unwrap();
-        f.render_widget(PasswordsList::new(passwords_list, selected, None), *body);
+        f.render_stateful_widget(
+            PasswordsList::new(&state.passwords_list, state.active_password_record, None)
+                .namespace(&state.active_namespace)
+                .jump(state.passwords_list_jump.as_deref()),
+            body[0],
+            table_state,
+        );
+        f.render_widget(
+            EntryDetails::new(
+                state.passwords_list.get(state.active_password_record),
+                state.selected_revealed_entry(),
+            )
+            .otp_code(state.otp_code.as_ref()),
+            body[1],
+        );
 
         // Render help tab
         let help_tab = root_layout.get_mut(1).unwrap();
@@ -362,6 +671,34 @@ impl UI {
                     Constraint::Length(3),
                     Constraint::Min(10),
                     Constraint::Length(3),
+                    Constraint::Length(3),
+                ]
+                .as_ref(),
+            )
+            .split(size)
+    }
+    /// Splits the body area of the entry form into the secret and its notes.
+    fn get_entry_body_layout(size: Rect, kind: EntryKind) -> Vec<Rect> {
+        let constraints = match kind {
+            EntryKind::Password => [Constraint::Length(3), Constraint::Min(3)],
+            EntryKind::Note => [Constraint::Percentage(70), Constraint::Percentage(30)],
+        };
+        Layout::default()
+            .direction(tui::layout::Direction::Vertical)
+            .margin(0)
+            .constraints(constraints.as_ref())
+            .split(size)
+    }
+    fn get_entry_details_layout(size: Rect) -> Vec<Rect> {
+        Layout::default()
+            .direction(tui::layout::Direction::Horizontal)
+            .margin(0)
+            .constraints(
+                [
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(25),
                 ]
                 .as_ref(),
             )
@@ -381,6 +718,20 @@ impl UI {
             )
             .split(size)
     }
+    fn get_list_with_details_layout(size: Rect) -> Vec<Rect> {
+        Layout::default()
+            .direction(tui::layout::Direction::Horizontal)
+            .margin(0)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(size)
+    }
+    fn get_status_bar_layout(size: Rect) -> Vec<Rect> {
+        Layout::default()
+            .direction(tui::layout::Direction::Vertical)
+            .margin(0)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(size)
+    }
     fn get_root_layout(size: Rect) -> Vec<Rect> {
         Layout::default()
             .direction(tui::layout::Direction::Vertical)
@@ -0,0 +1,58 @@
+use shared::audit::AuditReport;
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Paragraph, Widget},
+};
+
+use crate::components::get_bordered_block;
+
+pub struct AuditReportView<'b> {
+    report: &'b AuditReport,
+    scroll: usize,
+}
+
+impl<'b> AuditReportView<'b> {
+    pub fn new(report: &'b AuditReport, scroll: usize) -> Self {
+        Self { report, scroll }
+    }
+}
+
+impl<'b> Widget for AuditReportView<'b> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let score_color = match self.report.score() {
+            90.. => Color::Green,
+            50..=89 => Color::Yellow,
+            _ => Color::Red,
+        };
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                self.report.summary(),
+                Style::default()
+                    .fg(score_color)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Spans::default(),
+        ];
+        let sections = self.report.sections();
+        if sections.is_empty() {
+            lines.push(Spans::from("No findings"));
+        }
+        for (title, entries) in sections {
+            lines.push(Spans::from(Span::styled(
+                title,
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+            lines.extend(
+                entries
+                    .into_iter()
+                    .map(|line| Spans::from(format!("  {}", line))),
+            );
+            lines.push(Spans::default());
+        }
+        Paragraph::new(lines)
+            .block(get_bordered_block().title("Vault audit"))
+            .scroll((self.scroll as u16, 0))
+            .render(area, buf);
+    }
+}
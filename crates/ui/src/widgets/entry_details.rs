@@ -0,0 +1,126 @@
+use shared::password::{Entry, EntryKind, OtpCode, Password};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Paragraph, Widget, Wrap},
+};
+
+use crate::components::get_bordered_block;
+
+/// Read-only pane with the selected entry. The secret is only shown once the
+/// entry was decrypted on request.
+pub struct EntryDetails<'b> {
+    password: Option<&'b Password>,
+    revealed: Option<&'b Entry>,
+    otp_code: Option<&'b OtpCode>,
+}
+
+impl<'b> EntryDetails<'b> {
+    pub fn new(password: Option<&'b Password>, revealed: Option<&'b Entry>) -> Self {
+        Self {
+            password,
+            revealed,
+            otp_code: None,
+        }
+    }
+
+    /// Live one-time password of the revealed entry.
+    pub fn otp_code(mut self, otp_code: Option<&'b OtpCode>) -> Self {
+        self.otp_code = otp_code;
+        self
+    }
+
+    fn field(label: &'b str, value: String) -> Spans<'b> {
+        Spans::from(vec![
+            Span::styled(
+                format!("{:<10}", label),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(value),
+        ])
+    }
+}
+
+impl<'b> Widget for EntryDetails<'b> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let block = get_bordered_block().title("Details");
+        let password = match self.password {
+            Some(password) => password,
+            None => {
+                Paragraph::new("No entry selected")
+                    .block(block)
+                    .render(area, buf);
+                return;
+            }
+        };
+
+        let mut lines = vec![
+            Self::field("Name", password.name.clone()),
+            Self::field(
+                "Modified",
+                password
+                    .modified
+                    .map(|modified| modified.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+            ),
+            Self::field("Size", format!("{} bytes", password.size)),
+        ];
+        // Kind and metadata come from the index, the entry itself stays encrypted
+        if let Some(kind) = self.revealed.map(|entry| entry.kind).or(password.kind) {
+            let label = match kind {
+                EntryKind::Password => "Password",
+                EntryKind::Note => "Note",
+            };
+            lines.push(Self::field("Kind", label.to_string()));
+        }
+        let metadata = &password.metadata;
+        if let Some(username) = &metadata.username {
+            lines.push(Self::field("Username", username.clone()));
+        }
+        if let Some(url) = &metadata.url {
+            lines.push(Self::field("URL", url.clone()));
+        }
+        if !metadata.tags.is_empty() {
+            lines.push(Self::field("Tags", metadata.tags.join(", ")));
+        }
+        if let Some(count) = password.breached {
+            let mut spans = Self::field("Breached", String::new()).0;
+            spans.push(Span::styled(
+                format!("seen {} times, change this password", count),
+                Style::default().fg(Color::Red),
+            ));
+            lines.push(Spans::from(spans));
+        }
+        if let Some(otp_code) = self.otp_code {
+            let mut spans = Self::field("Code", otp_code.code.clone()).0;
+            if let Some(remaining) = otp_code.remaining {
+                let style = match remaining {
+                    0..=5 => Style::default().fg(Color::Red),
+                    _ => Style::default().fg(Color::DarkGray),
+                };
+                spans.push(Span::styled(format!("  {}s left", remaining), style));
+            }
+            lines.push(Spans::from(spans));
+        }
+        lines.push(Spans::default());
+        match self.revealed {
+            Some(entry) => {
+                // Borrowed, the decrypted text is not copied for every draw
+                lines.extend(entry.body.expose().lines().map(Spans::from));
+                if let Some(notes) = &entry.notes {
+                    lines.push(Spans::default());
+                    lines.extend(notes.expose().lines().map(Spans::from));
+                }
+            }
+            None => lines.push(Spans::from(Span::styled(
+                "Secret hidden, press v to decrypt and reveal",
+                Style::default().fg(Color::DarkGray),
+            ))),
+        }
+
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,58 @@
+use tui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::components::get_bordered_block;
+
+pub struct ErrorPopup<'b> {
+    message: &'b str,
+}
+
+impl<'b> ErrorPopup<'b> {
+    pub fn new(message: &'b str) -> Self {
+        Self { message }
+    }
+
+    fn centered_rect(area: Rect) -> Rect {
+        let vertical = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Percentage(35),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(35),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(60),
+                    Constraint::Percentage(20),
+                ]
+                .as_ref(),
+            )
+            .split(vertical[1])[1]
+    }
+}
+
+impl<'b> Widget for ErrorPopup<'b> {
+    fn render(self, area: Rect, buf: &mut tui::buffer::Buffer) {
+        let area = Self::centered_rect(area);
+        Clear.render(area, buf);
+        let text = format!("{}\n\nPress any key to continue", self.message);
+        let popup = Paragraph::new(text)
+            .block(
+                get_bordered_block()
+                    .title("Error")
+                    .border_style(Style::default().fg(Color::Red)),
+            )
+            .wrap(Wrap { trim: true });
+        popup.render(area, buf);
+    }
+}
@@ -17,18 +17,38 @@ impl Widget for HelpTab {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
         let message = match self.page {
             ActivePage::PasswordsList => {
-                "a: create new | e: edit entry | d: delete entry | /: search | q/Ctrl+c: quit | p: export secret key"
+                "a: create new | N: new note | e: edit entry | d: delete entry | v: reveal | t: copy 2FA code | /: search | f: jump to name | g/G/PgUp/PgDn: move | n: namespaces | l: message log | A: audit | I: import | B: backup | E: plaintext export | q/Ctrl+c: quit | p: export secret key"
             }
             ActivePage::CreateNewPasswordName => "Ctrl+c: cancel | Enter/Tab: continue",
-            ActivePage::CreateNewPasswordBody => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+d: save",
+            ActivePage::CreateNewPasswordBody => "Ctrl+c: cancel | Shift+Tab: back | Tab: details | Ctrl+r: reveal | Ctrl+d: save",
+            ActivePage::CreateNewPasswordDetails => "Ctrl+c: cancel | Tab/Shift+Tab: next/previous field | Ctrl+r: reveal | Ctrl+d: save",
             ActivePage::EditPasswordName => "Ctrl+c: cancel | Enter/Tab: continue",
-            ActivePage::EditPasswordBody => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+d: save",
+            ActivePage::EditPasswordBody => "Ctrl+c: cancel | Shift+Tab: back | Tab: details | Ctrl+r: reveal | Ctrl+d: save",
+            ActivePage::EditPasswordDetails => "Ctrl+c: cancel | Tab/Shift+Tab: next/previous field | Ctrl+r: reveal | Ctrl+d: save",
             ActivePage::SearchPasswordsList => {
-                "a: create new | e: edit entry | d: delete entry | Ctrl+c/Esc: back"
+                "a: create new | N: new note | e: edit entry | d: delete entry | t: copy 2FA code | E: export results in plain text | Ctrl+c/Esc: back"
             }
             ActivePage::SearchPasswordsListName => "Ctrl+c: cancel | Enter/Tab: continue",
             ActivePage::ExportPgpLocation => "Ctrl+c: cancel | Enter: continue",
-            ActivePage::ExportPgpMasterPassword => "Ctrl+c: cancel | Shift+Tab: back | Enter: export",
+            ActivePage::ExportPgpMasterPassword => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+r: reveal | Enter: export",
+            ActivePage::NamespacesList => {
+                "Enter: switch | a: create new | r: rename | d: delete | s: set default | o: hide/show file names of the active one | Esc: back"
+            }
+            ActivePage::CreateNamespaceName => "Ctrl+c: cancel | Enter/Tab: continue",
+            ActivePage::CreateNamespacePassphrase => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+r: reveal | Enter: continue",
+            ActivePage::CreateNamespacePassphraseConfirm => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+r: reveal | Enter: create",
+            ActivePage::RenameNamespace => "Ctrl+c: cancel | Enter: rename",
+            ActivePage::DeleteNamespace => "Ctrl+c: cancel | Enter: delete",
+            ActivePage::UnlockNamespace => "Ctrl+c: cancel | Ctrl+r: reveal | Enter: unlock",
+            ActivePage::MessageLog => "Up/Down/PgUp/PgDn: scroll | Esc: back",
+            ActivePage::Audit => "Up/Down/PgUp/PgDn: scroll | r: run again | Esc: back",
+            ActivePage::BackupLocation => "Ctrl+c: cancel | Enter: continue",
+            ActivePage::BackupPassword => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+r: reveal | Enter: continue",
+            ActivePage::BackupPasswordConfirm => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+r: reveal | Enter: write backup",
+            ActivePage::PlainExportLocation => "Ctrl+c: cancel | Enter: continue",
+            ActivePage::PlainExportPassphrase => "Ctrl+c: cancel | Shift+Tab: back | Ctrl+r: reveal | Enter: export",
+            ActivePage::ImportLocation => "Ctrl+c: cancel | Enter: preview",
+            ActivePage::ImportPreview => "Up/Down/PgUp/PgDn: scroll | c: change conflict policy | Enter: import | Esc: cancel",
         };
         let block = Paragraph::new(message).block(get_bordered_block().title("Hotkeys"));
         tui::widgets::Widget::render(block, area, buf);
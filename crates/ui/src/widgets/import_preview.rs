@@ -0,0 +1,83 @@
+use shared::{import::ImportPreview, password::EntryKind};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Paragraph, Widget},
+};
+
+use crate::components::get_bordered_block;
+
+pub struct ImportPreviewView<'b> {
+    preview: &'b ImportPreview,
+    scroll: usize,
+}
+
+impl<'b> ImportPreviewView<'b> {
+    pub fn new(preview: &'b ImportPreview, scroll: usize) -> Self {
+        Self { preview, scroll }
+    }
+}
+
+fn title(text: String) -> Spans<'static> {
+    Spans::from(Span::styled(
+        text,
+        Style::default().add_modifier(Modifier::BOLD),
+    ))
+}
+
+impl<'b> Widget for ImportPreviewView<'b> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let summary_color = match self.preview.conflicts() {
+            0 => Color::Green,
+            _ => Color::Yellow,
+        };
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                self.preview.summary(),
+                Style::default()
+                    .fg(summary_color)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Spans::default(),
+            title("Field mapping".to_string()),
+        ];
+        lines.extend(self.preview.mapping.iter().map(|(from, to)| {
+            let style = match to.as_str() {
+                "ignored" => Style::default().fg(Color::DarkGray),
+                _ => Style::default(),
+            };
+            Spans::from(Span::styled(format!("  {} -> {}", from, to), style))
+        }));
+        lines.push(Spans::default());
+
+        lines.push(title(format!("Entries ({})", self.preview.entries.len())));
+        lines.extend(self.preview.entries.iter().map(|entry| {
+            let kind = match entry.kind {
+                EntryKind::Password => "",
+                EntryKind::Note => " [note]",
+            };
+            let style = match entry.conflict {
+                true => Style::default().fg(Color::Yellow),
+                false => Style::default(),
+            };
+            Spans::from(vec![
+                Span::raw(format!("  {}{}: ", entry.name, kind)),
+                Span::styled(entry.action.clone(), style),
+            ])
+        }));
+        if !self.preview.skipped.is_empty() {
+            lines.push(Spans::default());
+            lines.push(title(format!("Skipped ({})", self.preview.skipped.len())));
+            lines.extend(self.preview.skipped.iter().map(|(name, reason)| {
+                Spans::from(Span::styled(
+                    format!("  {}: {}", name, reason),
+                    Style::default().fg(Color::DarkGray),
+                ))
+            }));
+        }
+        Paragraph::new(lines)
+            .block(get_bordered_block().title("Import preview"))
+            .scroll((self.scroll as u16, 0))
+            .render(area, buf);
+    }
+}
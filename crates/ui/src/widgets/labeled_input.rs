@@ -1,29 +1,89 @@
+use shared::input::TextInput;
 use tui::{
-    style::Style,
+    style::{Modifier, Style},
+    text::{Span, Spans},
     widgets::{Paragraph, Widget},
 };
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use crate::components::get_bordered_block;
 
-pub struct LabeledInput {
+pub struct LabeledInput<'a> {
     label: String,
-    text: String,
+    input: &'a TextInput,
     block_style: Option<Style>,
+    masked: bool,
 }
 
-impl LabeledInput {
-    pub fn new(text: String, label: String, block_style: Option<Style>) -> Self {
+const MASK: &str = "\u{2022}";
+
+impl<'a> LabeledInput<'a> {
+    /// The input is treated as focused, and draws its cursor, when no block style is given.
+    pub fn new(input: &'a TextInput, label: String, block_style: Option<Style>) -> Self {
         Self {
-            text,
+            input,
             label,
             block_style,
+            masked: false,
         }
     }
+
+    /// Draws a bullet for every character instead of the text.
+    pub fn masked(mut self, masked: bool) -> Self {
+        self.masked = masked;
+        self
+    }
+
+    /// First grapheme to draw so that the cursor stays inside `width` columns.
+    fn scroll_offset(graphemes: &[&str], cursor: usize, width: usize) -> usize {
+        let mut offset = cursor.min(graphemes.len());
+        // One column is kept for the cursor itself
+        let mut used = 1;
+        while offset > 0 {
+            let grapheme_width = graphemes[offset - 1].width();
+            if used + grapheme_width > width {
+                break;
+            }
+            used += grapheme_width;
+            offset -= 1;
+        }
+        offset
+    }
 }
 
-impl Widget for LabeledInput {
+impl<'a> Widget for LabeledInput<'a> {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
-        let block = Paragraph::new(self.text).block(
+        let focused = self.block_style.is_none();
+        let graphemes: Vec<&str> = if self.masked {
+            vec![MASK; self.input.text().graphemes(true).count()]
+        } else {
+            self.input.text().graphemes(true).collect()
+        };
+        let cursor = self.input.cursor();
+        let selection = self.input.selection();
+        let width = area.width.saturating_sub(2) as usize;
+        let offset = Self::scroll_offset(&graphemes, cursor, width);
+
+        let highlighted = Style::default().add_modifier(Modifier::REVERSED);
+        let mut spans: Vec<Span> = graphemes
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .map(|(i, grapheme)| {
+                let selected = selection.is_some_and(|(start, end)| i >= start && i < end);
+                if focused && (selected || i == cursor) {
+                    Span::styled(*grapheme, highlighted)
+                } else {
+                    Span::raw(*grapheme)
+                }
+            })
+            .collect();
+        if focused && cursor >= graphemes.len() {
+            spans.push(Span::styled(" ", highlighted));
+        }
+
+        let block = Paragraph::new(Spans::from(spans)).block(
             get_bordered_block()
                 .title(self.label)
                 .style(self.block_style.unwrap_or_default()),
@@ -0,0 +1,45 @@
+use shared::notification::{Notification, NotificationLevel};
+use tui::{
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Paragraph, Widget},
+};
+
+use super::status_bar::level_style;
+use crate::components::get_bordered_block;
+
+pub struct MessageLog<'b> {
+    messages: &'b [Notification],
+    scroll: usize,
+}
+
+impl<'b> MessageLog<'b> {
+    pub fn new(messages: &'b [Notification], scroll: usize) -> Self {
+        Self { messages, scroll }
+    }
+}
+
+impl<'b> Widget for MessageLog<'b> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let lines: Vec<Spans> = self
+            .messages
+            .iter()
+            .map(|message| {
+                let level = match message.level {
+                    NotificationLevel::Success => "ok   ",
+                    NotificationLevel::Warning => "warn ",
+                    NotificationLevel::Error => "error",
+                };
+                Spans::from(vec![
+                    Span::raw(format!("{} ", message.created_at.format("%H:%M:%S"))),
+                    Span::styled(level, level_style(message.level)),
+                    Span::styled(format!(" {}", message.message), Style::default()),
+                ])
+            })
+            .collect();
+        Paragraph::new(lines)
+            .block(get_bordered_block().title("Message log"))
+            .scroll((self.scroll as u16, 0))
+            .render(area, buf);
+    }
+}
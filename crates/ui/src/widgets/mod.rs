@@ -1,6 +1,22 @@
-mod passwords_list;
+mod audit_report;
+mod entry_details;
+mod error_popup;
 mod help_tab;
+mod import_preview;
 mod labeled_input;
-pub use passwords_list::PasswordsList;
+mod message_log;
+mod namespaces_list;
+mod note_editor;
+mod passwords_list;
+mod status_bar;
+pub use audit_report::AuditReportView;
+pub use entry_details::EntryDetails;
+pub use error_popup::ErrorPopup;
 pub use help_tab::HelpTab;
+pub use import_preview::ImportPreviewView;
 pub use labeled_input::LabeledInput;
+pub use message_log::MessageLog;
+pub use namespaces_list::NamespacesList;
+pub use note_editor::NoteEditor;
+pub use passwords_list::PasswordsList;
+pub use status_bar::StatusBar;
@@ -0,0 +1,80 @@
+use tui::{
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Cell, Row, Table, TableState, Widget},
+};
+
+use crate::components::get_bordered_block;
+
+pub struct NamespacesList<'b> {
+    namespaces: &'b Vec<String>,
+    active: &'b str,
+    default: &'b str,
+    selected: usize,
+    error: Option<&'b String>,
+}
+
+impl<'b> NamespacesList<'b> {
+    pub fn new(
+        namespaces: &'b Vec<String>,
+        active: &'b str,
+        default: &'b str,
+        selected: usize,
+        error: Option<&'b String>,
+    ) -> Self {
+        Self {
+            namespaces,
+            active,
+            default,
+            selected,
+            error,
+        }
+    }
+}
+
+impl<'b> Widget for NamespacesList<'b> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let items: Vec<_> = self
+            .namespaces
+            .iter()
+            .map(|name| {
+                let mut marks = vec![];
+                if name == self.active {
+                    marks.push("active");
+                }
+                if name == self.default {
+                    marks.push("default");
+                }
+                Row::new(vec![
+                    Cell::from(Span::raw(name)),
+                    Cell::from(Span::raw(marks.join(", "))),
+                ])
+            })
+            .collect();
+
+        let mut title = "Namespaces".to_string();
+        if let Some(error) = self.error {
+            title.push_str(&format!(" ({})", error));
+        }
+        let table = Table::new(items)
+            .header(Row::new(vec![
+                Cell::from(Span::styled(
+                    "Name",
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+                Cell::from(Span::styled(
+                    "",
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+            ]))
+            .block(get_bordered_block().title(title))
+            .widths(&[Constraint::Percentage(30), Constraint::Percentage(20)])
+            .highlight_style(Style::default().bg(Color::White).fg(Color::Black))
+            .highlight_symbol("> ");
+
+        let mut state = TableState::default();
+        state.select(Some(self.selected));
+        tui::widgets::StatefulWidget::render(table, area, buf, &mut state);
+    }
+}
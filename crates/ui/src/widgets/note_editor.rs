@@ -0,0 +1,153 @@
+use shared::input::TextInput;
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Paragraph, Widget},
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::components::get_bordered_block;
+
+/// Screen row of a note: the line number is only set on the first row of a line.
+struct Row<'t> {
+    line_number: Option<usize>,
+    graphemes: Vec<(usize, &'t str)>,
+    /// Grapheme index right after the last grapheme of the row
+    end: usize,
+}
+
+/// Multiline editor with line numbers and soft wrap, scrolled so the cursor stays visible.
+pub struct NoteEditor<'a> {
+    label: String,
+    input: &'a TextInput,
+    block_style: Option<Style>,
+}
+
+impl<'a> NoteEditor<'a> {
+    /// The editor is treated as focused, and draws its cursor, when no block style is given.
+    pub fn new(input: &'a TextInput, label: String, block_style: Option<Style>) -> Self {
+        Self {
+            input,
+            label,
+            block_style,
+        }
+    }
+
+    fn wrap(text: &str, width: usize) -> Vec<Row<'_>> {
+        let mut rows = vec![Row {
+            line_number: Some(1),
+            graphemes: vec![],
+            end: 0,
+        }];
+        let mut row_width = 0;
+        for (i, grapheme) in text.graphemes(true).enumerate() {
+            if grapheme == "\n" {
+                let line_number = rows.iter().filter(|r| r.line_number.is_some()).count() + 1;
+                rows.push(Row {
+                    line_number: Some(line_number),
+                    graphemes: vec![],
+                    end: i + 1,
+                });
+                row_width = 0;
+                continue;
+            }
+            let grapheme_width = grapheme.width();
+            if row_width + grapheme_width > width && row_width > 0 {
+                rows.push(Row {
+                    line_number: None,
+                    graphemes: vec![],
+                    end: i,
+                });
+                row_width = 0;
+            }
+            let row = rows.last_mut().expect("there is always a row");
+            row.graphemes.push((i, grapheme));
+            row.end = i + 1;
+            row_width += grapheme_width;
+        }
+        rows
+    }
+
+    /// Row holding the cursor. A cursor at a wrap point belongs to the next row.
+    fn cursor_row(rows: &[Row], cursor: usize) -> usize {
+        rows.iter()
+            .rposition(|row| {
+                row.graphemes
+                    .first()
+                    .map_or(row.end <= cursor, |(i, _)| *i <= cursor)
+            })
+            .unwrap_or(0)
+    }
+}
+
+impl<'a> Widget for NoteEditor<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let block: Block = get_bordered_block()
+            .title(self.label)
+            .style(self.block_style.unwrap_or_default());
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let focused = self.block_style.is_none();
+        let text = self.input.text();
+        let line_count = text.matches('\n').count() + 1;
+        let gutter_width = line_count.to_string().len() + 1;
+        let width = (inner.width as usize).saturating_sub(gutter_width).max(1);
+        let height = (inner.height as usize).max(1);
+
+        let rows = Self::wrap(text, width);
+        let cursor = self.input.cursor();
+        let selection = self.input.selection();
+        let cursor_row = Self::cursor_row(&rows, cursor);
+        let scroll = cursor_row.saturating_sub(height - 1);
+
+        let gutter_style = Style::default().fg(Color::DarkGray);
+        let highlighted = Style::default().add_modifier(Modifier::REVERSED);
+        let lines: Vec<Spans> = rows
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(height)
+            .map(|(row_index, row)| {
+                let gutter = match row.line_number {
+                    Some(number) => format!("{:>w$} ", number, w = gutter_width - 1),
+                    None => " ".repeat(gutter_width),
+                };
+                let mut spans = vec![Span::styled(gutter, gutter_style)];
+                spans.extend(row.graphemes.iter().map(|(i, grapheme)| {
+                    let selected = selection.is_some_and(|(start, end)| *i >= start && *i < end);
+                    if focused && (selected || *i == cursor) {
+                        Span::styled(*grapheme, highlighted)
+                    } else {
+                        Span::raw(*grapheme)
+                    }
+                }));
+                // Cursor after the last grapheme of its row
+                if focused && row_index == cursor_row && cursor >= row.end {
+                    spans.push(Span::styled(" ", highlighted));
+                }
+                Spans::from(spans)
+            })
+            .collect();
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NoteEditor;
+
+    #[test]
+    fn wraps_long_lines() {
+        let rows = NoteEditor::wrap("abcdef\ngh", 4);
+        let numbers: Vec<Option<usize>> = rows.iter().map(|r| r.line_number).collect();
+        assert_eq!(numbers, vec![Some(1), None, Some(2)]);
+        assert_eq!(rows[1].graphemes, vec![(4, "e"), (5, "f")]);
+        assert_eq!(NoteEditor::cursor_row(&rows, 4), 1);
+        assert_eq!(NoteEditor::cursor_row(&rows, 6), 1);
+        assert_eq!(NoteEditor::cursor_row(&rows, 7), 2);
+        assert_eq!(NoteEditor::cursor_row(&rows, 9), 2);
+    }
+}
@@ -2,8 +2,8 @@ use shared::password::Password;
 use tui::{
     layout::Constraint,
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Cell, Row, Table, TableState, Widget},
+    text::{Span, Spans},
+    widgets::{Cell, Row, StatefulWidget, Table, TableState},
 };
 
 use crate::components::get_bordered_block;
@@ -12,6 +12,9 @@ pub struct PasswordsList<'b> {
     passwords_list: &'b Vec<Password>,
     selected: usize,
     block_style: Option<Style>,
+    namespace: Option<&'b str>,
+    jump: Option<&'b str>,
+    highlights: Option<&'b [Vec<usize>]>,
 }
 
 impl<'b> PasswordsList<'b> {
@@ -24,34 +27,121 @@ impl<'b> PasswordsList<'b> {
             passwords_list,
             selected,
             block_style,
+            namespace: None,
+            jump: None,
+            highlights: None,
         }
     }
+
+    pub fn namespace(mut self, namespace: &'b str) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    fn title(&self) -> String {
+        let mut title = match self.namespace {
+            Some(namespace) => format!("Passwords [{}]", namespace),
+            None => "Passwords".to_string(),
+        };
+        if let Some(jump) = self.jump {
+            title.push_str(&format!(" jump: {}", jump));
+        }
+        title
+    }
+
+    /// Highlights matched characters, one list of char positions per row.
+    pub fn highlights(mut self, highlights: &'b [Vec<usize>]) -> Self {
+        self.highlights = Some(highlights);
+        self
+    }
+
+    fn name_cell(name: &'b str, positions: Option<&[usize]>) -> Cell<'b> {
+        let positions = match positions {
+            Some(positions) if !positions.is_empty() => positions,
+            _ => return Cell::from(Span::raw(name)),
+        };
+        let matched = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let spans: Vec<Span> = name
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if positions.binary_search(&i).is_ok() {
+                    Span::styled(c.to_string(), matched)
+                } else {
+                    Span::raw(c.to_string())
+                }
+            })
+            .collect();
+        Cell::from(Spans::from(spans))
+    }
+
+    fn status_cell(password: &Password) -> Cell<'b> {
+        match password.breached {
+            Some(count) => Cell::from(Span::styled(
+                format!("breached ({})", count),
+                Style::default().fg(Color::Red),
+            )),
+            None => Cell::from(""),
+        }
+    }
+
+    /// Shows the type-ahead prefix typed so far in the title.
+    pub fn jump(mut self, jump: Option<&'b str>) -> Self {
+        self.jump = jump;
+        self
+    }
 }
 
-impl<'b> Widget for PasswordsList<'b> {
-    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+/// Rendered with a [`TableState`] kept between draws, so the viewport only
+/// scrolls when the selection leaves it.
+impl<'b> StatefulWidget for PasswordsList<'b> {
+    type State = TableState;
+
+    fn render(
+        self,
+        area: tui::layout::Rect,
+        buf: &mut tui::buffer::Buffer,
+        state: &mut TableState,
+    ) {
         let items: Vec<_> = self
             .passwords_list
             .iter()
-            .map(|pass| Row::new(vec![Cell::from(Span::raw(&pass.name))]))
+            .enumerate()
+            .map(|(i, pass)| {
+                let positions = self
+                    .highlights
+                    .and_then(|highlights| highlights.get(i))
+                    .map(Vec::as_slice);
+                Row::new(vec![
+                    Self::name_cell(&pass.name, positions),
+                    Self::status_cell(pass),
+                ])
+            })
             .collect();
 
         let pass_detail = Table::new(items)
-            .header(Row::new(vec![Cell::from(Span::styled(
-                "Name",
-                Style::default().add_modifier(Modifier::BOLD),
-            ))]))
+            .header(Row::new(["Name", "Status"].map(|title| {
+                Cell::from(Span::styled(
+                    title,
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+            })))
             .block(
                 get_bordered_block()
-                    .title("Passwords")
+                    .title(self.title())
                     .style(self.block_style.unwrap_or_default()),
             )
             .widths(&[Constraint::Percentage(30), Constraint::Percentage(20)])
             .highlight_style(Style::default().bg(Color::White).fg(Color::Black))
             .highlight_symbol("> ");
 
-        let mut state = TableState::default();
-        state.select(Some(self.selected));
-        tui::widgets::StatefulWidget::render(pass_detail, area, buf, &mut state);
+        state.select(if self.passwords_list.is_empty() {
+            None
+        } else {
+            Some(self.selected.min(self.passwords_list.len() - 1))
+        });
+        StatefulWidget::render(pass_detail, area, buf, state);
     }
 }
@@ -0,0 +1,53 @@
+use shared::notification::{Notification, NotificationLevel};
+use tui::{
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Paragraph, Widget},
+};
+
+pub struct StatusBar<'b> {
+    notification: Option<&'b Notification>,
+    pending: usize,
+}
+
+impl<'b> StatusBar<'b> {
+    pub fn new(notification: Option<&'b Notification>, pending: usize) -> Self {
+        Self {
+            notification,
+            pending,
+        }
+    }
+}
+
+pub fn level_style(level: NotificationLevel) -> Style {
+    match level {
+        NotificationLevel::Success => Style::default().fg(Color::Green),
+        NotificationLevel::Warning => Style::default().fg(Color::Yellow),
+        NotificationLevel::Error => Style::default().fg(Color::Red),
+    }
+}
+
+impl<'b> Widget for StatusBar<'b> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let line = match self.notification {
+            Some(notification) => {
+                let mut spans = vec![Span::styled(
+                    format!(" {}", notification.message),
+                    level_style(notification.level),
+                )];
+                if self.pending > 1 {
+                    spans.push(Span::styled(
+                        format!(" (+{})", self.pending - 1),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                Spans::from(spans)
+            }
+            None => Spans::from(Span::styled(
+                " l: message log",
+                Style::default().fg(Color::DarkGray),
+            )),
+        };
+        Paragraph::new(line).render(area, buf);
+    }
+}